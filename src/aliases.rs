@@ -0,0 +1,136 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{commands::git_command, logging::warning};
+
+#[cfg(test)]
+mod tests;
+
+// git runs `git <name>` as `git-<name>` when that is on the PATH, so the binary also answers to
+// these names and runs the command that follows them: `git wt add x` is `git-tools worktree add x`
+pub const ALIASES: [(&str, &[&str]); 2] = [
+    ("clean-merged", &["clean-merged-branches"]),
+    ("wt", &["worktree"]),
+];
+
+// `args` without the program name, preceded by the alias's command when the binary was started
+// through one of the aliases
+pub fn arguments(program: &str, args: Vec<String>) -> Vec<String> {
+    let name = Path::new(program)
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let command = name
+        .strip_prefix("git-")
+        .and_then(|alias| ALIASES.iter().find(|(name, _)| *name == alias));
+
+    match command {
+        Some((_, words)) => words
+            .iter()
+            .map(|word| word.to_string())
+            .chain(args)
+            .collect(),
+        None => args,
+    }
+}
+
+#[cfg(unix)]
+fn symlink(source: &Path, destination: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(source, destination)
+}
+
+#[cfg(windows)]
+fn symlink(source: &Path, destination: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(source, destination)
+}
+
+// Links `git-<alias>` in `bin_dir` to `executable`. Nothing is created when any of the names is
+// taken by something else.
+pub fn install_symlinks(executable: &Path, bin_dir: &Path) -> Result<Vec<String>, String> {
+    let links = ALIASES
+        .iter()
+        .map(|(name, _)| bin_dir.join(format!("git-{}", name)))
+        .collect::<Vec<PathBuf>>();
+    let installed = |link: &PathBuf| fs::read_link(link).is_ok_and(|target| target == executable);
+
+    if let Some(taken) = links
+        .iter()
+        .find(|link| link.symlink_metadata().is_ok() && !installed(link))
+    {
+        return Err(format!(
+            "{:?} already exists and doesn't link to {:?}, remove it first",
+            taken, executable
+        ));
+    }
+
+    let on_path = env::var_os("PATH")
+        .is_some_and(|path| env::split_paths(&path).any(|directory| directory == bin_dir));
+    if !on_path {
+        warning!(
+            "{:?} isn't on the PATH, git won't find the aliases until it is",
+            bin_dir
+        );
+    }
+
+    links
+        .iter()
+        .map(|link| match installed(link) {
+            true => Ok(format!("{:?} is already installed", link)),
+            false => symlink(executable, link)
+                .map(|_| format!("Linked {:?} to {:?}", link, executable))
+                .map_err(|err| format!("Couldn't create {:?}: {}", link, err)),
+        })
+        .collect()
+}
+
+// Shell aliases run in the top directory of the worktree, so they change back into the directory
+// they were started in first
+fn git_alias_value(executable: &Path, words: &[&str]) -> String {
+    format!(
+        "!cd \"${{GIT_PREFIX:-.}}\" && '{}' {}",
+        executable.to_string_lossy().replace('\'', r"'\''"),
+        words.join(" ")
+    )
+}
+
+// Writes an `alias.<alias>` entry for every alias to the git config file that `scope` selects,
+// e.g. `--global`. Nothing is written when any of them is set to something else.
+pub fn install_git_aliases(
+    executable: &Path,
+    scope: &[&str],
+    cwd: &Path,
+) -> Result<Vec<String>, String> {
+    let entries = ALIASES
+        .iter()
+        .map(|(name, words)| {
+            let key = format!("alias.{}", name);
+            let current = git_command([&["config"], scope, &["--get", &key]].concat(), cwd)
+                .ok()
+                .and_then(|result| result.stdout_lines().into_iter().next());
+
+            (key, git_alias_value(executable, words), current)
+        })
+        .collect::<Vec<(String, String, Option<String>)>>();
+
+    if let Some((key, _, Some(current))) = entries
+        .iter()
+        .find(|(_, value, current)| current.as_ref().is_some_and(|current| current != value))
+    {
+        return Err(format!(
+            "{} is already set to '{}', remove it first",
+            key, current
+        ));
+    }
+
+    entries
+        .iter()
+        .map(|(key, value, current)| match current {
+            Some(_) => Ok(format!("{} is already installed", key)),
+            None => git_command([&["config"], scope, &[key, value]].concat(), cwd)
+                .map(|_| format!("Set {} to '{}'", key, value))
+                .map_err(|result| result.error_message()),
+        })
+        .collect()
+}
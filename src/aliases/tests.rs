@@ -0,0 +1,101 @@
+#[cfg(test)]
+use std::{fs, path::Path};
+
+#[cfg(test)]
+use super::{arguments, install_git_aliases, install_symlinks};
+
+#[cfg(test)]
+use crate::{commands::git_command, test_helpers::run_test};
+
+#[cfg(test)]
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn test_aliases_run_their_command() {
+    assert_eq!(
+        arguments("/usr/local/bin/git-wt", args(&["add", "x"])),
+        args(&["worktree", "add", "x"])
+    );
+    assert_eq!(
+        arguments("git-clean-merged", args(&["-v"])),
+        args(&["clean-merged-branches", "-v"])
+    );
+    assert_eq!(
+        arguments("/usr/local/bin/git-tools", args(&["sync"])),
+        args(&["sync"])
+    );
+    assert_eq!(arguments("git-unknown", args(&["sync"])), args(&["sync"]));
+}
+
+#[test]
+fn test_installs_symlinks_but_never_replaces_other_files() {
+    run_test(
+        "test_installs_symlinks_but_never_replaces_other_files",
+        "clean_repo",
+        |repo| {
+            let bin_dir = repo.root().join("bin");
+            let executable = Path::new("/opt/git-tools/git-tools");
+            fs::create_dir(&bin_dir).unwrap();
+
+            assert_eq!(
+                install_symlinks(executable, &bin_dir).map(|lines| lines.len()),
+                Ok(2)
+            );
+            assert_eq!(fs::read_link(bin_dir.join("git-wt")).unwrap(), executable);
+            assert_eq!(
+                install_symlinks(executable, &bin_dir),
+                Ok(vec![
+                    format!(
+                        "{:?} is already installed",
+                        bin_dir.join("git-clean-merged")
+                    ),
+                    format!("{:?} is already installed", bin_dir.join("git-wt")),
+                ])
+            );
+
+            // another program with the same name is left alone
+            fs::remove_file(bin_dir.join("git-clean-merged")).unwrap();
+            fs::remove_file(bin_dir.join("git-wt")).unwrap();
+            fs::write(bin_dir.join("git-wt"), "#!/bin/sh\n").unwrap();
+
+            assert!(install_symlinks(executable, &bin_dir).is_err());
+            assert!(!bin_dir.join("git-clean-merged").exists());
+        },
+    );
+}
+
+#[test]
+fn test_writes_git_aliases_unless_they_are_taken() {
+    run_test(
+        "test_writes_git_aliases_unless_they_are_taken",
+        "clean_repo",
+        |repo| {
+            let config = repo.root().join("aliases.gitconfig");
+            let config = config.to_str().unwrap();
+            let scope = ["--file", config];
+            let executable = Path::new("/opt/git tools/git-tools");
+            let get = |key: &str| {
+                git_command(vec!["config", "--file", config, "--get", key], repo.root())
+                    .map(|result| result.stdout_lines())
+                    .unwrap_or_default()
+            };
+
+            install_git_aliases(executable, &scope, repo.root()).unwrap();
+            assert_eq!(
+                get("alias.wt"),
+                vec!["!cd \"${GIT_PREFIX:-.}\" && '/opt/git tools/git-tools' worktree"]
+            );
+            assert!(install_git_aliases(executable, &scope, repo.root()).is_ok());
+
+            git_command(
+                vec!["config", "--file", config, "alias.clean-merged", "!echo"],
+                repo.root(),
+            )
+            .unwrap();
+            assert!(install_git_aliases(Path::new("/other"), &scope, repo.root()).is_err());
+            assert_eq!(get("alias.clean-merged"), vec!["!echo"]);
+        },
+    );
+}
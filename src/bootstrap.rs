@@ -0,0 +1,169 @@
+use std::{fs, io, path::Path};
+
+use crate::{
+    config::Config,
+    glob::find_matches,
+    hooks::{run_hook, HookContext},
+    logging::debug,
+    repository::BareRepository,
+    worktree::Worktree,
+};
+
+#[cfg(test)]
+mod tests;
+
+// Read from the configuration (see `config`), e.g. git config, where every key can be given
+// multiple times:
+//   git config --add git-tools.bootstrap.copy '.env*'
+//   git config --add git-tools.bootstrap.symlink node_modules
+//   git config --add git-tools.bootstrap.run 'npm run setup'
+// Paths and globs are relative to the main branch's worktree.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BootstrapConfig {
+    pub copy: Vec<String>,
+    pub symlink: Vec<String>,
+    pub run: Vec<String>,
+}
+
+impl From<&Config> for BootstrapConfig {
+    fn from(config: &Config) -> Self {
+        Self {
+            copy: config.bootstrap_copy.value.clone(),
+            symlink: config.bootstrap_symlink.value.clone(),
+            run: config.bootstrap_run.value.clone(),
+        }
+    }
+}
+
+impl BootstrapConfig {
+    pub fn is_empty(&self) -> bool {
+        self.copy.is_empty() && self.symlink.is_empty() && self.run.is_empty()
+    }
+}
+
+// Prepares a freshly created worktree and returns a description of every step that failed. A
+// failed step doesn't stop the remaining ones, but setup commands only run once every file is in
+// place.
+pub fn bootstrap_worktree(
+    repository: &BareRepository,
+    worktree: &Worktree,
+    config: &BootstrapConfig,
+) -> Vec<String> {
+    let mut failures: Vec<String> = vec![];
+
+    if config.is_empty() {
+        return failures;
+    }
+
+    let target = Path::new(&worktree.path);
+    let main_worktree = repository.main_worktree();
+
+    if !config.copy.is_empty() || !config.symlink.is_empty() {
+        match &main_worktree {
+            Some(source) => {
+                let source = Path::new(&source.path);
+
+                for pattern in &config.copy {
+                    failures.extend(place_matches(source, target, pattern, copy_recursively));
+                }
+
+                for pattern in &config.symlink {
+                    failures.extend(place_matches(source, target, pattern, symlink));
+                }
+            }
+            None => failures.push(format!(
+                "Files weren't copied or linked because no worktree holds the '{}' branch",
+                repository.main_branch_name()
+            )),
+        }
+    }
+
+    let context = HookContext {
+        worktree_path: &worktree.path,
+        branch: &worktree.name,
+        root: repository.root(),
+    };
+
+    for command in &config.run {
+        if let Err(msg) = run_hook(command, target, &context) {
+            failures.push(msg);
+        }
+    }
+
+    failures
+}
+
+fn place_matches(
+    source: &Path,
+    target: &Path,
+    pattern: &str,
+    place: fn(&Path, &Path) -> io::Result<()>,
+) -> Vec<String> {
+    let matches = find_matches(source, pattern);
+    let mut failures: Vec<String> = vec![];
+
+    if matches.is_empty() {
+        debug!("'{}' didn't match anything in {:?}", pattern, source);
+    }
+
+    for relative in matches {
+        let destination = target.join(&relative);
+
+        // never clobber files that are part of the checkout
+        if destination.symlink_metadata().is_ok() {
+            debug!("skipping {:?}: it already exists in the worktree", relative);
+            continue;
+        }
+
+        let result = match destination.parent() {
+            Some(parent) => fs::create_dir_all(parent),
+            None => Ok(()),
+        }
+        .and_then(|_| place(&source.join(&relative), &destination));
+
+        match result {
+            Ok(_) => eprintln!("Prepared {}", relative.to_string_lossy()),
+            Err(err) => failures.push(format!(
+                "Couldn't prepare {}: {}",
+                relative.to_string_lossy(),
+                err
+            )),
+        }
+    }
+
+    failures
+}
+
+fn copy_recursively(source: &Path, destination: &Path) -> io::Result<()> {
+    let metadata = source.symlink_metadata()?;
+
+    if metadata.file_type().is_symlink() {
+        return symlink(&fs::read_link(source)?, destination);
+    }
+
+    if metadata.is_dir() {
+        fs::create_dir_all(destination)?;
+
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+
+        return Ok(());
+    }
+
+    fs::copy(source, destination).map(|_| ())
+}
+
+#[cfg(unix)]
+fn symlink(source: &Path, destination: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(source, destination)
+}
+
+#[cfg(windows)]
+fn symlink(source: &Path, destination: &Path) -> io::Result<()> {
+    match source.is_dir() {
+        true => std::os::windows::fs::symlink_dir(source, destination),
+        false => std::os::windows::fs::symlink_file(source, destination),
+    }
+}
@@ -0,0 +1,165 @@
+#[cfg(test)]
+use std::fs;
+
+#[cfg(test)]
+use crate::{
+    config::Config,
+    repository::{BareRepository, Repository},
+    test_helpers::run_test,
+    test_setup::BARE_REPO_NAME,
+    worktree::{NewWorktree, Worktree},
+    worktree_path::DEFAULT_PATH_TEMPLATE,
+};
+
+#[cfg(test)]
+use super::{bootstrap_worktree, BootstrapConfig};
+
+#[cfg(test)]
+fn bare(repo: &Repository) -> &BareRepository {
+    match repo {
+        Repository::Bare(bare) => bare,
+        _ => panic!("repo is not bare"),
+    }
+}
+
+#[cfg(test)]
+fn create_worktree<'a>(repo: &'a BareRepository, branch: &str) -> Worktree<'a> {
+    Worktree::create(
+        repo,
+        &NewWorktree {
+            branch: branch.to_string(),
+            start_point: None,
+            path_template: DEFAULT_PATH_TEMPLATE.to_string(),
+        },
+    )
+    .expect("Couldn't create worktree")
+}
+
+#[test]
+fn test_bootstrap_copies_and_links_files_from_the_main_worktree() {
+    run_test(
+        "test_bootstrap_copies_and_links_files_from_the_main_worktree",
+        BARE_REPO_NAME,
+        |repo| {
+            let main = repo.root().join("main");
+            fs::write(main.join(".env"), "SECRET=1").unwrap();
+            fs::write(main.join(".env.local"), "LOCAL=1").unwrap();
+            fs::create_dir_all(main.join("node_modules/left-pad")).unwrap();
+
+            let worktree = create_worktree(bare(&repo), "some-work");
+            let config = BootstrapConfig {
+                copy: vec![".env*".to_string()],
+                symlink: vec!["node_modules".to_string()],
+                run: vec![],
+            };
+            let failures = bootstrap_worktree(bare(&repo), &worktree, &config);
+            let target = repo.root().join("some-work");
+
+            assert!(failures.is_empty(), "{:?}", failures);
+            assert_eq!("SECRET=1", fs::read_to_string(target.join(".env")).unwrap());
+            assert_eq!(
+                "LOCAL=1",
+                fs::read_to_string(target.join(".env.local")).unwrap()
+            );
+            assert!(fs::symlink_metadata(target.join("node_modules"))
+                .unwrap()
+                .file_type()
+                .is_symlink());
+        },
+    );
+}
+
+#[test]
+fn test_bootstrap_does_not_overwrite_checked_out_files() {
+    run_test(
+        "test_bootstrap_does_not_overwrite_checked_out_files",
+        BARE_REPO_NAME,
+        |repo| {
+            fs::write(repo.root().join("main/README.md"), "changed in main").unwrap();
+
+            let worktree = create_worktree(bare(&repo), "some-work");
+            let config = BootstrapConfig {
+                copy: vec!["README.md".to_string()],
+                symlink: vec![],
+                run: vec![],
+            };
+            let failures = bootstrap_worktree(bare(&repo), &worktree, &config);
+
+            assert!(failures.is_empty(), "{:?}", failures);
+            assert_eq!(
+                "",
+                fs::read_to_string(repo.root().join("some-work/README.md")).unwrap()
+            );
+        },
+    );
+}
+
+#[test]
+fn test_bootstrap_runs_setup_commands_in_the_new_worktree() {
+    run_test(
+        "test_bootstrap_runs_setup_commands_in_the_new_worktree",
+        BARE_REPO_NAME,
+        |repo| {
+            let worktree = create_worktree(bare(&repo), "some-work");
+            let config = BootstrapConfig {
+                copy: vec![],
+                symlink: vec![],
+                run: vec!["echo \"$GIT_TOOLS_BRANCH\" > branch.txt".to_string()],
+            };
+            let failures = bootstrap_worktree(bare(&repo), &worktree, &config);
+
+            assert!(failures.is_empty(), "{:?}", failures);
+            assert_eq!(
+                "some-work\n",
+                fs::read_to_string(repo.root().join("some-work/branch.txt")).unwrap()
+            );
+        },
+    );
+}
+
+#[test]
+fn test_bootstrap_reports_failed_setup_commands_and_keeps_going() {
+    run_test(
+        "test_bootstrap_reports_failed_setup_commands_and_keeps_going",
+        BARE_REPO_NAME,
+        |repo| {
+            let worktree = create_worktree(bare(&repo), "some-work");
+            let config = BootstrapConfig {
+                copy: vec![],
+                symlink: vec![],
+                run: vec!["exit 3".to_string(), "touch ran-anyway".to_string()],
+            };
+            let failures = bootstrap_worktree(bare(&repo), &worktree, &config);
+
+            assert_eq!(vec!["'exit 3' exited with status 3".to_string()], failures);
+            assert!(repo.root().join("some-work/ran-anyway").exists());
+        },
+    );
+}
+
+#[test]
+fn test_bootstrap_config_is_read_from_git_config() {
+    run_test(
+        "test_bootstrap_config_is_read_from_git_config",
+        BARE_REPO_NAME,
+        |repo| {
+            for (key, value) in [
+                ("git-tools.bootstrap.copy", ".env"),
+                ("git-tools.bootstrap.copy", ".env.local"),
+                ("git-tools.bootstrap.run", "make setup"),
+            ] {
+                crate::commands::git_command(vec!["config", "--add", key, value], repo.root())
+                    .expect("Couldn't set git config");
+            }
+
+            assert_eq!(
+                BootstrapConfig {
+                    copy: vec![".env".to_string(), ".env.local".to_string()],
+                    symlink: vec![],
+                    run: vec!["make setup".to_string()],
+                },
+                BootstrapConfig::from(&Config::load(Some(repo.root())))
+            );
+        },
+    );
+}
@@ -0,0 +1,452 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::Path,
+};
+
+use crate::{
+    commands::{git_command, git_command_with_input},
+    json::Json,
+    pool::{jobs, parallel_map},
+    table,
+    worktree_status::{ahead_behind, format_age, format_ahead_behind, worktree_entries},
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeState {
+    Main,
+    // The tip is an ancestor of the main branch
+    Merged,
+    // The changes are on the main branch, but as a different (squashed or rebased) commit
+    Squashed,
+    Unmerged,
+}
+
+impl fmt::Display for MergeState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Main => write!(f, "main"),
+            Self::Merged => write!(f, "merged"),
+            Self::Squashed => write!(f, "squashed"),
+            Self::Unmerged => write!(f, "unmerged"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    // Oldest first
+    Age,
+    Name,
+    Author,
+    // Most commits ahead of (or behind) the main branch first
+    Ahead,
+    Behind,
+}
+
+impl TryFrom<&str> for SortKey {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "age" => Ok(Self::Age),
+            "name" => Ok(Self::Name),
+            "author" => Ok(Self::Author),
+            "ahead" => Ok(Self::Ahead),
+            "behind" => Ok(Self::Behind),
+            _ => Err(format!(
+                "Unknown sort key '{}' (expected age, name, author, ahead or behind)",
+                value
+            )),
+        }
+    }
+}
+
+// Every filter that is set has to match
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Filters {
+    // Merged or squashed
+    pub merged: bool,
+    pub unmerged: bool,
+    pub gone: bool,
+    // Part of the author's name or email, ignoring case
+    pub author: Option<String>,
+    pub older_than_days: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BranchReport {
+    // `feature` for local branches, `origin/feature` for remote ones
+    pub name: String,
+    pub remote: bool,
+    pub head: String,
+    // Unix timestamp and ISO 8601 date of the tip's commit
+    pub last_commit: i64,
+    pub last_commit_date: String,
+    // The author of the tip
+    pub author: String,
+    pub author_email: String,
+    pub upstream: Option<String>,
+    pub upstream_gone: bool,
+    // (ahead, behind) compared to the main branch
+    pub main_ahead_behind: Option<(usize, usize)>,
+    pub merge: MergeState,
+    // The worktree holding the branch, relative to the repository root when it is inside it
+    pub worktree: Option<String>,
+}
+
+const REF_FORMAT: &str = "--format=%(refname)%00%(objectname)%00%(committerdate:unix)%00\
+%(committerdate:iso-strict)%00%(authorname)%00%(authoremail)%00%(upstream:short)%00\
+%(upstream:track)%00%(symref)";
+
+// `refs/heads/x` is the local `x`, `refs/remotes/origin/x` the remote `origin/x`
+fn short_name(refname: &str) -> Option<(String, bool)> {
+    match refname.strip_prefix("refs/heads/") {
+        Some(name) => Some((name.to_string(), false)),
+        None => refname
+            .strip_prefix("refs/remotes/")
+            .map(|name| (name.to_string(), true)),
+    }
+}
+
+// One line of `for-each-ref` with REF_FORMAT, without what needs more git commands
+pub fn parse_ref(line: &str) -> Option<BranchReport> {
+    let (refname, head, timestamp, date, author, email, upstream, track, symref) =
+        match line.split('\0').collect::<Vec<&str>>().as_slice() {
+            [refname, head, timestamp, date, author, email, upstream, track, symref] => (
+                *refname, *head, *timestamp, *date, *author, *email, *upstream, *track, *symref,
+            ),
+            _ => return None,
+        };
+
+    // `origin/HEAD` only points at another remote branch
+    if !symref.is_empty() {
+        return None;
+    }
+
+    let (name, remote) = short_name(refname)?;
+
+    Some(BranchReport {
+        name,
+        remote,
+        head: head.to_string(),
+        last_commit: timestamp.parse().unwrap_or_default(),
+        last_commit_date: date.to_string(),
+        author: author.to_string(),
+        author_email: email
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .to_string(),
+        upstream: Some(upstream.to_string()).filter(|upstream| !upstream.is_empty()),
+        upstream_gone: track == "[gone]",
+        main_ahead_behind: None,
+        merge: MergeState::Unmerged,
+        worktree: None,
+    })
+}
+
+// Squashed: all of the branch's changes since it forked off match a single commit on the main
+// branch. Rebased: each of the branch's commits matches one on the main branch. Changes are
+// compared by `git patch-id`, so nothing is written to the repository.
+fn is_rebased(root: &Path, main_branch_name: &str, head: &str) -> bool {
+    git_command(vec!["cherry", main_branch_name, head], root)
+        .map(|result| result.stdout_lines())
+        .is_ok_and(|lines| !lines.is_empty() && lines.iter().all(|line| line.starts_with('-')))
+}
+
+// The first column of `git patch-id` for the patches that `args` print
+fn patch_ids(root: &Path, args: Vec<&str>) -> Vec<String> {
+    git_command(args, root)
+        .and_then(|result| {
+            git_command_with_input(vec!["patch-id", "--stable"], result.stdout, root)
+        })
+        .map(|result| {
+            result
+                .stdout_lines()
+                .iter()
+                .filter_map(|line| line.split(' ').next().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Where the branch forked off the main branch and the patch-id of all its changes since then
+fn branch_patch_id(root: &Path, main_branch_name: &str, head: &str) -> Option<(String, String)> {
+    let base = git_command(vec!["merge-base", main_branch_name, head], root)
+        .ok()
+        .and_then(|result| result.stdout_value())?;
+    let patch_id = patch_ids(root, vec!["diff", "--no-ext-diff", &base, head])
+        .into_iter()
+        .next()?;
+
+    Some((base, patch_id))
+}
+
+// The patch-ids of the main branch's commits since the oldest of `bases`, read once for every
+// branch instead of once per branch
+fn main_patch_ids(root: &Path, main_branch_name: &str, bases: &[&str]) -> HashSet<String> {
+    let mut bases = bases.to_vec();
+    bases.sort();
+    bases.dedup();
+
+    // every base is on the main branch, so the common ancestor of all of them is the oldest
+    let oldest = match bases.as_slice() {
+        [] => return HashSet::new(),
+        [base] => Some(base.to_string()),
+        _ => git_command([vec!["merge-base", "--octopus"], bases].concat(), root)
+            .ok()
+            .and_then(|result| result.stdout_value()),
+    };
+    let main_commits = match oldest {
+        Some(oldest) => format!("{}..{}", oldest, main_branch_name),
+        None => return HashSet::new(),
+    };
+
+    patch_ids(
+        root,
+        vec![
+            "log",
+            "--no-merges",
+            "--no-ext-diff",
+            "--patch",
+            "--format=commit %H",
+            &main_commits,
+        ],
+    )
+    .into_iter()
+    .collect()
+}
+
+// Local branches, and remote ones too with `remote`
+pub fn branch_reports(
+    root: &Path,
+    main_branch_name: &str,
+    remote: bool,
+) -> Result<Vec<BranchReport>, String> {
+    let patterns = match remote {
+        true => vec!["refs/heads/", "refs/remotes/"],
+        false => vec!["refs/heads/"],
+    };
+    let lines = git_command(
+        [vec!["for-each-ref", REF_FORMAT], patterns.clone()].concat(),
+        root,
+    )
+    .map_err(|result| result.error_message())?
+    .stdout_lines();
+    let merged_arg = format!("--merged={}", main_branch_name);
+    let merged = git_command(
+        [
+            vec!["for-each-ref", "--format=%(refname)", &merged_arg],
+            patterns,
+        ]
+        .concat(),
+        root,
+    )
+    .map_err(|result| result.error_message())?
+    .stdout_lines()
+    .iter()
+    .filter_map(|refname| short_name(refname))
+    .collect::<HashSet<(String, bool)>>();
+    let worktrees = worktree_entries(root)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|entry| {
+            let path = Path::new(&entry.path);
+            let relative = match path.strip_prefix(root) {
+                Ok(relative) if relative.as_os_str().is_empty() => Path::new("."),
+                Ok(relative) => relative,
+                Err(_) => path,
+            };
+
+            Some((entry.branch?, relative.to_string_lossy().to_string()))
+        })
+        .collect::<HashMap<String, String>>();
+
+    let reports = lines
+        .iter()
+        .filter_map(|line| parse_ref(line))
+        .collect::<Vec<BranchReport>>();
+
+    // finding squashed branches takes a few git processes per branch
+    let reports = parallel_map(reports, jobs(), |mut report| {
+        report.main_ahead_behind = ahead_behind(root, main_branch_name, &report.head);
+        report.merge = if !report.remote && report.name == main_branch_name {
+            MergeState::Main
+        } else if merged.contains(&(report.name.clone(), report.remote)) {
+            MergeState::Merged
+        } else if is_rebased(root, main_branch_name, &report.head) {
+            MergeState::Squashed
+        } else {
+            MergeState::Unmerged
+        };
+        if !report.remote {
+            report.worktree = worktrees.get(&report.name).cloned();
+        }
+        let patch_id = match report.merge {
+            MergeState::Unmerged => branch_patch_id(root, main_branch_name, &report.head),
+            _ => None,
+        };
+
+        (report, patch_id)
+    });
+
+    let bases = reports
+        .iter()
+        .filter_map(|(_, patch_id)| patch_id.as_ref().map(|(base, _)| base.as_str()))
+        .collect::<Vec<&str>>();
+    let main_patch_ids = main_patch_ids(root, main_branch_name, &bases);
+
+    Ok(reports
+        .into_iter()
+        .map(|(mut report, patch_id)| {
+            if patch_id.is_some_and(|(_, patch_id)| main_patch_ids.contains(&patch_id)) {
+                report.merge = MergeState::Squashed;
+            }
+
+            report
+        })
+        .collect())
+}
+
+impl Filters {
+    pub fn matches(&self, report: &BranchReport, now: i64) -> bool {
+        let merged = matches!(report.merge, MergeState::Merged | MergeState::Squashed);
+        let author = |pattern: &String| {
+            let pattern = pattern.to_lowercase();
+
+            report.author.to_lowercase().contains(&pattern)
+                || report.author_email.to_lowercase().contains(&pattern)
+        };
+
+        (!self.merged || merged)
+            && (!self.unmerged || report.merge == MergeState::Unmerged)
+            && (!self.gone || report.upstream_gone)
+            && self.author.as_ref().is_none_or(author)
+            && self
+                .older_than_days
+                .is_none_or(|days| now - report.last_commit > days * 24 * 60 * 60)
+    }
+}
+
+pub fn sort_reports(reports: &mut [BranchReport], key: SortKey) {
+    let ahead = |report: &BranchReport| report.main_ahead_behind.map_or(0, |(ahead, _)| ahead);
+    let behind = |report: &BranchReport| report.main_ahead_behind.map_or(0, |(_, behind)| behind);
+
+    match key {
+        SortKey::Age => reports.sort_by_key(|report| report.last_commit),
+        SortKey::Name => reports.sort_by(|a, b| a.name.cmp(&b.name)),
+        SortKey::Author => reports.sort_by(|a, b| {
+            (a.author.to_lowercase(), a.last_commit).cmp(&(b.author.to_lowercase(), b.last_commit))
+        }),
+        SortKey::Ahead => reports.sort_by_key(|report| std::cmp::Reverse(ahead(report))),
+        SortKey::Behind => reports.sort_by_key(|report| std::cmp::Reverse(behind(report))),
+    }
+}
+
+pub fn render_table(reports: &[BranchReport], now: i64) -> String {
+    let rows = reports
+        .iter()
+        .map(|report| {
+            vec![
+                report.name.clone(),
+                format_age(now - report.last_commit),
+                report.author.clone(),
+                format_ahead_behind(report.main_ahead_behind),
+                report.merge.to_string(),
+                match (&report.upstream, report.upstream_gone) {
+                    (Some(upstream), true) => format!("{} gone", upstream),
+                    (Some(upstream), false) => upstream.clone(),
+                    (None, _) => "-".to_string(),
+                },
+                report.worktree.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect::<Vec<Vec<String>>>();
+
+    table::render(
+        &[
+            "BRANCH",
+            "LAST COMMIT",
+            "AUTHOR",
+            "MAIN",
+            "MERGE",
+            "UPSTREAM",
+            "WORKTREE",
+        ],
+        &rows,
+    )
+}
+
+// One row per branch with plain values, for spreadsheets
+pub fn render_csv(reports: &[BranchReport]) -> String {
+    let count = |count: Option<usize>| count.map_or(String::new(), |count| count.to_string());
+    let rows = reports
+        .iter()
+        .map(|report| {
+            vec![
+                report.name.clone(),
+                report.remote.to_string(),
+                report.last_commit_date.clone(),
+                report.author.clone(),
+                report.author_email.clone(),
+                count(report.main_ahead_behind.map(|(ahead, _)| ahead)),
+                count(report.main_ahead_behind.map(|(_, behind)| behind)),
+                report.merge.to_string(),
+                report.upstream.clone().unwrap_or_default(),
+                report.upstream_gone.to_string(),
+                report.worktree.clone().unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<Vec<String>>>();
+
+    table::render_csv(
+        &[
+            "branch",
+            "remote",
+            "last_commit",
+            "author",
+            "author_email",
+            "ahead",
+            "behind",
+            "merge",
+            "upstream",
+            "upstream_gone",
+            "worktree",
+        ],
+        &rows,
+    )
+}
+
+impl BranchReport {
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("branch", Json::from(self.name.as_str())),
+            ("remote", Json::from(self.remote)),
+            ("head", Json::from(self.head.as_str())),
+            ("last_commit", Json::from(self.last_commit)),
+            ("author", Json::from(self.author.as_str())),
+            ("author_email", Json::from(self.author_email.as_str())),
+            (
+                "main_ahead_behind",
+                match self.main_ahead_behind {
+                    Some((ahead, behind)) => Json::object(vec![
+                        ("ahead", Json::from(ahead)),
+                        ("behind", Json::from(behind)),
+                    ]),
+                    None => Json::Null,
+                },
+            ),
+            ("merge", Json::from(self.merge.to_string())),
+            ("upstream", Json::from(self.upstream.clone())),
+            ("upstream_gone", Json::from(self.upstream_gone)),
+            ("worktree", Json::from(self.worktree.clone())),
+        ])
+    }
+}
+
+pub fn render_json(reports: &[BranchReport]) -> String {
+    Json::Array(reports.iter().map(BranchReport::to_json).collect()).to_string()
+}
@@ -0,0 +1,193 @@
+#[cfg(test)]
+use super::{branch_reports, parse_ref, sort_reports, BranchReport, Filters, MergeState, SortKey};
+
+#[cfg(test)]
+use crate::{commands::git_command, test_helpers::run_test, test_setup::DEFAULT_BRANCH_NAME};
+
+#[cfg(test)]
+fn report(name: &str, author: &str, last_commit: i64, merge: MergeState) -> BranchReport {
+    BranchReport {
+        name: name.to_string(),
+        remote: false,
+        head: "0000000".to_string(),
+        last_commit,
+        last_commit_date: String::new(),
+        author: author.to_string(),
+        author_email: format!("{}@example.com", author.to_lowercase()),
+        upstream: None,
+        upstream_gone: false,
+        main_ahead_behind: None,
+        merge,
+        worktree: None,
+    }
+}
+
+#[cfg(test)]
+fn names(reports: &[BranchReport]) -> Vec<&str> {
+    reports.iter().map(|report| report.name.as_str()).collect()
+}
+
+#[test]
+fn test_parses_local_and_remote_branches() {
+    let line = |fields: &[&str]| fields.join("\0");
+    let local = parse_ref(&line(&[
+        "refs/heads/feature/x",
+        "abc",
+        "1700000000",
+        "2023-11-14T22:13:20+00:00",
+        "Ann Lee",
+        "<ann@example.com>",
+        "origin/feature/x",
+        "[gone]",
+        "",
+    ]))
+    .unwrap();
+
+    assert_eq!(local.name, "feature/x");
+    assert!(!local.remote);
+    assert_eq!(local.last_commit, 1700000000);
+    assert_eq!(local.author_email, "ann@example.com");
+    assert_eq!(local.upstream, Some("origin/feature/x".to_string()));
+    assert!(local.upstream_gone);
+
+    let remote = parse_ref(&line(&[
+        "refs/remotes/origin/main",
+        "abc",
+        "1",
+        "date",
+        "A",
+        "<a>",
+        "",
+        "",
+        "",
+    ]))
+    .unwrap();
+    assert_eq!(remote.name, "origin/main");
+    assert!(remote.remote);
+    assert_eq!(remote.upstream, None);
+
+    // `origin/HEAD` points at `origin/main`
+    assert_eq!(
+        parse_ref(&line(&[
+            "refs/remotes/origin/HEAD",
+            "abc",
+            "1",
+            "date",
+            "A",
+            "<a>",
+            "",
+            "",
+            "refs/remotes/origin/main",
+        ])),
+        None
+    );
+}
+
+#[test]
+fn test_filters_and_sorts_reports() {
+    let day = 24 * 60 * 60;
+    let now = 100 * day;
+    let mut reports = vec![
+        report("b", "Bob", now - 40 * day, MergeState::Squashed),
+        report("a", "ann", now - 5 * day, MergeState::Unmerged),
+        report("c", "Ann", now - 90 * day, MergeState::Merged),
+    ];
+    let matching = |filters: Filters| {
+        reports
+            .iter()
+            .filter(|report| filters.matches(report, now))
+            .map(|report| report.name.clone())
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(
+        matching(Filters {
+            merged: true,
+            ..Filters::default()
+        }),
+        vec!["b", "c"]
+    );
+    assert_eq!(
+        matching(Filters {
+            author: Some("ANN@".to_string()),
+            older_than_days: Some(30),
+            ..Filters::default()
+        }),
+        vec!["c"]
+    );
+
+    sort_reports(&mut reports, SortKey::Age);
+    assert_eq!(names(&reports), vec!["c", "b", "a"]);
+    sort_reports(&mut reports, SortKey::Name);
+    assert_eq!(names(&reports), vec!["a", "b", "c"]);
+    sort_reports(&mut reports, SortKey::Author);
+    assert_eq!(names(&reports), vec!["c", "a", "b"]);
+}
+
+#[test]
+fn test_reports_merged_squashed_and_unmerged_branches() {
+    run_test(
+        "test_reports_merged_squashed_and_unmerged_branches",
+        "clean_repo",
+        |repo| {
+            let root = repo.root();
+            let git = |args: Vec<&str>| {
+                git_command(args, root).unwrap();
+            };
+
+            git(vec!["checkout", "-b", "squashed"]);
+            for contents in ["squashed", "squashed again"] {
+                std::fs::write(root.join("squashed-file"), contents).unwrap();
+                git(vec!["add", "squashed-file"]);
+                git(vec!["commit", "-m", "commit squashed-file"]);
+            }
+            git(vec!["checkout", DEFAULT_BRANCH_NAME]);
+            git(vec!["merge", "--squash", "squashed"]);
+            git(vec!["commit", "-m", "squash merge"]);
+            // forks off later than the others, the patch-ids of the main branch cover both
+            git(vec!["checkout", "-b", "squashed-later"]);
+            std::fs::write(root.join("later-file"), "later").unwrap();
+            git(vec!["add", "later-file"]);
+            git(vec!["commit", "-m", "commit later-file"]);
+            git(vec!["checkout", DEFAULT_BRANCH_NAME]);
+            git(vec!["merge", "--squash", "squashed-later"]);
+            git(vec!["commit", "-m", "squash merge later"]);
+
+            let objects = || {
+                git_command(vec!["count-objects", "-v"], root)
+                    .unwrap()
+                    .stdout_lines()
+            };
+            let objects_before = objects();
+            let reports = branch_reports(root, DEFAULT_BRANCH_NAME, false).unwrap();
+            // reporting doesn't write anything
+            assert_eq!(objects_before, objects());
+            let merge = |name: &str| {
+                reports
+                    .iter()
+                    .find(|report| report.name == name)
+                    .map(|report| report.merge)
+            };
+
+            assert_eq!(merge(DEFAULT_BRANCH_NAME), Some(MergeState::Main));
+            assert_eq!(merge("merged"), Some(MergeState::Merged));
+            assert_eq!(merge("squashed"), Some(MergeState::Squashed));
+            assert_eq!(merge("squashed-later"), Some(MergeState::Squashed));
+            assert_eq!(merge("unmerged"), Some(MergeState::Unmerged));
+
+            let unmerged = reports
+                .iter()
+                .find(|report| report.name == "unmerged")
+                .unwrap();
+            assert_eq!(unmerged.main_ahead_behind, Some((1, 3)));
+            assert_eq!(unmerged.worktree, None);
+            assert_eq!(
+                reports
+                    .iter()
+                    .find(|report| report.name == DEFAULT_BRANCH_NAME)
+                    .and_then(|report| report.worktree.clone()),
+                Some(".".to_string())
+            );
+        },
+    );
+}
@@ -0,0 +1,455 @@
+use crate::shell::SUPPORTED_SHELLS;
+
+#[cfg(test)]
+mod tests;
+
+// What an argument or an option's value can be, used to complete it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Values {
+    Anything,
+    Branches,
+    Directories,
+    // The repository's worktree paths and directories
+    Worktrees,
+    Words(&'static [&'static str]),
+}
+
+#[derive(Debug)]
+pub struct ArgumentSpec {
+    pub name: &'static str,
+    pub required: bool,
+    pub values: Values,
+}
+
+#[derive(Debug)]
+pub struct OptionSpec {
+    pub name: &'static str,
+    // `None` for boolean flags
+    pub value: Option<Values>,
+    // How the value is shown in the help, e.g. `directory` for `--all-under <directory>`
+    pub value_name: &'static str,
+    pub help: &'static str,
+}
+
+// Everything the usage, `--help`, the man pages and the completions know about a command. `help`
+// holds paragraphs separated by blank lines, lines starting with `- ` are list items.
+#[derive(Debug)]
+pub struct CommandSpec {
+    pub words: &'static [&'static str],
+    pub arguments: &'static [ArgumentSpec],
+    pub options: &'static [OptionSpec],
+    pub description: &'static str,
+    pub help: &'static str,
+}
+
+const fn argument(name: &'static str, values: Values) -> ArgumentSpec {
+    ArgumentSpec {
+        name,
+        required: true,
+        values,
+    }
+}
+
+const fn optional(name: &'static str, values: Values) -> ArgumentSpec {
+    ArgumentSpec {
+        name,
+        required: false,
+        values,
+    }
+}
+
+const fn option(
+    name: &'static str,
+    value_name: &'static str,
+    value: Values,
+    help: &'static str,
+) -> OptionSpec {
+    OptionSpec {
+        name,
+        value: Some(value),
+        value_name,
+        help,
+    }
+}
+
+const fn flag(name: &'static str, help: &'static str) -> OptionSpec {
+    OptionSpec {
+        name,
+        value: None,
+        value_name: "",
+        help,
+    }
+}
+
+const ALL_UNDER: OptionSpec = option(
+    "--all-under",
+    "directory",
+    Values::Directories,
+    "Run in every repository below the directory instead of the current one.",
+);
+const JOBS: OptionSpec = option(
+    "--jobs",
+    "n",
+    Values::Anything,
+    "How many worktrees or repositories to work on at once, defaults to the number of CPUs (at most 8).",
+);
+const PATH_TEMPLATE: OptionSpec = option(
+    "--path-template",
+    "template",
+    Values::Anything,
+    "Where worktrees go, e.g. {root}/{branch_slug}. Overrides the worktreePathTemplate setting.",
+);
+const JSON: OptionSpec = flag("--json", "Print JSON, the same as --format json.");
+const FORMAT: OptionSpec = option(
+    "--format",
+    "table|csv|json",
+    Values::Words(&["table", "csv", "json"]),
+    "The output format. Overrides the output.format setting.",
+);
+const SHELL: ArgumentSpec = argument("bash|zsh|fish", Values::Words(&SUPPORTED_SHELLS));
+
+pub const GLOBAL_OPTION_SPECS: [OptionSpec; 4] = [
+    option(
+        "--main-branch",
+        "branch",
+        Values::Branches,
+        "The main branch, instead of the mainBranch setting or the first of the mainBranchCandidates that exists.",
+    ),
+    option(
+        "--repo",
+        "path",
+        Values::Worktrees,
+        "Run in the repository at the path instead of the current directory.",
+    ),
+    flag(
+        "--verbose",
+        "Log every git command and why branches are deleted or skipped, -vv also logs their output.",
+    ),
+    flag("--help", "Show the help of the command."),
+];
+
+// Every command with its arguments and options, in the order of the usage text
+pub const COMMANDS: [CommandSpec; 17] = [
+    CommandSpec {
+        words: &["clean-merged-branches"],
+        arguments: &[],
+        options: &[ALL_UNDER, JOBS],
+        description: "Delete merged branches and worktrees",
+        help: "Deletes every local branch whose last commit is on the main branch. In a bare repository the \
+worktrees of those branches are removed along with them.
+
+Nothing that isn't merged or that holds uncommitted work is deleted:
+
+- the main branch and branches matching the protectedBranches setting are never deleted, nor are their worktrees
+- branches that were squash merged or rebased onto the main branch aren't merged by this definition and are kept
+- a worktree with uncommitted changes or untracked files is kept, along with its branch
+- in a normal repository, branches checked out in another worktree are kept, as are branches that gained commits \
+since they were found to be merged
+- the current branch is only deleted when the working tree is clean, after checking out the main branch
+
+In a bare repository the hooks.preRemove commands run in each worktree before it is removed. When one fails, that \
+worktree and its branch are kept. The hooks.postRemove commands run in the repository root afterwards.
+
+Pressing Ctrl-C lets the current deletion finish, lists what was and wasn't deleted and exits with status 130. \
+The branch you have checked out stays checked out.",
+    },
+    CommandSpec {
+        words: &["worktree", "add"],
+        arguments: &[argument("branch", Values::Branches)],
+        options: &[
+            option(
+                "--from",
+                "start point",
+                Values::Branches,
+                "Create a new branch from this commit instead of the main branch.",
+            ),
+            PATH_TEMPLATE,
+        ],
+        description: "Create a worktree for a branch",
+        help: "Creates a worktree for the branch in a bare repository and prints its path, so that \
+cd \"$(git-tools worktree add my-branch)\" changes into it.
+
+An existing local branch is checked out as it is. A branch that only exists on the remote is created from it and \
+tracks it. Any other branch is created from an up-to-date main branch.
+
+The new worktree is prepared with the bootstrap.copy, bootstrap.symlink and bootstrap.run settings. Files that already exist in it are never overwritten.",
+    },
+    CommandSpec {
+        words: &["worktree", "rename"],
+        arguments: &[
+            argument("old branch", Values::Branches),
+            argument("new branch", Values::Anything),
+        ],
+        options: &[],
+        description: "Rename a branch and move its worktree",
+        help: "Renames the branch of a worktree and prints the worktree's path. The worktree moves as well when its \
+directory mirrors the old branch name. When the branch tracked a remote branch of the same name, the upstream is \
+pointed at the new name and the old remote branch is left alone.",
+    },
+    CommandSpec {
+        words: &["worktree", "list"],
+        arguments: &[],
+        options: &[JSON, FORMAT, ALL_UNDER, JOBS],
+        description: "List worktrees and their status",
+        help: "Shows every worktree with its branch, path, how far it is ahead of or behind its upstream and the main \
+branch, uncommitted changes, the age of its last commit and the flags main, merged, detached, locked and prunable.",
+    },
+    CommandSpec {
+        words: &["branches", "report"],
+        arguments: &[],
+        options: &[
+            flag("--remote", "Add the remote-tracking branches."),
+            option(
+                "--sort",
+                "age|name|author|ahead|behind",
+                Values::Words(&["age", "name", "author", "ahead", "behind"]),
+                "The order of the branches, oldest first by default.",
+            ),
+            flag("--merged", "Only merged or squashed branches."),
+            flag("--unmerged", "Only branches that aren't merged."),
+            flag("--gone", "Only branches whose upstream was deleted."),
+            option(
+                "--author",
+                "text",
+                Values::Anything,
+                "Only branches whose last commit's author name or email contains the text, ignoring case.",
+            ),
+            option(
+                "--older-than",
+                "days",
+                Values::Anything,
+                "Only branches whose last commit is older than this.",
+            ),
+            JSON,
+            FORMAT,
+        ],
+        description: "List branches with their age, author and merge status",
+        help: "Lists every local branch with the age and author of its last commit, how far it is ahead of or behind \
+the main branch, whether it is merged, its upstream and the worktree that holds it. Nothing is changed.
+
+A branch is merged when its last commit is on the main branch and squashed when its changes are, but as a different \
+commit.",
+    },
+    CommandSpec {
+        words: &["convert-to-bare"],
+        arguments: &[],
+        options: &[PATH_TEMPLATE],
+        description: "Turn a normal repository into a bare one with worktrees",
+        help: "Moves the repository to .bare, puts a .git file pointing at it in its place and turns the current \
+checkout into a worktree, whose path is printed. Files are moved rather than checked out again, so uncommitted \
+changes, the index, untracked and ignored files are all kept. If a step fails, the repository is restored.
+
+Repositories with other worktrees, submodules, a detached HEAD or an unfinished merge, rebase, cherry-pick, revert \
+or bisect are refused.",
+    },
+    CommandSpec {
+        words: &["convert-to-normal"],
+        arguments: &[],
+        options: &[],
+        description: "Turn a bare repository back into a normal one",
+        help: "Reverses convert-to-bare for a repository that has exactly one worktree.",
+    },
+    CommandSpec {
+        words: &["clone"],
+        arguments: &[
+            argument("url", Values::Anything),
+            optional("directory", Values::Directories),
+        ],
+        options: &[PATH_TEMPLATE],
+        description: "Clone a repository as a bare repository",
+        help: "Clones the repository with --bare into directory/.bare, sets it up to fetch remote-tracking branches \
+and adds a worktree for the default branch, whose path is printed. The directory defaults to the last part of the \
+URL without .git.",
+    },
+    CommandSpec {
+        words: &["sync"],
+        arguments: &[],
+        options: &[ALL_UNDER, JOBS],
+        description: "Fetch and update every worktree",
+        help: "Fetches all remotes of a bare repository and fast-forwards every local branch whose upstream moved \
+ahead. Branches that diverged from their upstream, whose worktree has changes or whose upstream is gone are reported \
+and left alone.",
+    },
+    CommandSpec {
+        words: &["workspace", "sync"],
+        arguments: &[],
+        options: &[JOBS],
+        description: "Clone and fetch the repositories of git-tools.toml",
+        help: "Clones the repositories listed in the closest git-tools.toml that are missing, fetches the others and \
+reports where they drifted from the manifest. Nothing is changed to fix drift.",
+    },
+    CommandSpec {
+        words: &["switch"],
+        arguments: &[argument("branch", Values::Branches)],
+        options: &[],
+        description: "Change into a branch's worktree",
+        help: "In a bare repository, prints the path of the branch's worktree and creates it like worktree add when \
+there is none. In a normal repository, runs git switch, or prints the path of the worktree that already has the \
+branch checked out. With the shell integration of shell-init, the shell changes \
+into the printed directory.",
+    },
+    CommandSpec {
+        words: &["pick"],
+        arguments: &[optional("query", Values::Branches)],
+        options: &[flag("--switch", "Switch to the picked branch like switch does.")],
+        description: "Pick a branch interactively",
+        help: "Opens a fuzzy finder over the local branches, most recently committed to first, and prints the picked \
+branch.",
+    },
+    CommandSpec {
+        words: &["shell-init"],
+        arguments: &[SHELL],
+        options: &[],
+        description: "Print the shell integration for switch and pick",
+        help: "Prints a shell function that wraps git-tools and changes into the directory that switch and \
+pick --switch print. Add eval \"$(git-tools shell-init bash)\" to the shell's startup file.",
+    },
+    CommandSpec {
+        words: &["completions"],
+        arguments: &[SHELL],
+        options: &[],
+        description: "Print a shell completion script",
+        help: "Prints a completion script for every command and option, which also completes branch names and \
+worktree paths.",
+    },
+    CommandSpec {
+        words: &["config", "show"],
+        arguments: &[],
+        options: &[JSON, FORMAT],
+        description: "Show every setting and where it comes from",
+        help: "Prints every setting with its value and where the value came from: the defaults, the user config file \
+($XDG_CONFIG_HOME/git-tools/config.toml), git config under git-tools. or a command line flag, later ones win.",
+    },
+    CommandSpec {
+        words: &["install-aliases"],
+        arguments: &[],
+        options: &[
+            option(
+                "--bin-dir",
+                "directory",
+                Values::Directories,
+                "Create git-clean-merged and git-wt symlinks in the directory, which has to be on the PATH.",
+            ),
+            flag(
+                "--git-config",
+                "Add alias.clean-merged and alias.wt to the global git config instead.",
+            ),
+        ],
+        description: "Install git-clean-merged and git-wt as git subcommands",
+        help: "Makes git clean-merged run clean-merged-branches and git wt run worktree. Existing files or aliases \
+with the same names are never replaced.",
+    },
+    CommandSpec {
+        words: &["man-pages"],
+        arguments: &[argument("directory", Values::Directories)],
+        options: &[],
+        description: "Write the man pages",
+        help: "Writes git-tools.1, a page for every command and git-clean-merged.1 and git-wt.1 for the aliases to \
+the directory, e.g. ~/.local/share/man/man1, so that man git-tools and git help clean-merged show them.",
+    },
+];
+
+// Options that take a value, across every command
+pub fn value_options() -> Vec<&'static str> {
+    GLOBAL_OPTION_SPECS
+        .iter()
+        .chain(COMMANDS.iter().flat_map(|command| command.options))
+        .filter(|option| option.value.is_some())
+        .map(|option| option.name)
+        .collect()
+}
+
+// The options of the command that `words` name, besides the global ones
+pub fn options_of(words: &[&str]) -> Vec<&'static str> {
+    match COMMANDS.iter().find(|command| command.words == words) {
+        Some(command) => command.options.iter().map(|option| option.name).collect(),
+        None => vec![],
+    }
+}
+
+// A deliberately small argument parser: positional arguments, `--name value`/`--name=value`
+// options, boolean `--flag`s and the global `-v`/`-vv`/`--verbose` verbosity flags.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ParsedArguments {
+    pub positional: Vec<String>,
+    pub verbosity: usize,
+    options: Vec<(String, Option<String>)>,
+}
+
+fn verbosity_of(arg: &str) -> usize {
+    match arg {
+        "--verbose" => 1,
+        flag if flag.len() > 1 && flag.starts_with('-') && flag[1..].chars().all(|c| c == 'v') => {
+            flag.len() - 1
+        }
+        _ => 0,
+    }
+}
+
+impl ParsedArguments {
+    // `value_options` lists the options that take a value, every other option is a boolean flag
+    pub fn parse(args: &[String], value_options: &[&str]) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                parsed.positional.extend(args.by_ref().cloned());
+                break;
+            }
+
+            let verbosity = verbosity_of(arg);
+            if verbosity > 0 {
+                parsed.verbosity += verbosity;
+                continue;
+            }
+
+            if !arg.starts_with('-') || arg == "-" {
+                parsed.positional.push(arg.clone());
+                continue;
+            }
+
+            match arg.split_once('=') {
+                Some((name, value)) => {
+                    parsed
+                        .options
+                        .push((name.to_string(), Some(value.to_string())));
+                }
+                None if value_options.contains(&arg.as_str()) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("{} requires a value", arg))?;
+                    parsed.options.push((arg.clone(), Some(value.clone())));
+                }
+                None => parsed.options.push((arg.clone(), None)),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    // Fails on the first option that the command doesn't know about
+    pub fn reject_unknown_options(&self, known: &[&str]) -> Result<(), String> {
+        match self.options.iter().find(|(option, _)| {
+            !known.contains(&option.as_str())
+                && !GLOBAL_OPTION_SPECS
+                    .iter()
+                    .any(|global| global.name == option.as_str())
+        }) {
+            Some((option, _)) => Err(format!("Unknown option '{}'", option)),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+#[cfg(test)]
+use super::{value_options, ParsedArguments, COMMANDS, GLOBAL_OPTION_SPECS};
+
+#[cfg(test)]
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn test_positional_arguments_are_kept_in_order() {
+    let parsed = ParsedArguments::parse(&args(&["worktree", "add", "some-work"]), &[])
+        .expect("Couldn't parse arguments");
+
+    assert_eq!(vec!["worktree", "add", "some-work"], parsed.positional);
+}
+
+#[test]
+fn test_verbosity_flags_are_counted_anywhere() {
+    let parsed = ParsedArguments::parse(&args(&["-v", "clean-merged-branches", "-vv"]), &[])
+        .expect("Couldn't parse arguments");
+
+    assert_eq!(3, parsed.verbosity);
+    assert_eq!(vec!["clean-merged-branches"], parsed.positional);
+}
+
+#[test]
+fn test_options_can_take_values_with_a_space_or_an_equals_sign() {
+    let parsed = ParsedArguments::parse(
+        &args(&["--from", "origin/main", "--path-template={root}/{branch}"]),
+        &["--from", "--path-template"],
+    )
+    .expect("Couldn't parse arguments");
+
+    assert_eq!(Some("origin/main"), parsed.value("--from"));
+    assert_eq!(Some("{root}/{branch}"), parsed.value("--path-template"));
+}
+
+#[test]
+fn test_options_that_require_a_value_fail_without_one() {
+    assert!(ParsedArguments::parse(&args(&["--from"]), &["--from"]).is_err());
+}
+
+#[test]
+fn test_arguments_after_a_double_dash_are_positional() {
+    let parsed = ParsedArguments::parse(&args(&["--", "--not-an-option", "-v"]), &[])
+        .expect("Couldn't parse arguments");
+
+    assert_eq!(vec!["--not-an-option", "-v"], parsed.positional);
+    assert_eq!(0, parsed.verbosity);
+}
+
+#[test]
+fn test_unknown_options_are_rejected() {
+    let parsed = ParsedArguments::parse(&args(&["--nope"]), &[]).expect("Couldn't parse arguments");
+
+    assert!(parsed.reject_unknown_options(&["--json"]).is_err());
+    assert!(parsed.reject_unknown_options(&["--nope"]).is_ok());
+}
+
+#[test]
+fn test_flags_are_detected_among_positional_arguments() {
+    let parsed = ParsedArguments::parse(&args(&["worktree", "--json", "list"]), &[])
+        .expect("Couldn't parse arguments");
+
+    assert!(parsed.flag("--json"));
+    assert!(!parsed.flag("--all"));
+    assert_eq!(vec!["worktree", "list"], parsed.positional);
+}
+
+#[test]
+fn test_value_options_are_the_options_that_take_a_value() {
+    let value_options = value_options();
+
+    for option in GLOBAL_OPTION_SPECS
+        .iter()
+        .chain(COMMANDS.iter().flat_map(|command| command.options))
+    {
+        assert_eq!(
+            option.value.is_some(),
+            value_options.contains(&option.name),
+            "{}",
+            option.name
+        );
+    }
+}
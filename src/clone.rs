@@ -0,0 +1,184 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    commands::{git_command, git_remote_command},
+    convert::BARE_DIRECTORY_NAME,
+    logging::debug,
+    worktree_path::{configured_path_template, expand_path_template},
+};
+
+#[cfg(test)]
+mod tests;
+
+const REMOTE_NAME: &str = "origin";
+
+// The directory `git clone` would pick: the last path component without `.git`
+pub fn directory_name(url: &str) -> Option<String> {
+    let trimmed = url.trim_end_matches(['/', '\\']);
+    let trimmed = trimmed.strip_suffix(".git").unwrap_or(trimmed);
+    let name = trimmed.rsplit(['/', '\\', ':']).next()?;
+
+    match name.is_empty() {
+        true => None,
+        false => Some(name.to_string()),
+    }
+}
+
+// Clones `url` into `target` with the repository in `.bare` and a worktree for the default branch,
+// and returns the worktree's path. A relative local URL is relative to `cwd`, like a relative
+// target should be.
+pub fn clone_bare(
+    cwd: &Path,
+    url: &str,
+    target: &Path,
+    path_template: Option<String>,
+) -> Result<PathBuf, String> {
+    let target_existed = target.exists();
+
+    if target_existed
+        && fs::read_dir(target)
+            .map_err(|err| err.to_string())?
+            .next()
+            .is_some()
+    {
+        return Err(format!("{:?} already exists and is not empty", target));
+    }
+
+    fs::create_dir_all(target).map_err(|err| err.to_string())?;
+
+    let result = clone_into(cwd, url, target, path_template);
+
+    if result.is_err() {
+        debug!("cloning failed, removing {:?}", target);
+        let _ = fs::remove_dir_all(target);
+
+        // an empty directory that was there before is left as it was found
+        if target_existed {
+            let _ = fs::create_dir(target);
+        }
+    }
+
+    result
+}
+
+fn clone_into(
+    cwd: &Path,
+    url: &str,
+    target: &Path,
+    path_template: Option<String>,
+) -> Result<PathBuf, String> {
+    let bare_dir = target.join(BARE_DIRECTORY_NAME);
+    let bare_dir_str = bare_dir
+        .to_str()
+        .ok_or_else(|| format!("{:?} is not a valid path", bare_dir))?;
+    let run = |args: Vec<&str>| {
+        git_command(args, target)
+            .map(|_| ())
+            .map_err(|result| result.error_message())
+    };
+
+    eprintln!("Cloning {} into {:?}", url, target);
+    git_remote_command(
+        vec![
+            "clone",
+            "--bare",
+            "--origin",
+            REMOTE_NAME,
+            url,
+            bare_dir_str,
+        ],
+        cwd,
+    )
+    .map_err(|result| result.error_message())?;
+
+    fs::write(
+        target.join(".git"),
+        format!("gitdir: ./{}\n", BARE_DIRECTORY_NAME),
+    )
+    .map_err(|err| err.to_string())?;
+
+    // bare clones map the remote's branches onto local branches and don't fetch anything else
+    let refspec = format!("+refs/heads/*:refs/remotes/{}/*", REMOTE_NAME);
+    run(vec![
+        "config",
+        &format!("remote.{}.fetch", REMOTE_NAME),
+        &refspec,
+    ])?;
+    for args in [
+        vec!["fetch", "--quiet", REMOTE_NAME],
+        vec!["remote", "set-head", REMOTE_NAME, "--auto"],
+    ] {
+        git_remote_command(args, target).map_err(|result| result.error_message())?;
+    }
+
+    let default_branch = git_command(vec!["symbolic-ref", "--short", "HEAD"], target)
+        .map_err(|result| result.error_message())?
+        .stdout_value()
+        .ok_or_else(|| "The remote doesn't have a default branch".to_string())?;
+
+    remove_copied_branches(target, &default_branch);
+
+    let merge_ref = format!("refs/heads/{}", default_branch);
+    run(vec![
+        "config",
+        &format!("branch.{}.remote", default_branch),
+        REMOTE_NAME,
+    ])?;
+    run(vec![
+        "config",
+        &format!("branch.{}.merge", default_branch),
+        &merge_ref,
+    ])?;
+
+    let path_template = path_template.unwrap_or_else(|| configured_path_template(target));
+    let worktree_path = expand_path_template(&path_template, target, &default_branch);
+    let worktree_str = worktree_path
+        .to_str()
+        .ok_or_else(|| format!("{:?} is not a valid path", worktree_path))?;
+
+    run(vec!["worktree", "add", worktree_str, &default_branch])?;
+
+    Ok(worktree_path)
+}
+
+// Like a normal clone, only the default branch stays local. The others are available as
+// remote-tracking branches now, so only exact copies of those are removed.
+fn remove_copied_branches(target: &Path, default_branch: &str) {
+    let branches = git_command(
+        vec![
+            "for-each-ref",
+            "--format=%(refname:lstrip=2) %(objectname)",
+            "refs/heads/",
+        ],
+        target,
+    )
+    .map(|result| result.stdout_lines())
+    .unwrap_or_default();
+
+    for line in branches {
+        let (branch, oid) = match line.rsplit_once(' ') {
+            Some(split) => split,
+            None => continue,
+        };
+        let remote_ref = format!("refs/remotes/{}/{}", REMOTE_NAME, branch);
+        let remote_oid = git_command(
+            vec!["rev-parse", "--verify", "--quiet", &remote_ref],
+            target,
+        )
+        .ok()
+        .and_then(|result| result.stdout_value());
+
+        if branch == default_branch || remote_oid.as_deref() != Some(oid) {
+            continue;
+        }
+
+        debug!("removing the local copy of '{}'", branch);
+        let _ = git_command(
+            vec!["update-ref", "-d", &format!("refs/heads/{}", branch), oid],
+            target,
+        );
+    }
+}
@@ -0,0 +1,88 @@
+#[cfg(test)]
+use super::{clone_bare, directory_name};
+
+#[cfg(test)]
+use crate::{
+    repository::Repository,
+    test_helpers::{self, run_test},
+    test_setup::{BARE_REPO_NAME, DEFAULT_BRANCH_NAME},
+    utils::{get_git_config_value, ref_exists},
+};
+
+#[test]
+fn test_directory_names_follow_git_clone() {
+    assert_eq!(
+        Some("git-tools".to_string()),
+        directory_name("https://example.com/someone/git-tools.git")
+    );
+    assert_eq!(
+        Some("repo".to_string()),
+        directory_name("git@example.com:someone/repo.git/")
+    );
+    assert_eq!(Some("repo".to_string()), directory_name("host:repo"));
+    assert_eq!(
+        Some("source".to_string()),
+        directory_name("../local/source")
+    );
+    assert_eq!(None, directory_name("/"));
+}
+
+#[test]
+fn test_clone_creates_a_bare_repository_with_a_main_worktree() {
+    run_test(
+        "test_clone_creates_a_bare_repository_with_a_main_worktree",
+        BARE_REPO_NAME,
+        |repo| {
+            let source = repo.root().join("../bare_repo_source");
+            let target = repo.root().join("../cloned");
+
+            let worktree_path = clone_bare(repo.root(), source.to_str().unwrap(), &target, None)
+                .expect("Couldn't clone");
+
+            assert_eq!(target.join(DEFAULT_BRANCH_NAME), worktree_path);
+            assert!(ref_exists(&target, "refs/remotes/origin/HEAD"));
+            assert!(ref_exists(&target, "refs/remotes/origin/other-branch"));
+            // only the default branch is kept as a local branch, like a normal clone
+            assert!(!ref_exists(&target, "refs/heads/other-branch"));
+            assert_eq!(
+                Some("refs/heads/main".to_string()),
+                get_git_config_value(&target, "branch.main.merge")
+            );
+
+            let cloned = Repository::at(&worktree_path).expect("Couldn't open the clone");
+            assert!(matches!(cloned, Repository::Bare(_)));
+            // the root is the path git reports, without the `..`
+            assert_eq!(&target.canonicalize().unwrap(), cloned.root());
+            test_helpers::assert_worktree_exists(&cloned, DEFAULT_BRANCH_NAME.to_string());
+        },
+    );
+}
+
+#[test]
+fn test_relative_urls_are_relative_to_the_given_directory() {
+    run_test(
+        "test_relative_urls_are_relative_to_the_given_directory",
+        BARE_REPO_NAME,
+        |repo| {
+            let target = repo.root().join("../cloned");
+
+            clone_bare(repo.root(), "../bare_repo_source", &target, None).expect("Couldn't clone");
+
+            assert!(ref_exists(&target, "refs/remotes/origin/other-branch"));
+        },
+    );
+}
+
+#[test]
+fn test_failed_clone_leaves_nothing_behind() {
+    run_test(
+        "test_failed_clone_leaves_nothing_behind",
+        BARE_REPO_NAME,
+        |repo| {
+            let target = repo.root().join("../cloned");
+
+            assert!(clone_bare(repo.root(), "/does/not/exist", &target, None).is_err());
+            assert!(!target.exists());
+        },
+    );
+}
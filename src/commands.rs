@@ -1,73 +1,410 @@
 use std::{
+    env,
+    io::{Read, Write},
     path::{Path, PathBuf},
-    process::Command,
+    process::{Child, Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
 };
 
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+
+#[cfg(test)]
+use std::cell::RefCell;
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    logging::{debug, trace, warning},
+    recording::{append_to_file, RecordedCommand, RECORD_ENV_VAR},
+};
+
+#[cfg(test)]
+use crate::recording::Replay;
+
+// Forces output into the format we parse no matter how the user has configured git. The values
+// are passed through GIT_CONFIG_COUNT/KEY/VALUE so they don't show up in the argument list.
+const GIT_CONFIG_OVERRIDES: [(&str, &str); 5] = [
+    ("color.ui", "never"),
+    ("column.ui", "never"),
+    ("core.quotepath", "false"),
+    ("status.showUntrackedFiles", "normal"),
+    ("advice.detachedHead", "false"),
+];
+
+// Seconds a single command may run before it is killed, 0 disables the timeout
+pub const TIMEOUT_ENV_VAR: &str = "GIT_TOOLS_TIMEOUT";
+
+// Only for local commands, how long a clone or fetch takes depends on the remote and the network
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+// These would point git at a different repository than the one we were asked to operate on
+const GIT_REPOSITORY_ENV_VARS: [&str; 7] = [
+    "GIT_DIR",
+    "GIT_WORK_TREE",
+    "GIT_INDEX_FILE",
+    "GIT_COMMON_DIR",
+    "GIT_OBJECT_DIRECTORY",
+    "GIT_ALTERNATE_OBJECT_DIRECTORIES",
+    "GIT_NAMESPACE",
+];
+
 pub struct CommandConfiguration<'a> {
     cmd: &'a str,
     args: Option<Vec<&'a str>>,
     cwd: &'a PathBuf,
+    envs: Vec<(String, String)>,
+    removed_envs: Vec<&'a str>,
+    timeout: Option<Duration>,
+    // Written to the process's stdin, which is inherited otherwise
+    input: Option<Vec<u8>>,
+    // Whether the process is started in a process group of its own, see `run_command`
+    own_process_group: bool,
 }
 
-#[derive(Debug)]
-pub struct SuccessfulCommandExecution {
-    pub output: Vec<String>,
+// Everything a process produced, untouched. Callers decide whether a blank line or a line of
+// whitespace is meaningful (e.g. a branch named `x` is a single character line).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandOutput {
+    pub status: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
 }
 
-#[derive(Debug)]
-pub struct FailedCommandExecution {
-    pub output: Vec<String>,
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+
+    // One item per line with the line terminator removed. A trailing newline doesn't produce an
+    // extra empty line, but empty lines in the middle of the output are kept.
+    pub fn stdout_lines(&self) -> Vec<String> {
+        split_lines(&self.stdout)
+    }
+
+    // The first line of stdout, for commands that print a single value
+    pub fn stdout_value(&self) -> Option<String> {
+        self.stdout_lines().into_iter().next()
+    }
+
+    pub fn is_stdout_empty(&self) -> bool {
+        self.stdout.is_empty()
+    }
+
+    // A human readable description of why a command failed
+    pub fn error_message(&self) -> String {
+        let stderr = String::from_utf8_lossy(&self.stderr).trim().to_string();
+
+        match (stderr.is_empty(), self.status) {
+            (false, _) => stderr,
+            (true, Some(code)) => format!("exited with status {}", code),
+            (true, None) => "terminated by a signal".to_string(),
+        }
+    }
 }
 
-pub type CommandExecutionResult = Result<SuccessfulCommandExecution, FailedCommandExecution>;
+fn split_lines(bytes: &[u8]) -> Vec<String> {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
 
-fn remove_empty_string_elements(items: Vec<&str>) -> Vec<String> {
-    items
-        .iter()
-        .filter_map(|item: &&str| {
-            if item.trim().len() > 1 {
-                Some(String::from(*item))
-            } else {
-                None
-            }
+    if bytes.is_empty() {
+        return vec![];
+    }
+
+    bytes
+        .split(|byte| *byte == b'\n')
+        .map(|line| {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            String::from_utf8_lossy(line).to_string()
         })
-        .collect::<Vec<String>>()
+        .collect()
+}
+
+pub type CommandExecutionResult = Result<CommandOutput, CommandOutput>;
+
+#[cfg(test)]
+thread_local! {
+    static REPLAY: RefCell<Option<Replay>> = const { RefCell::new(None) };
+}
+
+// Serve every command run on the current thread from `replay` instead of spawning processes
+#[cfg(test)]
+pub fn start_replay(replay: Replay) {
+    REPLAY.with(|r| *r.borrow_mut() = Some(replay));
+}
+
+// Stop replaying and hand back whatever wasn't consumed so tests can assert on it
+#[cfg(test)]
+pub fn stop_replay() -> Option<Replay> {
+    REPLAY.with(|r| r.borrow_mut().take())
+}
+
+#[cfg(test)]
+fn is_replaying() -> bool {
+    REPLAY.with(|r| r.borrow().is_some())
+}
+
+#[cfg(not(test))]
+fn is_replaying() -> bool {
+    false
+}
+
+// Recordings are replayed in the order the commands ran and a replay is only served to the thread
+// that started it, so while recording or replaying nothing may run concurrently
+pub fn must_run_sequentially() -> bool {
+    env::var_os(RECORD_ENV_VAR).is_some() || is_replaying()
+}
+
+#[cfg(test)]
+fn replayed_command(program: &str, args: &[String]) -> Option<RecordedCommand> {
+    REPLAY.with(|r| {
+        r.borrow_mut().as_mut().map(|replay| {
+            replay
+                .next_for(program, args)
+                .unwrap_or_else(|msg| panic!("Replay failed: {}", msg))
+        })
+    })
+}
+
+#[cfg(not(test))]
+fn replayed_command(_program: &str, _args: &[String]) -> Option<RecordedCommand> {
+    None
+}
+
+fn execute(
+    command: &mut Command,
+    program: &str,
+    args: &[String],
+    cwd: &Path,
+    timeout: Option<Duration>,
+    input: Option<Vec<u8>>,
+) -> RecordedCommand {
+    if let Some(recorded) = replayed_command(program, args) {
+        debug!(
+            "replayed {} {:?} (cwd: {:?}) exited with {:?}",
+            program, args, cwd, recorded.status
+        );
+        return recorded;
+    }
+
+    if input.is_some() {
+        command.stdin(Stdio::piped());
+    }
+
+    let started_at = Instant::now();
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|err| {
+            panic!(
+                "Failed to execute {:?} with error {:?}",
+                command.get_program(),
+                err.to_string()
+            )
+        });
+    let writer = input.map(|input| write_in_background(child.stdin.take(), input));
+    let result = wait_with_timeout(child, timeout)
+        .unwrap_or_else(|_| panic!("process {:?} failed to execute", command.get_program()));
+
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+
+    debug!(
+        "ran {} {:?} (cwd: {:?}) in {:?}, exited with {:?}",
+        program,
+        args,
+        cwd,
+        started_at.elapsed(),
+        result.status.and_then(|status| status.code())
+    );
+    trace!("stdout: {:?}", String::from_utf8_lossy(&result.stdout));
+    trace!("stderr: {:?}", String::from_utf8_lossy(&result.stderr));
+
+    RecordedCommand {
+        program: program.to_string(),
+        args: args.to_vec(),
+        cwd: cwd.to_path_buf(),
+        status: result.status.and_then(|status| status.code()),
+        stdout: result.stdout,
+        stderr: result.stderr,
+    }
+}
+
+struct ProcessOutput {
+    // None when the process was killed because it ran for too long
+    status: Option<ExitStatus>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+fn read_in_background<R: Read + Send + 'static>(stream: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes: Vec<u8> = vec![];
+
+        if let Some(mut stream) = stream {
+            let _ = stream.read_to_end(&mut bytes);
+        }
+
+        bytes
+    })
+}
+
+// stdin is closed once everything is written, the process may exit without reading all of it
+fn write_in_background<W: Write + Send + 'static>(
+    stream: Option<W>,
+    bytes: Vec<u8>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        if let Some(mut stream) = stream {
+            let _ = stream.write_all(&bytes);
+        }
+    })
+}
+
+fn wait_with_timeout(
+    mut child: Child,
+    timeout: Option<Duration>,
+) -> std::io::Result<ProcessOutput> {
+    // stdout and stderr are drained while waiting so a chatty process can't block on a full pipe
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let started_at = Instant::now();
+    let mut poll_interval = Duration::from_millis(1);
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+
+        if timeout.is_some_and(|timeout| started_at.elapsed() >= timeout) {
+            child.kill()?;
+            child.wait()?;
+            break None;
+        }
+
+        thread::sleep(poll_interval);
+        poll_interval = (poll_interval * 2).min(Duration::from_millis(50));
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let mut stderr = stderr.join().unwrap_or_default();
+
+    if status.is_none() {
+        stderr.extend_from_slice(
+            format!(
+                "git-tools: killed after running for longer than {:?}\n",
+                timeout.unwrap_or_default()
+            )
+            .as_bytes(),
+        );
+    }
+
+    Ok(ProcessOutput {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+// The timeout set through the environment, or `default` when there is none
+fn timeout(default: Option<Duration>) -> Option<Duration> {
+    match env::var(TIMEOUT_ENV_VAR) {
+        Ok(value) => match value.parse::<u64>() {
+            Ok(0) => None,
+            Ok(seconds) => Some(Duration::from_secs(seconds)),
+            Err(_) => {
+                warning!(
+                    "Ignoring {}={:?}, it must be a number of seconds",
+                    TIMEOUT_ENV_VAR,
+                    value
+                );
+                default
+            }
+        },
+        Err(_) => default,
+    }
 }
 
 pub fn run_command(config: CommandConfiguration) -> CommandExecutionResult {
     let mut command = Command::new(config.cmd);
+    let args = config
+        .args
+        .unwrap_or_default()
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<String>>();
 
-    if let Some(args) = config.args {
-        command.args(args);
+    command.args(&args);
+    command.current_dir(config.cwd);
+    command.envs(config.envs);
+
+    for name in config.removed_envs {
+        command.env_remove(name);
     }
 
-    command.current_dir(config.cwd);
+    // Keep child processes out of our process group so that Ctrl-C only reaches us and the
+    // current step can finish before we stop. Processes in another group can't read from the
+    // terminal, so anything that may prompt for a password or passphrase stays in ours.
+    #[cfg(unix)]
+    if config.own_process_group {
+        command.process_group(0);
+    }
 
-    let output = command.output();
+    let result = execute(
+        &mut command,
+        config.cmd,
+        &args,
+        config.cwd,
+        config.timeout,
+        config.input,
+    );
 
-    if let Err(err) = output {
-        panic!(
-            "Failed to execute {:?} with error {:?}",
-            command.get_program(),
-            err.to_string()
-        );
+    if let Ok(path) = env::var(RECORD_ENV_VAR) {
+        if let Err(msg) = append_to_file(&PathBuf::from(path), &result) {
+            warning!("{}", msg);
+        }
     }
 
-    let result =
-        output.unwrap_or_else(|_| panic!("process {:?} failed to execute", command.get_program()));
+    let output = CommandOutput {
+        status: result.status,
+        stdout: result.stdout,
+        stderr: result.stderr,
+    };
 
-    if result.status.success() {
-        let stdout = String::from_utf8(result.stdout).unwrap_or_default();
-        let items = remove_empty_string_elements(stdout.split('\n').collect::<Vec<&str>>());
-        Ok(SuccessfulCommandExecution { output: items })
+    if output.success() {
+        Ok(output)
     } else {
-        let stderr = String::from_utf8(result.stderr).unwrap_or_default();
-        let items = remove_empty_string_elements(stderr.split('\n').collect::<Vec<&str>>());
-        Err(FailedCommandExecution { output: items })
+        Err(output)
     }
 }
 
-pub fn git_command(args: Vec<&str>, cwd: &Path) -> CommandExecutionResult {
+fn git_envs() -> Vec<(String, String)> {
+    let mut envs: Vec<(String, String)> = vec![
+        ("LC_ALL".to_string(), "C".to_string()),
+        ("GIT_TERMINAL_PROMPT".to_string(), "0".to_string()),
+        (
+            "GIT_CONFIG_COUNT".to_string(),
+            GIT_CONFIG_OVERRIDES.len().to_string(),
+        ),
+    ];
+
+    for (index, (key, value)) in GIT_CONFIG_OVERRIDES.iter().enumerate() {
+        envs.push((format!("GIT_CONFIG_KEY_{}", index), key.to_string()));
+        envs.push((format!("GIT_CONFIG_VALUE_{}", index), value.to_string()));
+    }
+
+    envs
+}
+
+fn run_git(
+    args: Vec<&str>,
+    cwd: &Path,
+    local: bool,
+    input: Option<Vec<u8>>,
+) -> CommandExecutionResult {
     let mut all_args: Vec<&str> = vec!["--no-pager"];
     all_args.extend(args);
 
@@ -75,5 +412,34 @@ pub fn git_command(args: Vec<&str>, cwd: &Path) -> CommandExecutionResult {
         cmd: "git",
         args: Some(all_args),
         cwd: &cwd.to_path_buf(),
+        envs: git_envs(),
+        removed_envs: GIT_REPOSITORY_ENV_VARS.to_vec(),
+        timeout: match local {
+            true => timeout(Some(DEFAULT_TIMEOUT)),
+            false => timeout(None),
+        },
+        input,
+        own_process_group: local,
     })
 }
+
+// For local commands, which never ask the user anything
+pub fn git_command(args: Vec<&str>, cwd: &Path) -> CommandExecutionResult {
+    run_git(args, cwd, true, None)
+}
+
+// For local commands that read their input from stdin, like `patch-id`
+pub fn git_command_with_input(
+    args: Vec<&str>,
+    input: Vec<u8>,
+    cwd: &Path,
+) -> CommandExecutionResult {
+    run_git(args, cwd, true, Some(input))
+}
+
+// For commands that talk to a remote (clone, fetch, ...), where ssh or a credential helper may
+// prompt on the terminal. Ctrl-C stops them as well, callers check `signals::interrupted()`
+// afterwards to tell that apart from a failure. They only time out when a timeout was set.
+pub fn git_remote_command(args: Vec<&str>, cwd: &Path) -> CommandExecutionResult {
+    run_git(args, cwd, false, None)
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+use super::CommandOutput;
+
+#[cfg(test)]
+fn output(stdout: &[u8], stderr: &[u8], status: Option<i32>) -> CommandOutput {
+    CommandOutput {
+        status,
+        stdout: stdout.to_vec(),
+        stderr: stderr.to_vec(),
+    }
+}
+
+#[test]
+fn test_single_character_lines_are_kept() {
+    let output = output(b"main\nx\n", b"", Some(0));
+
+    assert_eq!(vec!["main", "x"], output.stdout_lines());
+}
+
+#[test]
+fn test_whitespace_within_lines_is_kept() {
+    let output = output(b"  main\n* some work \n", b"", Some(0));
+
+    assert_eq!(vec!["  main", "* some work "], output.stdout_lines());
+}
+
+#[test]
+fn test_empty_output_has_no_lines() {
+    let output = output(b"", b"", Some(0));
+
+    assert!(output.stdout_lines().is_empty());
+    assert!(output.is_stdout_empty());
+    assert_eq!(None, output.stdout_value());
+}
+
+#[test]
+fn test_blank_lines_in_the_middle_of_output_are_kept() {
+    let output = output(b"first\n\nthird\n", b"", Some(0));
+
+    assert_eq!(vec!["first", "", "third"], output.stdout_lines());
+}
+
+#[test]
+fn test_carriage_returns_are_removed_from_line_endings() {
+    let output = output(b"first\r\nsecond\r\n", b"", Some(0));
+
+    assert_eq!(vec!["first", "second"], output.stdout_lines());
+}
+
+#[test]
+fn test_raw_bytes_are_preserved_for_non_utf8_output() {
+    let output = output(&[0xff, b'\n'], b"", Some(0));
+
+    assert_eq!(vec![0xff, b'\n'], output.stdout);
+    assert_eq!(vec!["\u{fffd}"], output.stdout_lines());
+}
+
+#[test]
+fn test_error_message_prefers_stderr() {
+    let output = output(b"", b"fatal: not a git repository\n", Some(128));
+
+    assert_eq!("fatal: not a git repository", output.error_message());
+}
+
+#[test]
+fn test_error_message_falls_back_to_the_exit_status() {
+    assert_eq!(
+        "exited with status 1",
+        output(b"", b"", Some(1)).error_message()
+    );
+    assert_eq!(
+        "terminated by a signal",
+        output(b"", b"", None).error_message()
+    );
+}
+
+#[test]
+fn test_commands_that_run_too_long_are_killed() {
+    let result = super::run_command(super::CommandConfiguration {
+        cmd: "sleep",
+        args: Some(vec!["5"]),
+        cwd: &std::env::temp_dir(),
+        envs: vec![],
+        removed_envs: vec![],
+        timeout: Some(std::time::Duration::from_millis(100)),
+        input: None,
+        own_process_group: true,
+    });
+
+    let output = result.expect_err("The command should have timed out");
+    assert_eq!(None, output.status);
+    assert!(output
+        .error_message()
+        .contains("killed after running for longer"));
+}
+
+#[test]
+fn test_input_is_written_to_stdin() {
+    let result = super::run_command(super::CommandConfiguration {
+        cmd: "cat",
+        args: None,
+        cwd: &std::env::temp_dir(),
+        envs: vec![],
+        removed_envs: vec![],
+        timeout: None,
+        input: Some(b"first\nsecond\n".to_vec()),
+        own_process_group: true,
+    });
+
+    assert_eq!(
+        vec!["first", "second"],
+        result.expect("cat failed").stdout_lines()
+    );
+}
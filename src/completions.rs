@@ -0,0 +1,229 @@
+use std::{fs, path::Path};
+
+use crate::{
+    cli::{CommandSpec, OptionSpec, Values, COMMANDS, GLOBAL_OPTION_SPECS},
+    repository::{all_branch_names, Repository},
+    shell::SUPPORTED_SHELLS,
+};
+
+#[cfg(test)]
+mod tests;
+
+// The scripts only pass the words on the command line to `git-tools __complete`, which prints one
+// `candidate<TAB>description` line per candidate, so branch names and worktree paths are always
+// current. Directories end with a slash and don't get a space after them.
+const BASH_COMPLETIONS: &str = r#"_git_tools() {
+    local IFS=$'\n'
+    COMPREPLY=($(command git-tools __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null | cut -f1))
+
+    if [[ ${#COMPREPLY[@]} -eq 1 && ${COMPREPLY[0]} == */ ]]; then
+        compopt -o nospace
+    fi
+}
+complete -F _git_tools git-tools
+"#;
+
+const ZSH_COMPLETIONS: &str = r#"#compdef git-tools
+_git_tools() {
+    local line candidate
+    local -a candidates directories
+
+    for line in "${(@f)$(command git-tools __complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}"; do
+        candidate="${line%%$'\t'*}"
+        case "$candidate" in
+            '') ;;
+            */) directories+=("$candidate") ;;
+            *) candidates+=("${candidate//:/\\:}:${line#*$'\t'}") ;;
+        esac
+    done
+
+    _describe -t candidates git-tools candidates
+    (( ${#directories} )) && compadd -S '' -- "${directories[@]}"
+}
+compdef _git_tools git-tools
+"#;
+
+const FISH_COMPLETIONS: &str = r#"function __git_tools_complete
+    set -l words (commandline -opc)[2..-1] (commandline -ct)
+    command git-tools __complete -- $words 2>/dev/null
+end
+complete -c git-tools -f -a '(__git_tools_complete)'
+"#;
+
+pub fn completion_script(shell: &str) -> Result<&'static str, String> {
+    match shell {
+        "bash" => Ok(BASH_COMPLETIONS),
+        "zsh" => Ok(ZSH_COMPLETIONS),
+        "fish" => Ok(FISH_COMPLETIONS),
+        _ => Err(format!(
+            "Unsupported shell '{}', expected one of: {}",
+            shell,
+            SUPPORTED_SHELLS.join(", ")
+        )),
+    }
+}
+
+fn find_option(name: &str) -> Option<&'static OptionSpec> {
+    GLOBAL_OPTION_SPECS
+        .iter()
+        .chain(COMMANDS.iter().flat_map(|command| command.options))
+        .find(|option| option.name == name)
+}
+
+// Directories that complete `current`, relative to `cwd` like the shell would
+fn directories(cwd: &Path, current: &str) -> Vec<(String, String)> {
+    let (parent, prefix) = match current.rfind('/') {
+        Some(index) => current.split_at(index + 1),
+        None => ("", current),
+    };
+    let entries = match fs::read_dir(cwd.join(parent)) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut directories = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        // hidden directories only when asked for
+        .filter(|name| !name.starts_with('.') || prefix.starts_with('.'))
+        .map(|name| (format!("{}{}/", parent, name), String::new()))
+        .collect::<Vec<(String, String)>>();
+    directories.sort();
+
+    directories
+}
+
+fn candidates_for(
+    values: Values,
+    repo_path: &Path,
+    cwd: &Path,
+    current: &str,
+) -> Vec<(String, String)> {
+    let repo = || Repository::at(repo_path).ok();
+
+    match values {
+        Values::Anything => vec![],
+        Values::Words(words) => words
+            .iter()
+            .map(|word| (word.to_string(), String::new()))
+            .collect(),
+        Values::Branches => match repo() {
+            Some(repo) => all_branch_names(repo.root())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|branch| (branch, String::new()))
+                .collect(),
+            None => vec![],
+        },
+        Values::Directories => directories(cwd, current),
+        Values::Worktrees => {
+            let mut candidates = match repo() {
+                Some(Repository::Bare(bare)) => bare
+                    .all_worktrees()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|worktree| (worktree.path.to_string(), worktree.name))
+                    .collect(),
+                _ => vec![],
+            };
+            candidates.extend(directories(cwd, current));
+
+            candidates
+        }
+    }
+}
+
+// The next word after `positional` for every command that it is the start of, with the
+// command's description or, for groups like `worktree`, their subcommands
+fn next_words(positional: &[&str]) -> Vec<(String, String)> {
+    let mut candidates: Vec<(String, String)> = vec![];
+    let starting_with = |words: &[&str]| {
+        COMMANDS
+            .iter()
+            .filter(|command| command.words.starts_with(words))
+            .collect::<Vec<&CommandSpec>>()
+    };
+
+    for command in starting_with(positional) {
+        let word = match command.words.get(positional.len()) {
+            Some(word) => *word,
+            None => continue,
+        };
+        if candidates.iter().any(|(candidate, _)| candidate == word) {
+            continue;
+        }
+
+        let words = [positional, &[word]].concat();
+        let description = match command.words.len() == words.len() {
+            true => command.description.to_string(),
+            false => starting_with(&words)
+                .iter()
+                .map(|command| command.words[words.len()])
+                .collect::<Vec<&str>>()
+                .join(", "),
+        };
+        candidates.push((word.to_string(), description));
+    }
+
+    candidates
+}
+
+// Candidates for the last of `words`, the one being completed. `cwd` is the directory the shell is
+// in, branches and worktrees come from the repository there or the one given with `--repo`.
+pub fn complete(words: &[String], cwd: &Path) -> Vec<(String, String)> {
+    let (current, before) = match words.split_last() {
+        Some(split) => split,
+        None => return vec![],
+    };
+    let mut positional: Vec<&str> = vec![];
+    let mut repo_path = cwd.to_path_buf();
+    // the option whose value is being completed
+    let mut pending: Option<Values> = None;
+    let mut index = 0;
+
+    while index < before.len() {
+        let word = before[index].as_str();
+
+        match find_option(word).and_then(|option| option.value) {
+            Some(values) => match before.get(index + 1) {
+                Some(value) => {
+                    if word == "--repo" {
+                        repo_path = cwd.join(value);
+                    }
+                    index += 1;
+                }
+                None => pending = Some(values),
+            },
+            None if word.starts_with('-') => {}
+            None => positional.push(word),
+        }
+        index += 1;
+    }
+
+    let command = COMMANDS
+        .iter()
+        .find(|command| positional.starts_with(command.words));
+
+    let candidates = match (pending, command) {
+        (Some(values), _) => candidates_for(values, &repo_path, cwd, current),
+        (None, _) if current.starts_with('-') => GLOBAL_OPTION_SPECS
+            .iter()
+            .chain(command.map_or(&[][..], |command| command.options))
+            .map(|option| (option.name.to_string(), String::new()))
+            .collect(),
+        (None, Some(command)) => match command
+            .arguments
+            .get(positional.len() - command.words.len())
+        {
+            Some(argument) => candidates_for(argument.values, &repo_path, cwd, current),
+            None => vec![],
+        },
+        (None, None) => next_words(&positional),
+    };
+
+    candidates
+        .into_iter()
+        .filter(|(candidate, _)| candidate.starts_with(current.as_str()))
+        .collect()
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+use std::path::Path;
+
+#[cfg(test)]
+use super::{complete, completion_script};
+
+#[cfg(test)]
+use crate::{
+    repository::Repository, shell::SUPPORTED_SHELLS, test_helpers::run_test,
+    test_setup::BARE_REPO_NAME,
+};
+
+#[cfg(test)]
+fn candidates(words: &[&str], cwd: &Path) -> Vec<String> {
+    let words = words
+        .iter()
+        .map(|word| word.to_string())
+        .collect::<Vec<String>>();
+
+    complete(&words, cwd)
+        .into_iter()
+        .map(|(candidate, _)| candidate)
+        .collect()
+}
+
+#[test]
+fn test_completes_commands_and_subcommands() {
+    let cwd = Path::new("/nonexistent");
+
+    assert_eq!(candidates(&["wor"], cwd), vec!["worktree", "workspace"]);
+    assert_eq!(
+        candidates(&["worktree", ""], cwd),
+        vec!["add", "rename", "list"]
+    );
+    assert_eq!(
+        candidates(&["-v", "--repo", "x", "config", ""], cwd),
+        vec!["show"]
+    );
+    assert_eq!(
+        complete(&["workspace".to_string()], cwd),
+        vec![("workspace".to_string(), "sync".to_string())]
+    );
+}
+
+#[test]
+fn test_completes_options_and_their_values() {
+    let cwd = Path::new("/nonexistent");
+
+    assert_eq!(
+        candidates(&["worktree", "list", "--j"], cwd),
+        vec!["--json", "--jobs"]
+    );
+    assert_eq!(
+        candidates(&["--"], cwd),
+        vec!["--main-branch", "--repo", "--verbose", "--help"]
+    );
+    assert_eq!(
+        candidates(&["worktree", "list", "--format", ""], cwd),
+        vec!["table", "csv", "json"]
+    );
+    assert_eq!(candidates(&["completions", ""], cwd), SUPPORTED_SHELLS);
+    assert!(candidates(&["sync", "--jobs", ""], cwd).is_empty());
+}
+
+#[test]
+fn test_completes_branch_names() {
+    run_test("test_completes_branch_names", "clean_repo", |repo| {
+        let root = repo.root();
+
+        assert_eq!(candidates(&["switch", "m"], root), vec!["main", "merged"]);
+        assert_eq!(
+            candidates(&["worktree", "add", "x", "--from", "un"], root),
+            vec!["unmerged"]
+        );
+
+        // `--repo` picks the repository the names come from
+        let parent = root.parent().unwrap();
+        assert_eq!(
+            candidates(&["--repo", "clean_repo", "switch", "un"], parent),
+            vec!["unmerged"]
+        );
+    });
+}
+
+#[test]
+fn test_completes_worktree_paths_and_directories() {
+    run_test(
+        "test_completes_worktree_paths_and_directories",
+        BARE_REPO_NAME,
+        |repo| {
+            let worktrees = match &repo {
+                Repository::Bare(bare) => bare.all_worktrees().unwrap(),
+                Repository::Normal(_) => panic!("expected a bare repository"),
+            };
+            let completed = candidates(&["--repo", ""], repo.root());
+
+            for worktree in worktrees {
+                assert!(completed.contains(&worktree.path.to_string()));
+            }
+            assert!(completed.contains(&"refs/".to_string()));
+            assert_eq!(candidates(&["--repo", "ref"], repo.root()), vec!["refs/"]);
+        },
+    );
+}
+
+#[test]
+fn test_every_supported_shell_has_a_script() {
+    for shell in SUPPORTED_SHELLS {
+        assert!(completion_script(shell)
+            .expect("Missing completion script")
+            .contains("git-tools __complete"));
+    }
+    assert!(completion_script("powershell").is_err());
+}
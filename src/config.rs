@@ -0,0 +1,487 @@
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{
+    commands::git_command,
+    glob::matches_component,
+    logging::warning,
+    toml::{self, TomlTable, TomlValue},
+    worktree_path::DEFAULT_PATH_TEMPLATE,
+};
+
+#[cfg(test)]
+mod tests;
+
+// Settings are looked up in this order, later ones win:
+//   1. the defaults
+//   2. the user's config file, e.g. `mainBranchCandidates = ["trunk", "main"]` or
+//      `[hooks]` followed by `preRemove = ["make clean"]`
+//   3. git config (repository, global or system), e.g. `git config git-tools.mainBranch trunk`
+//   4. command line flags
+// Settings that hold a list take every value of a multi-valued git config key.
+pub const MAIN_BRANCH: &str = "mainBranch";
+pub const MAIN_BRANCH_CANDIDATES: &str = "mainBranchCandidates";
+pub const PROTECTED_BRANCHES: &str = "protectedBranches";
+pub const WORKTREE_PATH_TEMPLATE: &str = "worktreePathTemplate";
+pub const PRE_REMOVE_HOOKS: &str = "hooks.preRemove";
+pub const POST_REMOVE_HOOKS: &str = "hooks.postRemove";
+pub const OUTPUT_FORMAT: &str = "output.format";
+pub const BOOTSTRAP_COPY: &str = "bootstrap.copy";
+pub const BOOTSTRAP_SYMLINK: &str = "bootstrap.symlink";
+pub const BOOTSTRAP_RUN: &str = "bootstrap.run";
+
+const SETTINGS: [&str; 10] = [
+    MAIN_BRANCH,
+    MAIN_BRANCH_CANDIDATES,
+    PROTECTED_BRANCHES,
+    WORKTREE_PATH_TEMPLATE,
+    PRE_REMOVE_HOOKS,
+    POST_REMOVE_HOOKS,
+    OUTPUT_FORMAT,
+    BOOTSTRAP_COPY,
+    BOOTSTRAP_SYMLINK,
+    BOOTSTRAP_RUN,
+];
+
+const DEFAULT_MAIN_BRANCH_CANDIDATES: [&str; 2] = ["main", "master"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+impl TryFrom<&str> for OutputFormat {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "table" => Ok(Self::Table),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "Unknown output format '{}' (expected table, csv or json)",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Table => write!(f, "table"),
+            Self::Csv => write!(f, "csv"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    UserFile(PathBuf),
+    GitConfig(String),
+    CommandLine(&'static str),
+    // Worked out from the repository, e.g. the main branch
+    Detected,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::UserFile(path) => write!(f, "{}", path.to_string_lossy()),
+            Self::GitConfig(key) => write!(f, "git config {}", key),
+            Self::CommandLine(flag) => write!(f, "{}", flag),
+            Self::Detected => write!(f, "detected"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    // None to pick the first of the candidates that exists
+    pub main_branch: Setting<Option<String>>,
+    pub main_branch_candidates: Setting<Vec<String>>,
+    // Patterns (`*` and `?`) of branches that clean-merged-branches never deletes
+    pub protected_branches: Setting<Vec<String>>,
+    pub worktree_path_template: Setting<String>,
+    pub pre_remove_hooks: Setting<Vec<String>>,
+    pub post_remove_hooks: Setting<Vec<String>>,
+    pub output_format: Setting<OutputFormat>,
+    // What new worktrees are prepared with, see `bootstrap`
+    pub bootstrap_copy: Setting<Vec<String>>,
+    pub bootstrap_symlink: Setting<Vec<String>>,
+    pub bootstrap_run: Setting<Vec<String>>,
+}
+
+// What the command line flags set, see `set_overrides`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Overrides {
+    pub main_branch: Option<String>,
+    pub worktree_path_template: Option<String>,
+    pub output_format: Option<(OutputFormat, &'static str)>,
+}
+
+static OVERRIDES: Mutex<Overrides> = Mutex::new(Overrides {
+    main_branch: None,
+    worktree_path_template: None,
+    output_format: None,
+});
+
+// The config is loaded by several steps of a command, each problem is only reported once
+static REPORTED: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+fn warn_once(msg: String) {
+    let mut reported = REPORTED.lock().unwrap();
+
+    if !reported.contains(&msg) {
+        warning!("{}", msg);
+        reported.push(msg);
+    }
+}
+
+pub fn set_overrides(overrides: Overrides) {
+    *OVERRIDES.lock().unwrap() = overrides;
+}
+
+// `$XDG_CONFIG_HOME/git-tools/config.toml`, by default in `~/.config`
+pub fn user_config_path() -> Option<PathBuf> {
+    let config_home = match env::var("XDG_CONFIG_HOME") {
+        Ok(directory) if !directory.is_empty() => PathBuf::from(directory),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+    };
+
+    Some(config_home.join("git-tools").join("config.toml"))
+}
+
+// The values of a setting in the user's config file. Lists can also be given as a single string.
+fn file_values(table: &TomlTable, name: &str) -> Option<Result<Vec<String>, String>> {
+    let (path, key) = name.rsplit_once('.').unwrap_or(("", name));
+    let mut table = table;
+
+    for part in path.split('.').filter(|part| !part.is_empty()) {
+        table = toml::get(table, part)?.as_table()?;
+    }
+
+    Some(match toml::get(table, key)? {
+        TomlValue::String(value) => Ok(vec![value.clone()]),
+        TomlValue::Array(values) => values
+            .iter()
+            .map(|value| {
+                value
+                    .as_str()
+                    .map(String::from)
+                    .ok_or_else(|| "expected strings".to_string())
+            })
+            .collect(),
+        value => Err(format!("expected a string, not {}", value.type_name())),
+    })
+}
+
+// Dotted names of every value in the file, to spot typos
+fn file_keys(table: &TomlTable, prefix: &str) -> Vec<String> {
+    table
+        .iter()
+        .flat_map(|(key, value)| {
+            let name = format!("{}{}", prefix, key);
+
+            match value {
+                TomlValue::Table(table) => file_keys(table, &format!("{}.", name)),
+                _ => vec![name],
+            }
+        })
+        .collect()
+}
+
+// `git config --get-regexp` prints `key value` with the section and key names in lower case
+fn parse_git_config(lines: &[String]) -> Vec<(String, String)> {
+    lines
+        .iter()
+        .map(|line| {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            (key.to_lowercase(), value.to_string())
+        })
+        .collect()
+}
+
+struct Layers {
+    user_file: Option<(PathBuf, TomlTable)>,
+    git_config: Vec<(String, String)>,
+    overrides: Overrides,
+}
+
+impl Layers {
+    fn load(user_file: Option<&Path>, root: Option<&Path>) -> Self {
+        let user_file = user_file.filter(|path| path.is_file()).and_then(|path| {
+            let text = match fs::read_to_string(path) {
+                Ok(text) => text,
+                Err(err) => {
+                    warn_once(format!("Ignoring {:?}: {}", path, err));
+                    return None;
+                }
+            };
+            // a mistake only costs the setting it is in
+            let (table, errors) = toml::parse_skipping_errors(&text);
+
+            for msg in errors {
+                warn_once(format!("Ignoring part of {:?}, {}", path, msg));
+            }
+            for key in file_keys(&table, "") {
+                if !SETTINGS.contains(&key.as_str()) {
+                    warn_once(format!("Ignoring unknown setting '{}' in {:?}", key, path));
+                }
+            }
+
+            Some((path.to_path_buf(), table))
+        });
+
+        let cwd = root
+            .map(Path::to_path_buf)
+            .or_else(|| env::current_dir().ok());
+        let git_config = cwd
+            .and_then(|cwd| {
+                git_command(
+                    vec!["config", "--includes", "--get-regexp", "^git-tools\\."],
+                    &cwd,
+                )
+                .ok()
+            })
+            .map(|result| parse_git_config(&result.stdout_lines()))
+            .unwrap_or_default();
+
+        for (key, _) in &git_config {
+            let name = key.trim_start_matches("git-tools.");
+            let known = SETTINGS
+                .iter()
+                .any(|setting| setting.to_lowercase() == name);
+
+            if !known {
+                warn_once(format!("Ignoring unknown setting '{}' in git config", key));
+            }
+        }
+
+        Self {
+            user_file,
+            git_config,
+            overrides: OVERRIDES.lock().unwrap().clone(),
+        }
+    }
+
+    fn resolve<T>(
+        &self,
+        name: &str,
+        default: T,
+        parse: impl Fn(Vec<String>) -> Result<T, String>,
+    ) -> Setting<T> {
+        let mut setting = Setting {
+            value: default,
+            source: Source::Default,
+        };
+
+        if let Some((path, table)) = &self.user_file {
+            match file_values(table, name).map(|values| values.and_then(&parse)) {
+                Some(Ok(value)) => {
+                    setting = Setting {
+                        value,
+                        source: Source::UserFile(path.clone()),
+                    }
+                }
+                Some(Err(msg)) => warn_once(format!("Ignoring '{}' in {:?}: {}", name, path, msg)),
+                None => {}
+            }
+        }
+
+        let git_key = format!("git-tools.{}", name);
+        let values = self
+            .git_config
+            .iter()
+            .filter(|(key, _)| *key == git_key.to_lowercase())
+            .map(|(_, value)| value.clone())
+            .collect::<Vec<String>>();
+
+        if !values.is_empty() {
+            match parse(values) {
+                Ok(value) => {
+                    setting = Setting {
+                        value,
+                        source: Source::GitConfig(git_key),
+                    }
+                }
+                Err(msg) => warn_once(format!("Ignoring git config {}: {}", git_key, msg)),
+            }
+        }
+
+        setting
+    }
+}
+
+fn single(values: Vec<String>) -> Result<String, String> {
+    values
+        .last()
+        .cloned()
+        .ok_or_else(|| "expected a value".to_string())
+}
+
+fn overridden<T>(setting: Setting<T>, value: Option<T>, flag: &'static str) -> Setting<T> {
+    match value {
+        Some(value) => Setting {
+            value,
+            source: Source::CommandLine(flag),
+        },
+        None => setting,
+    }
+}
+
+impl Config {
+    // The configuration for the repository at `root`, or outside of any repository when None
+    pub fn load(root: Option<&Path>) -> Self {
+        Self::load_with(user_config_path().as_deref(), root)
+    }
+
+    pub fn load_with(user_file: Option<&Path>, root: Option<&Path>) -> Self {
+        let layers = Layers::load(user_file, root);
+        let overrides = layers.overrides.clone();
+        let list = |values: Vec<String>| Ok(values);
+
+        Self {
+            main_branch: overridden(
+                layers.resolve(MAIN_BRANCH, None, |values| single(values).map(Some)),
+                overrides.main_branch.map(Some),
+                "--main-branch",
+            ),
+            main_branch_candidates: layers.resolve(
+                MAIN_BRANCH_CANDIDATES,
+                DEFAULT_MAIN_BRANCH_CANDIDATES
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect(),
+                list,
+            ),
+            protected_branches: layers.resolve(PROTECTED_BRANCHES, vec![], list),
+            worktree_path_template: overridden(
+                layers.resolve(
+                    WORKTREE_PATH_TEMPLATE,
+                    DEFAULT_PATH_TEMPLATE.to_string(),
+                    single,
+                ),
+                overrides.worktree_path_template,
+                "--path-template",
+            ),
+            pre_remove_hooks: layers.resolve(PRE_REMOVE_HOOKS, vec![], list),
+            post_remove_hooks: layers.resolve(POST_REMOVE_HOOKS, vec![], list),
+            output_format: match overrides.output_format {
+                Some((format, flag)) => Setting {
+                    value: format,
+                    source: Source::CommandLine(flag),
+                },
+                None => layers.resolve(OUTPUT_FORMAT, OutputFormat::Table, |values| {
+                    OutputFormat::try_from(single(values)?.as_str())
+                }),
+            },
+            bootstrap_copy: layers.resolve(BOOTSTRAP_COPY, vec![], list),
+            bootstrap_symlink: layers.resolve(BOOTSTRAP_SYMLINK, vec![], list),
+            bootstrap_run: layers.resolve(BOOTSTRAP_RUN, vec![], list),
+        }
+    }
+
+    // The configured main branch, or else the first candidate in `branches`
+    pub fn main_branch_in(&self, branches: &[String]) -> Option<String> {
+        self.main_branch.value.clone().or_else(|| {
+            self.main_branch_candidates
+                .value
+                .iter()
+                .find(|candidate| branches.contains(candidate))
+                .cloned()
+        })
+    }
+
+    pub fn is_protected(&self, branch: &str) -> bool {
+        self.protected_branches
+            .value
+            .iter()
+            .any(|pattern| matches_component(pattern, branch))
+    }
+
+    // Every setting as (name, value, source), lists are comma separated
+    // `detected_main_branch` is shown when no main branch is configured, it is the one the
+    // repository would use
+    pub fn entries(
+        &self,
+        detected_main_branch: Option<String>,
+    ) -> Vec<(&'static str, String, &Source)> {
+        let list = |values: &Vec<String>| match values.is_empty() {
+            true => "(none)".to_string(),
+            false => values.join(", "),
+        };
+        let (main_branch, main_branch_source) =
+            match (&self.main_branch.value, detected_main_branch) {
+                (Some(main_branch), _) => (main_branch.clone(), &self.main_branch.source),
+                (None, Some(detected)) => (detected, &Source::Detected),
+                (None, None) => (
+                    "(first existing candidate)".to_string(),
+                    &self.main_branch.source,
+                ),
+            };
+
+        vec![
+            (MAIN_BRANCH, main_branch, main_branch_source),
+            (
+                MAIN_BRANCH_CANDIDATES,
+                list(&self.main_branch_candidates.value),
+                &self.main_branch_candidates.source,
+            ),
+            (
+                PROTECTED_BRANCHES,
+                list(&self.protected_branches.value),
+                &self.protected_branches.source,
+            ),
+            (
+                WORKTREE_PATH_TEMPLATE,
+                self.worktree_path_template.value.clone(),
+                &self.worktree_path_template.source,
+            ),
+            (
+                PRE_REMOVE_HOOKS,
+                list(&self.pre_remove_hooks.value),
+                &self.pre_remove_hooks.source,
+            ),
+            (
+                POST_REMOVE_HOOKS,
+                list(&self.post_remove_hooks.value),
+                &self.post_remove_hooks.source,
+            ),
+            (
+                OUTPUT_FORMAT,
+                self.output_format.value.to_string(),
+                &self.output_format.source,
+            ),
+            (
+                BOOTSTRAP_COPY,
+                list(&self.bootstrap_copy.value),
+                &self.bootstrap_copy.source,
+            ),
+            (
+                BOOTSTRAP_SYMLINK,
+                list(&self.bootstrap_symlink.value),
+                &self.bootstrap_symlink.source,
+            ),
+            (
+                BOOTSTRAP_RUN,
+                list(&self.bootstrap_run.value),
+                &self.bootstrap_run.source,
+            ),
+        ]
+    }
+}
@@ -0,0 +1,238 @@
+#[cfg(test)]
+use std::fs;
+
+#[cfg(test)]
+use super::{overridden, parse_git_config, Config, OutputFormat, Setting, Source, MAIN_BRANCH};
+
+#[cfg(test)]
+use crate::{commands::git_command, test_helpers::run_test, worktree_path::DEFAULT_PATH_TEMPLATE};
+
+#[cfg(test)]
+fn setting<T>(value: T, source: Source) -> Setting<T> {
+    Setting { value, source }
+}
+
+#[test]
+fn test_later_layers_win() {
+    run_test("test_later_layers_win", "clean_repo", |repo| {
+        let user_file = repo.root().parent().unwrap().join("config.toml");
+        fs::write(
+            &user_file,
+            r#"
+mainBranchCandidates = ["trunk", "main"]
+protectedBranches = "release/*"
+worktreePathTemplate = "{root}/../{branch}"
+
+[hooks]
+preRemove = ["make clean"]
+
+[output]
+format = "json"
+
+[bootstrap]
+copy = ".env"
+run = ["make setup"]
+"#,
+        )
+        .unwrap();
+        for value in ["release/*", "production"] {
+            git_command(
+                vec!["config", "--add", "git-tools.protectedBranches", value],
+                repo.root(),
+            )
+            .unwrap();
+        }
+        git_command(
+            vec!["config", "git-tools.bootstrap.run", "npm run setup"],
+            repo.root(),
+        )
+        .unwrap();
+
+        let config = Config::load_with(Some(&user_file), Some(repo.root()));
+        let from_file = || Source::UserFile(user_file.clone());
+
+        assert_eq!(config.main_branch, setting(None, Source::Default));
+        assert_eq!(
+            config.main_branch_candidates,
+            setting(vec!["trunk".to_string(), "main".to_string()], from_file())
+        );
+        assert_eq!(
+            config.protected_branches,
+            setting(
+                vec!["release/*".to_string(), "production".to_string()],
+                Source::GitConfig("git-tools.protectedBranches".to_string())
+            )
+        );
+        assert_eq!(
+            config.worktree_path_template,
+            setting("{root}/../{branch}".to_string(), from_file())
+        );
+        assert_eq!(
+            config.pre_remove_hooks,
+            setting(vec!["make clean".to_string()], from_file())
+        );
+        assert_eq!(config.post_remove_hooks, setting(vec![], Source::Default));
+        assert_eq!(
+            config.output_format,
+            setting(OutputFormat::Json, from_file())
+        );
+        assert_eq!(
+            config.bootstrap_copy,
+            setting(vec![".env".to_string()], from_file())
+        );
+        assert_eq!(config.bootstrap_symlink, setting(vec![], Source::Default));
+        assert_eq!(
+            config.bootstrap_run,
+            setting(
+                vec!["npm run setup".to_string()],
+                Source::GitConfig("git-tools.bootstrap.run".to_string())
+            )
+        );
+    });
+}
+
+#[test]
+fn test_invalid_values_are_ignored() {
+    run_test("test_invalid_values_are_ignored", "clean_repo", |repo| {
+        let user_file = repo.root().parent().unwrap().join("config.toml");
+        fs::write(&user_file, "worktreePathTemplate = 1\n").unwrap();
+        git_command(
+            vec!["config", "git-tools.output.format", "yaml"],
+            repo.root(),
+        )
+        .unwrap();
+
+        let config = Config::load_with(Some(&user_file), Some(repo.root()));
+
+        assert_eq!(
+            config.worktree_path_template,
+            setting(DEFAULT_PATH_TEMPLATE.to_string(), Source::Default)
+        );
+        assert_eq!(
+            config.output_format,
+            setting(OutputFormat::Table, Source::Default)
+        );
+    });
+}
+
+#[test]
+fn test_dotted_keys_work_and_mistakes_only_cost_their_setting() {
+    run_test(
+        "test_dotted_keys_work_and_mistakes_only_cost_their_setting",
+        "clean_repo",
+        |repo| {
+            let user_file = repo.root().parent().unwrap().join("config.toml");
+            fs::write(
+                &user_file,
+                r#"
+mainBranch = "trunk"
+hooks.preRemove = ["make clean"]
+output = { format = "csv" }
+protectedBranches = ["release/*"
+"#,
+            )
+            .unwrap();
+
+            let config = Config::load_with(Some(&user_file), Some(repo.root()));
+            let from_file = || Source::UserFile(user_file.clone());
+
+            assert_eq!(
+                config.main_branch,
+                setting(Some("trunk".to_string()), from_file())
+            );
+            assert_eq!(
+                config.pre_remove_hooks,
+                setting(vec!["make clean".to_string()], from_file())
+            );
+            assert_eq!(
+                config.output_format,
+                setting(OutputFormat::Csv, from_file())
+            );
+            assert_eq!(config.protected_branches, setting(vec![], Source::Default));
+        },
+    );
+}
+
+#[test]
+fn test_entries_show_the_detected_main_branch_unless_one_is_configured() {
+    let mut config = Config::load_with(None, Some(&std::env::temp_dir()));
+    let main_branch = |config: &Config, detected: Option<&str>| {
+        config
+            .entries(detected.map(String::from))
+            .into_iter()
+            .find(|(name, _, _)| *name == MAIN_BRANCH)
+            .map(|(_, value, source)| (value, source.clone()))
+            .unwrap()
+    };
+
+    assert_eq!(
+        main_branch(&config, Some("trunk")),
+        ("trunk".to_string(), Source::Detected)
+    );
+    assert_eq!(
+        main_branch(&config, None),
+        ("(first existing candidate)".to_string(), Source::Default)
+    );
+
+    config.main_branch = setting(
+        Some("develop".to_string()),
+        Source::CommandLine("--main-branch"),
+    );
+    assert_eq!(
+        main_branch(&config, Some("trunk")),
+        ("develop".to_string(), Source::CommandLine("--main-branch"))
+    );
+}
+
+#[test]
+fn test_command_line_flags_win() {
+    let from_git = setting(
+        "{root}/{branch}".to_string(),
+        Source::GitConfig("git-tools.worktreePathTemplate".to_string()),
+    );
+
+    assert_eq!(
+        overridden(from_git.clone(), Some("x".to_string()), "--path-template"),
+        setting("x".to_string(), Source::CommandLine("--path-template"))
+    );
+    assert_eq!(
+        overridden(from_git.clone(), None, "--path-template"),
+        from_git
+    );
+}
+
+#[test]
+fn test_finds_the_main_branch_and_protected_branches() {
+    let mut config = Config::load_with(None, None);
+    config.main_branch.value = None;
+    config.main_branch_candidates.value = vec!["main".to_string(), "master".to_string()];
+    config.protected_branches.value = vec!["release/*".to_string(), "prod".to_string()];
+
+    assert!(config.is_protected("release/1.0"));
+    assert!(config.is_protected("prod"));
+    assert!(!config.is_protected("production"));
+
+    let branches = vec!["feature".to_string(), "master".to_string()];
+    assert_eq!(config.main_branch_in(&branches), Some("master".to_string()));
+    assert_eq!(config.main_branch_in(&["feature".to_string()]), None);
+
+    config.main_branch.value = Some("trunk".to_string());
+    assert_eq!(config.main_branch_in(&branches), Some("trunk".to_string()));
+}
+
+#[test]
+fn test_parses_git_config_output() {
+    assert_eq!(
+        parse_git_config(&[
+            "git-tools.hooks.preremove make clean".to_string(),
+            "git-tools.flag".to_string(),
+        ]),
+        vec![
+            (
+                "git-tools.hooks.preremove".to_string(),
+                "make clean".to_string()
+            ),
+            ("git-tools.flag".to_string(), String::new()),
+        ]
+    );
+}
@@ -0,0 +1,373 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    commands::git_command,
+    logging::{debug, warning},
+    repo_location::RepoLocation,
+    utils::{get_current_branch_name, get_git_config_value, ref_exists},
+    worktree_path::expand_path_template,
+    worktree_status::worktree_entries,
+};
+
+#[cfg(test)]
+mod tests;
+
+// The converted layout keeps the repository in `<root>/.bare` and points `<root>/.git` at it, so
+// every git command run inside `<root>` still finds it
+pub const BARE_DIRECTORY_NAME: &str = ".bare";
+
+// Files that exist inside a git directory while an operation is waiting to be continued
+const IN_PROGRESS_MARKERS: [&str; 6] = [
+    "MERGE_HEAD",
+    "CHERRY_PICK_HEAD",
+    "REVERT_HEAD",
+    "BISECT_LOG",
+    "rebase-merge",
+    "rebase-apply",
+];
+
+// Everything that has to look the same before and after a conversion
+#[derive(Debug, PartialEq, Eq)]
+struct Snapshot {
+    refs: Vec<String>,
+    stashes: Vec<String>,
+    config: Vec<String>,
+    hooks: Vec<String>,
+    status: Vec<String>,
+}
+
+impl Snapshot {
+    // `worktree` is the checkout whose status is compared, config and refs are shared anyway
+    fn capture(worktree: &Path) -> Result<Self, String> {
+        let lines = |args: Vec<&str>| {
+            git_command(args, worktree)
+                .map(|result| result.stdout_lines())
+                .map_err(|result| result.error_message())
+        };
+        let common_dir = lines(vec![
+            "rev-parse",
+            "--path-format=absolute",
+            "--git-common-dir",
+        ])?
+        .join("");
+        let mut hooks = fs::read_dir(Path::new(&common_dir).join("hooks"))
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.file_name().to_string_lossy().to_string())
+                    .collect::<Vec<String>>()
+            })
+            .unwrap_or_default();
+        hooks.sort();
+
+        Ok(Self {
+            refs: lines(vec!["for-each-ref", "--format=%(objectname) %(refname)"])?,
+            stashes: lines(vec!["stash", "list", "--format=%H %gs"])?,
+            config: lines(vec!["config", "--local", "--list"])?
+                .into_iter()
+                .filter(|line| !line.starts_with("core.bare="))
+                .collect(),
+            hooks,
+            status: lines(vec!["status", "--porcelain=v2", "--branch"])?,
+        })
+    }
+
+    fn differences(&self, after: &Self) -> Vec<String> {
+        let mut differences: Vec<String> = vec![];
+
+        for (label, before, after) in [
+            ("ref", &self.refs, &after.refs),
+            ("stash", &self.stashes, &after.stashes),
+            ("config", &self.config, &after.config),
+            ("hook", &self.hooks, &after.hooks),
+            ("status", &self.status, &after.status),
+        ] {
+            for line in before.iter().filter(|line| !after.contains(line)) {
+                differences.push(format!("{} lost: {}", label, line));
+            }
+            for line in after.iter().filter(|line| !before.contains(line)) {
+                differences.push(format!("{} appeared: {}", label, line));
+            }
+        }
+
+        differences
+    }
+}
+
+// Turns the normal repository at `root` into a bare repository with the current checkout (including
+// uncommitted, untracked and ignored files) as its only worktree, and returns the worktree's path
+pub fn convert_to_bare(root: &Path, path_template: &str) -> Result<PathBuf, String> {
+    let git_dir = root.join(".git");
+
+    if !git_dir.is_dir() {
+        return Err(format!(
+            "{:?} is not the main worktree of a repository",
+            root
+        ));
+    }
+
+    if root.join(BARE_DIRECTORY_NAME).symlink_metadata().is_ok() {
+        return Err(format!(
+            "{:?} already exists",
+            root.join(BARE_DIRECTORY_NAME)
+        ));
+    }
+
+    check_convertible(root)?;
+
+    let branch = get_current_branch_name(root)
+        .ok_or_else(|| "Check out a branch before converting, HEAD is detached".to_string())?;
+
+    if !ref_exists(root, "HEAD") {
+        return Err(format!("'{}' doesn't have any commits yet", branch));
+    }
+
+    let worktree_path = expand_path_template(path_template, root, &branch);
+    let top_level = top_level_entry(root, &worktree_path)?;
+
+    if top_level.symlink_metadata().is_ok() {
+        return Err(format!(
+            "The worktree would be placed inside {:?}, which is part of the checkout",
+            top_level
+        ));
+    }
+
+    let before = Snapshot::capture(root)?;
+
+    if let Err(msg) = into_bare_layout(root, &branch, &worktree_path) {
+        warning!("converting failed, restoring the normal layout: {}", msg);
+
+        return match into_normal_layout(root, &worktree_path) {
+            Ok(_) => Err(format!("{}\nThe repository was restored", msg)),
+            Err(restore_msg) => Err(format!(
+                "{}\nRestoring the repository failed as well, it needs to be fixed by hand: {}",
+                msg, restore_msg
+            )),
+        };
+    }
+
+    verify(&before, &worktree_path)?;
+
+    Ok(worktree_path)
+}
+
+// The reverse of `convert_to_bare`: moves the only worktree back into `root`
+pub fn convert_to_normal(root: &Path) -> Result<(), String> {
+    let bare_dir = root.join(BARE_DIRECTORY_NAME);
+
+    if !bare_dir.is_dir() || !root.join(".git").is_file() {
+        return Err(format!(
+            "Only repositories converted with `convert-to-bare` ({:?} with a .git file) can be \
+             converted back",
+            bare_dir
+        ));
+    }
+
+    let worktrees = worktree_entries(root)?
+        .into_iter()
+        .filter(|entry| !entry.is_bare)
+        .map(|entry| PathBuf::from(entry.path))
+        .collect::<Vec<PathBuf>>();
+
+    let worktree_path = match worktrees.as_slice() {
+        [worktree_path] => worktree_path.clone(),
+        [] => return Err("There is no worktree to move into the repository root".to_string()),
+        _ => {
+            return Err(format!(
+                "Remove all but one worktree first, found {}",
+                worktrees.len()
+            ))
+        }
+    };
+
+    if !worktree_path.starts_with(root) {
+        return Err(format!("{:?} is not inside {:?}", worktree_path, root));
+    }
+
+    check_convertible(&worktree_path)?;
+
+    let before = Snapshot::capture(&worktree_path)?;
+
+    into_normal_layout(root, &worktree_path)?;
+    verify(&before, root)
+}
+
+fn check_convertible(worktree: &Path) -> Result<(), String> {
+    let location = RepoLocation::discover(worktree)?;
+
+    if let Some(marker) = IN_PROGRESS_MARKERS
+        .iter()
+        .find(|marker| location.git_dir.join(marker).exists())
+    {
+        return Err(format!(
+            "Finish or abort the operation in progress first ({} exists)",
+            marker
+        ));
+    }
+
+    // submodule checkouts point at their repository through relative paths
+    if worktree.join(".gitmodules").exists() || location.common_dir.join("modules").exists() {
+        return Err("Repositories with submodules can't be converted".to_string());
+    }
+
+    if get_git_config_value(worktree, "core.worktree").is_some() {
+        return Err("Repositories with core.worktree set can't be converted".to_string());
+    }
+
+    let worktree_count = worktree_entries(worktree)?.len();
+    let expected = match location.git_dir != location.common_dir {
+        // a linked worktree is listed after the bare repository itself
+        true => 2,
+        false => 1,
+    };
+
+    if worktree_count > expected {
+        return Err("Remove the other worktrees first".to_string());
+    }
+
+    Ok(())
+}
+
+// The directory directly inside `root` that will contain the worktree
+fn top_level_entry(root: &Path, worktree_path: &Path) -> Result<PathBuf, String> {
+    let relative = worktree_path.strip_prefix(root).map_err(|_| {
+        format!(
+            "The worktree path {:?} is outside {:?}",
+            worktree_path, root
+        )
+    })?;
+
+    match relative.components().next() {
+        Some(component) => Ok(root.join(component)),
+        None => Err("The worktree path can't be the repository root".to_string()),
+    }
+}
+
+fn git_dir_of(worktree: &Path) -> Result<PathBuf, String> {
+    git_command(
+        vec!["rev-parse", "--path-format=absolute", "--git-dir"],
+        worktree,
+    )
+    .map_err(|result| result.error_message())
+    .map(|result| PathBuf::from(result.stdout_value().unwrap_or_default()))
+}
+
+fn into_bare_layout(root: &Path, branch: &str, worktree_path: &Path) -> Result<(), String> {
+    let bare_dir = root.join(BARE_DIRECTORY_NAME);
+    let path_str = worktree_path
+        .to_str()
+        .ok_or_else(|| format!("{:?} is not a valid path", worktree_path))?;
+
+    debug!("moving {:?} to {:?}", root.join(".git"), bare_dir);
+    fs::rename(root.join(".git"), &bare_dir).map_err(|err| err.to_string())?;
+    fs::write(
+        root.join(".git"),
+        format!("gitdir: ./{}\n", BARE_DIRECTORY_NAME),
+    )
+    .map_err(|err| err.to_string())?;
+    git_command(vec!["config", "--bool", "core.bare", "true"], root)
+        .map_err(|result| result.error_message())?;
+
+    if let Some(parent) = worktree_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+
+    // the files are moved in afterwards, so nothing has to be checked out
+    git_command(
+        vec!["worktree", "add", "--no-checkout", path_str, branch],
+        root,
+    )
+    .map_err(|result| result.error_message())?;
+
+    // keeping the index keeps staged changes staged
+    let index = bare_dir.join("index");
+    if index.exists() {
+        fs::rename(&index, git_dir_of(worktree_path)?.join("index"))
+            .map_err(|err| err.to_string())?;
+    }
+
+    let top_level = top_level_entry(root, worktree_path)?;
+    for entry in fs::read_dir(root).map_err(|err| err.to_string())? {
+        let entry = entry.map_err(|err| err.to_string())?;
+        let name = entry.file_name();
+
+        if name == ".git" || name == BARE_DIRECTORY_NAME || entry.path() == top_level {
+            continue;
+        }
+
+        debug!("moving {:?} into the worktree", name);
+        fs::rename(entry.path(), worktree_path.join(&name))
+            .map_err(|err| format!("Couldn't move {:?}: {}", name, err))?;
+    }
+
+    Ok(())
+}
+
+// Also used to undo a failed `into_bare_layout`, so every step copes with it not having happened
+fn into_normal_layout(root: &Path, worktree_path: &Path) -> Result<(), String> {
+    let bare_dir = root.join(BARE_DIRECTORY_NAME);
+    let worktree_git_file = worktree_path.join(".git");
+
+    if worktree_git_file.is_file() {
+        let admin_dir = git_dir_of(worktree_path)?;
+
+        if admin_dir.join("index").exists() {
+            fs::rename(admin_dir.join("index"), bare_dir.join("index"))
+                .map_err(|err| err.to_string())?;
+        }
+
+        // the repository's HEAD becomes whatever the worktree had checked out
+        fs::copy(admin_dir.join("HEAD"), bare_dir.join("HEAD")).map_err(|err| err.to_string())?;
+        fs::remove_dir_all(&admin_dir).map_err(|err| err.to_string())?;
+        fs::remove_file(&worktree_git_file).map_err(|err| err.to_string())?;
+    }
+
+    if worktree_path.is_dir() {
+        for entry in fs::read_dir(worktree_path).map_err(|err| err.to_string())? {
+            let entry = entry.map_err(|err| err.to_string())?;
+            let destination = root.join(entry.file_name());
+
+            if destination.symlink_metadata().is_ok() {
+                return Err(format!("{:?} already exists", destination));
+            }
+
+            debug!("moving {:?} into {:?}", entry.file_name(), root);
+            fs::rename(entry.path(), &destination)
+                .map_err(|err| format!("Couldn't move {:?}: {}", entry.file_name(), err))?;
+        }
+
+        for directory in worktree_path.ancestors() {
+            if directory == root || fs::remove_dir(directory).is_err() {
+                break;
+            }
+        }
+    }
+
+    if bare_dir.is_dir() {
+        if root.join(".git").is_file() {
+            fs::remove_file(root.join(".git")).map_err(|err| err.to_string())?;
+        }
+
+        fs::rename(&bare_dir, root.join(".git")).map_err(|err| err.to_string())?;
+    }
+
+    git_command(vec!["config", "--bool", "core.bare", "false"], root)
+        .map_err(|result| result.error_message())?;
+
+    Ok(())
+}
+
+fn verify(before: &Snapshot, worktree: &Path) -> Result<(), String> {
+    let differences = before.differences(&Snapshot::capture(worktree)?);
+
+    match differences.is_empty() {
+        true => Ok(()),
+        false => Err(format!(
+            "The repository was converted, but it doesn't look the same as before:\n  {}",
+            differences.join("\n  ")
+        )),
+    }
+}
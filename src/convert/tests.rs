@@ -0,0 +1,117 @@
+#[cfg(test)]
+use std::fs;
+
+#[cfg(test)]
+use super::{convert_to_bare, convert_to_normal, BARE_DIRECTORY_NAME};
+
+#[cfg(test)]
+use crate::{
+    commands::git_command,
+    repository::Repository,
+    test_helpers::{self, run_test},
+    test_setup::{CLEAN_NORMAL_REPO_NAME, DIRTY_NORMAL_REPO_NAME},
+    worktree_path::DEFAULT_PATH_TEMPLATE,
+};
+
+#[test]
+fn test_converting_to_bare_keeps_the_checkout_and_its_changes() {
+    run_test(
+        "test_converting_to_bare_keeps_the_checkout_and_its_changes",
+        DIRTY_NORMAL_REPO_NAME,
+        |repo| {
+            let root = repo.root().clone();
+            let branch = crate::utils::get_current_branch_name(&root).unwrap();
+            fs::write(root.join("untracked-file"), "keep me").expect("Couldn't write file");
+            let status_before = git_command(vec!["status", "--porcelain"], &root)
+                .expect("Couldn't get status")
+                .stdout_lines();
+
+            let worktree_path =
+                convert_to_bare(&root, DEFAULT_PATH_TEMPLATE).expect("Couldn't convert");
+
+            assert_eq!(root.join(&branch), worktree_path);
+            assert!(root.join(BARE_DIRECTORY_NAME).is_dir());
+            assert_eq!(
+                "keep me",
+                fs::read_to_string(worktree_path.join("untracked-file")).unwrap()
+            );
+            assert_eq!(
+                status_before,
+                git_command(vec!["status", "--porcelain"], &worktree_path)
+                    .expect("Couldn't get status")
+                    .stdout_lines()
+            );
+
+            let converted = Repository::at(&root).expect("Couldn't open converted repo");
+            assert!(matches!(converted, Repository::Bare(_)));
+            test_helpers::assert_worktree_exists(&converted, branch);
+        },
+    );
+}
+
+#[test]
+fn test_converting_to_bare_and_back_restores_the_repository() {
+    run_test(
+        "test_converting_to_bare_and_back_restores_the_repository",
+        CLEAN_NORMAL_REPO_NAME,
+        |repo| {
+            let root = repo.root().clone();
+            fs::write(root.join("README.md"), "stashed").expect("Couldn't write file");
+            git_command(vec!["stash"], &root).expect("Couldn't stash");
+            let stashes_before = git_command(vec!["stash", "list"], &root)
+                .expect("Couldn't list stashes")
+                .stdout_lines();
+
+            convert_to_bare(&root, DEFAULT_PATH_TEMPLATE).expect("Couldn't convert to bare");
+            convert_to_normal(&root).expect("Couldn't convert back");
+
+            assert!(root.join(".git").is_dir());
+            assert!(!root.join(BARE_DIRECTORY_NAME).exists());
+            assert!(root.join("README.md").is_file());
+            assert_eq!(
+                stashes_before,
+                git_command(vec!["stash", "list"], &root)
+                    .expect("Couldn't list stashes")
+                    .stdout_lines()
+            );
+            assert!(matches!(
+                Repository::at(&root).expect("Couldn't open repo"),
+                Repository::Normal(_)
+            ));
+        },
+    );
+}
+
+#[test]
+fn test_converting_to_bare_is_refused_with_other_worktrees() {
+    run_test(
+        "test_converting_to_bare_is_refused_with_other_worktrees",
+        CLEAN_NORMAL_REPO_NAME,
+        |repo| {
+            let root = repo.root().clone();
+            let other = root.with_file_name("clean_repo_other_worktree");
+            git_command(
+                vec!["worktree", "add", other.to_str().unwrap(), "unmerged"],
+                &root,
+            )
+            .expect("Couldn't add worktree");
+
+            assert!(convert_to_bare(&root, DEFAULT_PATH_TEMPLATE).is_err());
+            assert!(root.join(".git").is_dir());
+        },
+    );
+}
+
+#[test]
+fn test_converting_to_bare_is_refused_when_the_worktree_path_is_taken() {
+    run_test(
+        "test_converting_to_bare_is_refused_when_the_worktree_path_is_taken",
+        CLEAN_NORMAL_REPO_NAME,
+        |repo| {
+            let root = repo.root().clone();
+
+            assert!(convert_to_bare(&root, "{root}/README.md").is_err());
+            assert!(root.join(".git").is_dir());
+        },
+    );
+}
@@ -0,0 +1,114 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+mod tests;
+
+// Matches a single path component against a pattern where `*` matches any run of characters and
+// `?` matches exactly one character
+pub fn matches_component(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let name = name.chars().collect::<Vec<char>>();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// Finds the paths below `base` that match `pattern` (relative, `/` separated). A `**` component
+// matches any number of directories. Matching directories are returned as-is and not descended
+// into, and `.git` is never searched.
+pub fn find_matches(base: &Path, pattern: &str) -> Vec<PathBuf> {
+    let components = pattern
+        .split('/')
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect::<Vec<&str>>();
+    let mut matches: Vec<PathBuf> = vec![];
+
+    // patterns must stay below `base`
+    if components.is_empty() || components.contains(&"..") {
+        return matches;
+    }
+
+    find_matches_in(base, PathBuf::new(), &components, &mut matches);
+    matches.sort();
+
+    // a match inside a matched directory is already covered by the directory
+    let mut outermost: Vec<PathBuf> = vec![];
+    for path in matches {
+        if !outermost.iter().any(|parent| path.starts_with(parent)) {
+            outermost.push(path);
+        }
+    }
+
+    outermost
+}
+
+fn find_matches_in(
+    base: &Path,
+    relative: PathBuf,
+    components: &[&str],
+    matches: &mut Vec<PathBuf>,
+) {
+    let (component, rest) = match components.split_first() {
+        Some(split) => split,
+        None => {
+            matches.push(relative);
+            return;
+        }
+    };
+
+    if *component == "**" {
+        // zero directories
+        find_matches_in(base, relative.clone(), rest, matches);
+    } else if !component.contains(['*', '?']) {
+        // no need to list the directory when the component is a plain name
+        let candidate = relative.join(component);
+
+        if base.join(&candidate).symlink_metadata().is_ok() {
+            find_matches_in(base, candidate, rest, matches);
+        }
+
+        return;
+    }
+
+    let entries = match fs::read_dir(base.join(&relative)) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+
+        if name == ".git" {
+            continue;
+        }
+
+        if *component == "**" {
+            if is_dir {
+                find_matches_in(base, relative.join(&name), components, matches);
+            }
+        } else if matches_component(component, &name) {
+            find_matches_in(base, relative.join(&name), rest, matches);
+        }
+    }
+}
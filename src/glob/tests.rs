@@ -0,0 +1,104 @@
+#[cfg(test)]
+use std::{env::temp_dir, fs, path::PathBuf};
+
+#[cfg(test)]
+use super::{find_matches, matches_component};
+
+#[cfg(test)]
+fn create_tree(name: &str, files: &[&str]) -> PathBuf {
+    let base = temp_dir().join(format!("git-tools-glob-{}", name));
+    let _ = fs::remove_dir_all(&base);
+
+    for file in files {
+        let path = base.join(file);
+        fs::create_dir_all(path.parent().unwrap()).expect("Couldn't create directory");
+        fs::write(&path, "").expect("Couldn't create file");
+    }
+
+    base
+}
+
+#[test]
+fn test_component_wildcards() {
+    assert!(matches_component(".env*", ".env.local"));
+    assert!(matches_component(".env*", ".env"));
+    assert!(matches_component("*.log", "debug.log"));
+    assert!(matches_component("a?c", "abc"));
+    assert!(matches_component("*a*b*", "xxaxxbxx"));
+    assert!(!matches_component("*.log", "debug.txt"));
+    assert!(!matches_component("a?c", "ac"));
+}
+
+#[test]
+fn test_plain_paths_match_only_when_they_exist() {
+    let base = create_tree("plain", &[".env", "config/local.yml"]);
+
+    assert_eq!(vec![PathBuf::from(".env")], find_matches(&base, ".env"));
+    assert_eq!(
+        vec![PathBuf::from("config/local.yml")],
+        find_matches(&base, "config/local.yml")
+    );
+    assert!(find_matches(&base, "missing").is_empty());
+
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[test]
+fn test_wildcards_match_within_a_directory() {
+    let base = create_tree("wildcards", &[".env", ".env.local", "README.md"]);
+
+    assert_eq!(
+        vec![PathBuf::from(".env"), PathBuf::from(".env.local")],
+        find_matches(&base, ".env*")
+    );
+
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[test]
+fn test_double_star_matches_nested_directories() {
+    let base = create_tree(
+        "double_star",
+        &[".env", "apps/web/.env", "apps/api/.env", ".git/.env"],
+    );
+
+    assert_eq!(
+        vec![
+            PathBuf::from(".env"),
+            PathBuf::from("apps/api/.env"),
+            PathBuf::from("apps/web/.env"),
+        ],
+        find_matches(&base, "**/.env")
+    );
+
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[test]
+fn test_matched_directories_are_not_descended_into() {
+    let base = create_tree(
+        "directories",
+        &["node_modules/a/index.js", "node_modules/b.js"],
+    );
+
+    assert_eq!(
+        vec![PathBuf::from("node_modules")],
+        find_matches(&base, "node_modules")
+    );
+    assert_eq!(
+        vec![PathBuf::from("node_modules")],
+        find_matches(&base, "node_modules/**")
+    );
+
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[test]
+fn test_patterns_cannot_leave_the_base_directory() {
+    let base = create_tree("parent", &["inner/file"]);
+
+    assert!(find_matches(&base.join("inner"), "../inner").is_empty());
+    assert!(find_matches(&base, "").is_empty());
+
+    fs::remove_dir_all(base).unwrap();
+}
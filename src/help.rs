@@ -0,0 +1,438 @@
+use crate::{
+    aliases::ALIASES,
+    cli::{ArgumentSpec, CommandSpec, OptionSpec, Values, COMMANDS, GLOBAL_OPTION_SPECS},
+};
+
+#[cfg(test)]
+mod tests;
+
+const WIDTH: usize = 80;
+
+const DESCRIPTION: &str = "A collection of tools that interact with git repositories";
+
+// Environment variables that change how every command behaves, for the man page
+const ENVIRONMENT: [(&str, &str); 5] = [
+    (
+        "REPO",
+        "The repository to run in when --repo isn't given, instead of the current directory.",
+    ),
+    (
+        "GIT_TOOLS_TIMEOUT",
+        "How many seconds a git command may run before it is killed, 0 for no timeout. By default local commands are killed after 120 seconds and clones and fetches have no timeout.",
+    ),
+    (
+        "GIT_TOOLS_LOG",
+        "The log level, one of error, warn, info, debug or trace. Overrides -v and -vv.",
+    ),
+    (
+        "GIT_TOOLS_RECORD",
+        "A file that every git command and its output is appended to, for bug reports. Everything runs as if --jobs 1 was given.",
+    ),
+    (
+        "XDG_CONFIG_HOME",
+        "The user config file is $XDG_CONFIG_HOME/git-tools/config.toml, or ~/.config/git-tools/config.toml.",
+    ),
+];
+
+// `<branch>`, `[directory]`, or `bash|zsh|fish` when the argument is one of a few words
+fn argument_synopsis(argument: &ArgumentSpec) -> String {
+    match (argument.required, argument.values) {
+        (true, Values::Words(_)) => argument.name.to_string(),
+        (true, _) => format!("<{}>", argument.name),
+        (false, _) => format!("[{}]", argument.name),
+    }
+}
+
+fn option_synopsis(option: &OptionSpec) -> String {
+    match option.value {
+        None => option.name.to_string(),
+        Some(Values::Words(_)) => format!("{} {}", option.name, option.value_name),
+        Some(_) => format!("{} <{}>", option.name, option.value_name),
+    }
+}
+
+// The command's words followed by its arguments and options, each one kept on a single line when
+// the synopsis is wrapped
+fn synopsis_parts(command: &CommandSpec) -> Vec<String> {
+    std::iter::once(command.words.join(" "))
+        .chain(command.arguments.iter().map(argument_synopsis))
+        .chain(
+            command
+                .options
+                .iter()
+                .map(|option| format!("[{}]", option_synopsis(option))),
+        )
+        .collect()
+}
+
+pub fn synopsis(command: &CommandSpec) -> String {
+    synopsis_parts(command).join(" ")
+}
+
+// Joins `parts` with spaces into lines of at most `WIDTH` characters, unless a part is longer
+fn fill(parts: &[&str], first_indent: &str, indent: &str) -> String {
+    let mut lines = vec![first_indent.to_string()];
+
+    for part in parts {
+        let prefix = match lines.len() {
+            1 => first_indent,
+            _ => indent,
+        };
+        let line = lines.last_mut().unwrap();
+
+        if line.len() == prefix.len() {
+            line.push_str(part);
+        } else if line.len() + 1 + part.len() <= WIDTH {
+            line.push(' ');
+            line.push_str(part);
+        } else {
+            lines.push(format!("{}{}", indent, part));
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn fill_words(text: &str, first_indent: &str, indent: &str) -> String {
+    fill(
+        &text.split(' ').collect::<Vec<&str>>(),
+        first_indent,
+        indent,
+    )
+}
+
+// A paragraph or a list item of a command's help
+enum Block<'a> {
+    Paragraph(&'a str),
+    Item(&'a str),
+}
+
+fn blocks(text: &str) -> Vec<Vec<Block<'_>>> {
+    text.split("\n\n")
+        .map(|block| {
+            block
+                .lines()
+                .map(|line| match line.strip_prefix("- ") {
+                    Some(item) => Block::Item(item),
+                    None => Block::Paragraph(line),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn render_text(text: &str, indent: &str) -> String {
+    blocks(text)
+        .iter()
+        .map(|block| {
+            block
+                .iter()
+                .map(|line| match line {
+                    Block::Paragraph(text) => fill_words(text, indent, indent),
+                    Block::Item(text) => {
+                        fill_words(text, &format!("{}- ", indent), &format!("{}  ", indent))
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+fn render_options(title: &str, options: &[OptionSpec]) -> String {
+    let options = options
+        .iter()
+        .map(|option| {
+            format!(
+                "  {}\n{}",
+                option_synopsis(option),
+                fill_words(option.help, "      ", "      ")
+            )
+        })
+        .collect::<Vec<String>>();
+
+    format!("{}:\n{}", title, options.join("\n"))
+}
+
+// Wrapped lines line up after the command's words
+fn wrapped_synopsis(command: &CommandSpec, first_indent: &str) -> String {
+    let parts = synopsis_parts(command);
+    let indent = " ".repeat(first_indent.len() + parts[0].len() + 1);
+
+    fill(
+        &parts.iter().map(String::as_str).collect::<Vec<&str>>(),
+        first_indent,
+        &indent,
+    )
+}
+
+// What `git-tools` without a known command prints
+pub fn usage() -> String {
+    let commands = COMMANDS
+        .iter()
+        .map(|command| wrapped_synopsis(command, "  "))
+        .collect::<Vec<String>>();
+
+    format!(
+        "Available commands:\n{}\n\n{}",
+        commands.join("\n"),
+        fill_words(
+            "Every command accepts --repo <path> (instead of the current directory), --main-branch <branch> and \
+-v/--verbose. Run `git-tools help <command>` or `git-tools <command> --help` for what a command does.",
+            "",
+            ""
+        )
+    )
+}
+
+fn command_help(command: &CommandSpec) -> String {
+    let mut sections = vec![
+        format!(
+            "git-tools {} - {}",
+            command.words.join(" "),
+            command.description
+        ),
+        format!("Usage:\n{}", wrapped_synopsis(command, "  git-tools ")),
+        render_text(command.help, ""),
+    ];
+
+    if !command.options.is_empty() {
+        sections.push(render_options("Options", command.options));
+    }
+    sections.push(render_options("Global options", &GLOBAL_OPTION_SPECS));
+
+    sections.join("\n\n")
+}
+
+// `clean-merged` and `wt` stand for the commands they run
+fn resolve_alias(words: Vec<&str>) -> Vec<&str> {
+    match words.split_first() {
+        Some((first, rest)) => match ALIASES.iter().find(|(alias, _)| alias == first) {
+            Some((_, command)) => [*command, rest].concat(),
+            None => words,
+        },
+        None => words,
+    }
+}
+
+fn commands_under(words: &[&str]) -> Vec<&'static CommandSpec> {
+    COMMANDS
+        .iter()
+        .filter(|command| command.words.starts_with(words))
+        .collect()
+}
+
+// The help of a command, or of every command starting with `words` (e.g. `worktree`)
+pub fn long_help(words: &[String]) -> Result<String, String> {
+    let words = resolve_alias(words.iter().map(String::as_str).collect());
+
+    if words.is_empty() {
+        return Ok(usage());
+    }
+
+    let commands = commands_under(&words);
+    match commands.as_slice() {
+        [] => Err(format!(
+            "Unknown command '{}', run `git-tools help` for the list of commands",
+            words.join(" ")
+        )),
+        [command] if command.words == words.as_slice() => Ok(command_help(command)),
+        _ => Ok(format!(
+            "git-tools {} commands:\n{}\n\nRun `git-tools help {} <command>` for what a command does.",
+            words.join(" "),
+            commands
+                .iter()
+                .map(|command| format!(
+                    "{}\n{}",
+                    wrapped_synopsis(command, "  "),
+                    fill_words(command.description, "      ", "      ")
+                ))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            words.join(" ")
+        )),
+    }
+}
+
+// Text that roff prints as-is: backslashes and dashes are escaped, and a line can't start with a
+// control character
+fn roff_escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+
+    match escaped.starts_with('.') || escaped.starts_with('\'') {
+        true => format!("\\&{}", escaped),
+        false => escaped,
+    }
+}
+
+fn roff_text(text: &str) -> String {
+    blocks(text)
+        .iter()
+        .flat_map(|block| {
+            block.iter().map(|line| match line {
+                Block::Paragraph(text) => format!(".PP\n{}\n", roff_escape(text)),
+                Block::Item(text) => format!(".IP \\(bu 2\n{}\n", roff_escape(text)),
+            })
+        })
+        .collect()
+}
+
+fn roff_options(options: &[OptionSpec]) -> String {
+    options
+        .iter()
+        .map(|option| {
+            format!(
+                ".TP\n.B {}\n{}\n",
+                roff_escape(&option_synopsis(option)),
+                roff_escape(option.help)
+            )
+        })
+        .collect()
+}
+
+fn roff_header(name: &str, description: &str) -> String {
+    format!(
+        ".TH \"{}\" 1 \"\" \"git-tools {}\" \"git-tools Manual\"\n.SH NAME\n{} \\- {}\n",
+        name.to_uppercase(),
+        env!("CARGO_PKG_VERSION"),
+        roff_escape(name),
+        roff_escape(description)
+    )
+}
+
+const SEE_ALSO: &str = ".SH SEE ALSO\n.BR git\\-tools (1)\n";
+
+// The page of `name`, which runs the commands starting with `words` as `invocation`, e.g.
+// `git-wt` runs the `worktree` commands as `git wt`
+fn command_page(name: &str, invocation: &str, words: &[&str]) -> String {
+    let commands = commands_under(words);
+    let invocation_of = |command: &CommandSpec| {
+        std::iter::once(invocation)
+            .chain(command.words[words.len()..].iter().copied())
+            .collect::<Vec<&str>>()
+            .join(" ")
+    };
+    let description = match commands.as_slice() {
+        [command] => command.description.to_string(),
+        _ => format!("Run git-tools {}", words.join(" ")),
+    };
+
+    let synopsis = commands
+        .iter()
+        .map(|command| {
+            format!(
+                ".B {}\n{}\n",
+                roff_escape(&invocation_of(command)),
+                roff_escape(&synopsis_parts(command)[1..].join(" "))
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(".br\n");
+
+    let details = match commands.as_slice() {
+        [command] => {
+            let options = match command.options.is_empty() {
+                true => String::new(),
+                false => format!(".SH OPTIONS\n{}", roff_options(command.options)),
+            };
+            format!(".SH DESCRIPTION\n{}{}", roff_text(command.help), options)
+        }
+        _ => format!(
+            ".SH COMMANDS\n{}",
+            commands
+                .iter()
+                .map(|command| format!(
+                    ".SS {}\n.PP\n{}.\n{}{}",
+                    roff_escape(&invocation_of(command)),
+                    roff_escape(command.description),
+                    roff_text(command.help),
+                    roff_options(command.options)
+                ))
+                .collect::<String>()
+        ),
+    };
+
+    format!(
+        "{}.SH SYNOPSIS\n{}{}.SH GLOBAL OPTIONS\n{}{}",
+        roff_header(name, &description),
+        synopsis,
+        details,
+        roff_options(&GLOBAL_OPTION_SPECS),
+        SEE_ALSO
+    )
+}
+
+fn main_page() -> String {
+    let commands = COMMANDS
+        .iter()
+        .map(|command| {
+            format!(
+                ".TP\n.B {}\n{}. See\n.BR {} (1).\n",
+                roff_escape(&format!("git-tools {}", synopsis(command))),
+                roff_escape(command.description),
+                roff_escape(&format!("git-tools-{}", command.words.join("-")))
+            )
+        })
+        .collect::<String>();
+    let aliases = ALIASES
+        .iter()
+        .map(|(alias, words)| {
+            format!(
+                ".TP\n.B git {}\n{}. See\n.BR {} (1).\n",
+                roff_escape(alias),
+                roff_escape(&format!("Runs git-tools {}", words.join(" "))),
+                roff_escape(&format!("git-{}", alias))
+            )
+        })
+        .collect::<String>();
+    let environment = ENVIRONMENT
+        .iter()
+        .map(|(name, help)| format!(".TP\n.B {}\n{}\n", name, roff_escape(help)))
+        .collect::<String>();
+
+    format!(
+        "{}.SH SYNOPSIS\n.B git\\-tools\n<command> [options]\n.SH DESCRIPTION\n{}.SH COMMANDS\n{}\
+.SH GIT COMMANDS\n{}.SH GLOBAL OPTIONS\n{}.SH ENVIRONMENT\n{}",
+        roff_header("git-tools", DESCRIPTION),
+        roff_text(
+            "Runs the command with the repository in the current directory, or the one given with --repo. \
+Settings come from the defaults, the user config file, git config under git-tools. and command line flags, later \
+ones win. See git-tools config show.
+
+The git commands are available once git-tools install-aliases has been run."
+        ),
+        commands,
+        aliases,
+        roff_options(&GLOBAL_OPTION_SPECS),
+        environment
+    )
+}
+
+// Every man page as its file name and content: git-tools.1, one page per command and one per alias
+// so that `git help clean-merged` finds it
+pub fn man_pages() -> Vec<(String, String)> {
+    let commands = COMMANDS.iter().map(|command| {
+        let name = format!("git-tools-{}", command.words.join("-"));
+        let invocation = format!("git-tools {}", command.words.join(" "));
+
+        (
+            name.clone(),
+            command_page(&name, &invocation, command.words),
+        )
+    });
+    let aliases = ALIASES.iter().map(|(alias, words)| {
+        let name = format!("git-{}", alias);
+
+        (
+            name,
+            command_page(&format!("git-{}", alias), &format!("git {}", alias), words),
+        )
+    });
+
+    std::iter::once(("git-tools".to_string(), main_page()))
+        .chain(commands)
+        .chain(aliases)
+        .map(|(name, page)| (format!("{}.1", name), page))
+        .collect()
+}
@@ -0,0 +1,104 @@
+#[cfg(test)]
+use super::{long_help, man_pages, roff_escape, synopsis, usage};
+
+#[cfg(test)]
+use crate::cli::{COMMANDS, GLOBAL_OPTION_SPECS};
+
+#[cfg(test)]
+fn words(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn test_every_command_and_option_is_described() {
+    for command in COMMANDS.iter() {
+        assert!(!command.description.is_empty(), "{:?}", command.words);
+        assert!(!command.help.is_empty(), "{:?}", command.words);
+
+        for option in command.options.iter().chain(GLOBAL_OPTION_SPECS.iter()) {
+            assert!(!option.help.is_empty(), "{}", option.name);
+        }
+    }
+}
+
+#[test]
+fn test_synopses_show_arguments_and_options() {
+    let synopsis_of = |words: &[&str]| {
+        synopsis(
+            COMMANDS
+                .iter()
+                .find(|command| command.words == words)
+                .unwrap(),
+        )
+    };
+
+    assert_eq!(
+        synopsis_of(&["worktree", "add"]),
+        "worktree add <branch> [--from <start point>] [--path-template <template>]"
+    );
+    assert_eq!(
+        synopsis_of(&["clone"]),
+        "clone <url> [directory] [--path-template <template>]"
+    );
+    assert_eq!(synopsis_of(&["shell-init"]), "shell-init bash|zsh|fish");
+    assert!(usage().contains("\n  worktree rename <old branch> <new branch>\n"));
+    assert!(usage().lines().all(|line| line.len() <= 80));
+}
+
+#[test]
+fn test_help_covers_commands_groups_and_aliases() {
+    let help = long_help(&words(&["clean-merged-branches"])).unwrap();
+    assert!(help.starts_with("git-tools clean-merged-branches - "));
+    assert!(help.contains("uncommitted changes"));
+    assert!(help.contains("--all-under <directory>"));
+    assert!(help.contains("--main-branch <branch>"));
+    assert!(help.lines().all(|line| line.len() <= 80));
+
+    assert_eq!(long_help(&words(&["clean-merged"])), Ok(help));
+
+    let group = long_help(&words(&["wt"])).unwrap();
+    assert_eq!(long_help(&words(&["worktree"])), Ok(group.clone()));
+    assert!(group.contains("worktree add <branch>"));
+    assert!(group.contains("worktree list"));
+
+    assert_eq!(long_help(&[]), Ok(usage()));
+    assert!(long_help(&words(&["worktree", "nope"])).is_err());
+}
+
+#[test]
+fn test_escapes_roff() {
+    assert_eq!(roff_escape("--json"), "\\-\\-json");
+    assert_eq!(roff_escape("C:\\dir"), "C:\\edir");
+    assert_eq!(roff_escape(".bare"), "\\&.bare");
+    assert_eq!(roff_escape("'quoted'"), "\\&'quoted'");
+}
+
+#[test]
+fn test_writes_a_page_per_command_and_alias() {
+    let pages = man_pages();
+    let page = |name: &str| {
+        pages
+            .iter()
+            .find(|(file_name, _)| file_name == name)
+            .map(|(_, page)| page.clone())
+            .unwrap()
+    };
+
+    assert_eq!(pages.len(), 1 + COMMANDS.len() + 2);
+    assert!(page("git-tools.1").contains(".BR git\\-tools\\-worktree\\-add (1)."));
+    assert!(page("git-clean-merged.1").contains("\n.B git clean\\-merged\n"));
+    assert!(page("git-clean-merged.1").contains("uncommitted changes"));
+    assert!(page("git-wt.1").contains("\n.SS git wt rename\n"));
+
+    // every line that starts with a control character is a request
+    let requests = [".TH", ".SH", ".SS", ".PP", ".IP", ".TP", ".B", ".BR", ".br"];
+    for (name, page) in pages.iter() {
+        assert!(page.starts_with(".TH "), "{}", name);
+        for line in page.lines() {
+            if line.starts_with('.') || line.starts_with('\'') {
+                let request = line.split(' ').next().unwrap();
+                assert!(requests.contains(&request), "{}: {}", name, line);
+            }
+        }
+    }
+}
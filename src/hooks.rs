@@ -0,0 +1,85 @@
+use std::{
+    io,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::{config::Config, logging::debug};
+
+// Hooks learn which worktree they are running for through these environment variables
+pub const WORKTREE_PATH_ENV_VAR: &str = "GIT_TOOLS_WORKTREE_PATH";
+pub const BRANCH_ENV_VAR: &str = "GIT_TOOLS_BRANCH";
+pub const ROOT_ENV_VAR: &str = "GIT_TOOLS_ROOT";
+
+pub struct HookContext<'a> {
+    pub worktree_path: &'a str,
+    pub branch: &'a str,
+    pub root: &'a Path,
+}
+
+impl<'a> HookContext<'a> {
+    fn envs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (WORKTREE_PATH_ENV_VAR, self.worktree_path.to_string()),
+            (BRANCH_ENV_VAR, self.branch.to_string()),
+            (ROOT_ENV_VAR, self.root.to_string_lossy().to_string()),
+        ]
+    }
+}
+
+// Read from the configuration (see `config`), e.g. git config, where both keys can be given
+// multiple times:
+//   git config --add git-tools.hooks.preRemove 'docker compose down --volumes'
+//   git config --add git-tools.hooks.postRemove 'dropdb "app_$GIT_TOOLS_BRANCH"'
+// Pre-remove hooks run inside the worktree and a failure keeps the worktree from being removed.
+// Post-remove hooks run in the repository root once the worktree and its branch are gone.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RemoveHooks {
+    pub pre_remove: Vec<String>,
+    pub post_remove: Vec<String>,
+}
+
+impl From<&Config> for RemoveHooks {
+    fn from(config: &Config) -> Self {
+        Self {
+            pre_remove: config.pre_remove_hooks.value.clone(),
+            post_remove: config.post_remove_hooks.value.clone(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+// Runs `command` through the shell with the hook environment. Its output is streamed to stderr
+// as it is produced so that stdout stays reserved for our own output.
+pub fn run_hook(command: &str, cwd: &Path, context: &HookContext) -> Result<(), String> {
+    debug!("running hook {:?} (cwd: {:?})", command, cwd);
+    eprintln!("Running: {}", command);
+
+    let status = shell_command(command)
+        .current_dir(cwd)
+        .envs(context.envs())
+        .stdin(Stdio::null())
+        .stdout(Stdio::from(io::stderr()))
+        .stderr(Stdio::inherit())
+        .status()
+        .map_err(|err| format!("'{}' couldn't be started: {}", command, err))?;
+
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(format!("'{}' exited with status {}", command, code)),
+        None => Err(format!("'{}' was terminated by a signal", command)),
+    }
+}
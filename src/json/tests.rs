@@ -0,0 +1,28 @@
+#[cfg(test)]
+use super::Json;
+
+#[test]
+fn test_strings_are_escaped() {
+    assert_eq!(
+        r#""a \"quoted\" \\ path\n\u0001""#,
+        Json::from("a \"quoted\" \\ path\n\u{1}").to_string()
+    );
+}
+
+#[test]
+fn test_objects_keep_the_order_of_their_keys() {
+    let value = Json::object(vec![
+        ("name", Json::from("main")),
+        ("ahead", Json::from(2_usize)),
+        ("upstream", Json::from(None::<String>)),
+        (
+            "tags",
+            Json::Array(vec![Json::from(true), Json::from(-1_i64)]),
+        ),
+    ]);
+
+    assert_eq!(
+        r#"{"name":"main","ahead":2,"upstream":null,"tags":[true,-1]}"#,
+        value.to_string()
+    );
+}
@@ -0,0 +1,110 @@
+use std::{
+    env,
+    fmt::Arguments,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+#[cfg(test)]
+mod tests;
+
+// Log output always goes to stderr so that stdout stays clean for command output
+pub const LOG_ENV_VAR: &str = "GIT_TOOLS_LOG";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Warn as u8);
+
+impl TryFrom<&str> for Level {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value.to_lowercase().as_str() {
+            "error" => Ok(Self::Error),
+            "warn" | "warning" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            "trace" => Ok(Self::Trace),
+            _ => Err(format!(
+                "Unknown log level '{}' (expected error, warn, info, debug or trace)",
+                value
+            )),
+        }
+    }
+}
+
+impl Level {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+            Self::Trace => "TRACE",
+        }
+    }
+
+    // -v shows every decision and git invocation, -vv adds the output of each invocation
+    pub fn from_verbosity(verbosity: usize) -> Self {
+        match verbosity {
+            0 => Self::Warn,
+            1 => Self::Debug,
+            _ => Self::Trace,
+        }
+    }
+}
+
+pub fn set_max_level(level: Level) {
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+// GIT_TOOLS_LOG wins over the verbosity flags so that logging can be turned on without changing
+// how the tool is invoked (e.g. from an editor integration)
+pub fn init(verbosity: usize) -> Result<(), String> {
+    let level = match env::var(LOG_ENV_VAR) {
+        Ok(value) if !value.is_empty() => Level::try_from(value.as_str())?,
+        _ => Level::from_verbosity(verbosity),
+    };
+
+    set_max_level(level);
+
+    Ok(())
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+pub fn log(level: Level, args: Arguments) {
+    if enabled(level) {
+        eprintln!("[{}] {}", level.label(), args);
+    }
+}
+
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Trace, format_args!($($arg)*))
+    };
+}
+
+pub(crate) use debug;
+pub(crate) use trace;
+pub(crate) use warning;
@@ -0,0 +1,28 @@
+#[cfg(test)]
+use super::Level;
+
+#[test]
+fn test_level_names_are_parsed_case_insensitively() {
+    assert_eq!(Ok(Level::Debug), Level::try_from("debug"));
+    assert_eq!(Ok(Level::Trace), Level::try_from("TRACE"));
+    assert_eq!(Ok(Level::Warn), Level::try_from("warning"));
+}
+
+#[test]
+fn test_unknown_level_names_are_rejected() {
+    assert!(Level::try_from("loud").is_err());
+}
+
+#[test]
+fn test_verbosity_flags_map_to_levels() {
+    assert_eq!(Level::Warn, Level::from_verbosity(0));
+    assert_eq!(Level::Debug, Level::from_verbosity(1));
+    assert_eq!(Level::Trace, Level::from_verbosity(2));
+    assert_eq!(Level::Trace, Level::from_verbosity(5));
+}
+
+#[test]
+fn test_more_verbose_levels_are_ordered_after_less_verbose_levels() {
+    assert!(Level::Error < Level::Warn);
+    assert!(Level::Debug < Level::Trace);
+}
@@ -1,12 +1,71 @@
-use std::{env, path::PathBuf, process::exit};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::exit,
+    sync::Mutex,
+};
 
-use crate::utils::expand_path;
+use crate::{
+    aliases::{install_git_aliases, install_symlinks},
+    bootstrap::{bootstrap_worktree, BootstrapConfig},
+    branch_report::{branch_reports, sort_reports, Filters, SortKey},
+    cli::{options_of, value_options, ParsedArguments},
+    clone::{clone_bare, directory_name},
+    commands::git_command,
+    completions::{complete, completion_script},
+    config::{set_overrides, Config, OutputFormat, Overrides},
+    convert::{convert_to_bare, convert_to_normal},
+    help::{long_help, man_pages, usage},
+    json::Json,
+    manifest::Manifest,
+    output::out,
+    pick::pick,
+    pool::{jobs, parse_jobs, set_jobs},
+    repo_location::RepoLocation,
+    repository::{all_branch_names, BareRepository, Repository},
+    shell::init_script,
+    sync::sync,
+    utils::expand_path,
+    workspace::{discover_repositories, run_in_each, sync_workspace},
+    worktree::{NewWorktree, Worktree},
+    worktree_path::configured_path_template,
+    worktree_status::{
+        now, render_csv, render_json, render_table, worktree_entries, worktree_statuses,
+    },
+};
 
+mod aliases;
+mod bootstrap;
+mod branch_report;
+mod cli;
+mod clone;
 mod commands;
+mod completions;
+mod config;
+mod convert;
+mod glob;
+mod help;
+mod hooks;
+mod json;
+mod logging;
+mod manifest;
+mod output;
+mod pick;
+mod pool;
+mod recording;
+mod repo_location;
 mod repository;
+mod shell;
+mod signals;
+mod sync;
+mod table;
+mod terminal;
+mod toml;
 mod utils;
+mod workspace;
 mod worktree;
-mod worktree_list_item;
+mod worktree_path;
+mod worktree_status;
 
 #[cfg(test)]
 mod test_helpers;
@@ -20,53 +79,705 @@ mod tests;
 #[derive(Debug)]
 enum AvailableCommands {
     CleanMergedBranches,
+    WorktreeAdd {
+        branch: String,
+        start_point: Option<String>,
+        path_template: Option<String>,
+    },
+    WorktreeRename {
+        old_branch: String,
+        new_branch: String,
+    },
+    WorktreeList,
+    ConvertToBare {
+        path_template: Option<String>,
+    },
+    ConvertToNormal,
+    Clone {
+        url: String,
+        directory: Option<String>,
+        path_template: Option<String>,
+    },
+    Sync,
+    WorkspaceSync,
+    Switch {
+        branch: String,
+    },
+    Pick {
+        query: Option<String>,
+        switch: bool,
+    },
+    ShellInit {
+        shell: String,
+    },
+    Completions {
+        shell: String,
+    },
+    // Used by the completion scripts
+    Complete {
+        words: Vec<String>,
+    },
+    ConfigShow,
+    BranchesReport {
+        remote: bool,
+        sort: SortKey,
+        filters: Filters,
+    },
+    // Without a directory the aliases go to the global git config
+    InstallAliases {
+        bin_dir: Option<String>,
+    },
+    // The help of the command made of `words`, or the usage without any
+    Help {
+        words: Vec<String>,
+    },
+    ManPages {
+        directory: String,
+    },
     Invalid,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum RepoType {
     Bare,
     Normal,
 }
 
-impl From<String> for AvailableCommands {
-    fn from(value: String) -> Self {
-        match value.as_str() {
-            "clean-merged-branches" => Self::CleanMergedBranches,
-            _ => Self::Invalid,
+impl TryFrom<&ParsedArguments> for AvailableCommands {
+    type Error = String;
+
+    fn try_from(args: &ParsedArguments) -> Result<Self, String> {
+        let positional = args
+            .positional
+            .iter()
+            .map(|arg| arg.as_str())
+            .collect::<Vec<&str>>();
+
+        // `--help` works after any command, `git help wt` runs `git-wt --help` when there's no man page
+        if args.flag("--help") || args.flag("-h") {
+            return Ok(Self::Help {
+                words: args.positional.clone(),
+            });
+        }
+
+        match positional.as_slice() {
+            ["clean-merged-branches"] => {
+                args.reject_unknown_options(&options_of(&["clean-merged-branches"]))?;
+                Ok(Self::CleanMergedBranches)
+            }
+            ["worktree", "add", branch] => {
+                args.reject_unknown_options(&options_of(&["worktree", "add"]))?;
+                Ok(Self::WorktreeAdd {
+                    branch: branch.to_string(),
+                    start_point: args.value("--from").map(String::from),
+                    path_template: args.value("--path-template").map(String::from),
+                })
+            }
+            ["worktree", "rename", old_branch, new_branch] => {
+                args.reject_unknown_options(&options_of(&["worktree", "rename"]))?;
+                Ok(Self::WorktreeRename {
+                    old_branch: old_branch.to_string(),
+                    new_branch: new_branch.to_string(),
+                })
+            }
+            ["worktree", "list"] => {
+                args.reject_unknown_options(&options_of(&["worktree", "list"]))?;
+                Ok(Self::WorktreeList)
+            }
+            ["convert-to-bare"] => {
+                args.reject_unknown_options(&options_of(&["convert-to-bare"]))?;
+                Ok(Self::ConvertToBare {
+                    path_template: args.value("--path-template").map(String::from),
+                })
+            }
+            ["convert-to-normal"] => {
+                args.reject_unknown_options(&options_of(&["convert-to-normal"]))?;
+                Ok(Self::ConvertToNormal)
+            }
+            ["clone", url, rest @ ..] if rest.len() <= 1 => {
+                args.reject_unknown_options(&options_of(&["clone"]))?;
+                Ok(Self::Clone {
+                    url: url.to_string(),
+                    directory: rest.first().map(|directory| directory.to_string()),
+                    path_template: args.value("--path-template").map(String::from),
+                })
+            }
+            ["sync"] => {
+                args.reject_unknown_options(&options_of(&["sync"]))?;
+                Ok(Self::Sync)
+            }
+            ["workspace", "sync"] => {
+                args.reject_unknown_options(&options_of(&["workspace", "sync"]))?;
+                Ok(Self::WorkspaceSync)
+            }
+            ["switch", branch] => {
+                args.reject_unknown_options(&options_of(&["switch"]))?;
+                Ok(Self::Switch {
+                    branch: branch.to_string(),
+                })
+            }
+            ["pick", rest @ ..] if rest.len() <= 1 => {
+                args.reject_unknown_options(&options_of(&["pick"]))?;
+                Ok(Self::Pick {
+                    query: rest.first().map(|query| query.to_string()),
+                    switch: args.flag("--switch"),
+                })
+            }
+            ["shell-init", shell] => {
+                args.reject_unknown_options(&options_of(&["shell-init"]))?;
+                Ok(Self::ShellInit {
+                    shell: shell.to_string(),
+                })
+            }
+            ["completions", shell] => {
+                args.reject_unknown_options(&options_of(&["completions"]))?;
+                Ok(Self::Completions {
+                    shell: shell.to_string(),
+                })
+            }
+            ["__complete", words @ ..] => {
+                args.reject_unknown_options(&[])?;
+                Ok(Self::Complete {
+                    words: words.iter().map(|word| word.to_string()).collect(),
+                })
+            }
+            ["config", "show"] => {
+                args.reject_unknown_options(&options_of(&["config", "show"]))?;
+                Ok(Self::ConfigShow)
+            }
+            ["branches", "report"] => {
+                args.reject_unknown_options(&options_of(&["branches", "report"]))?;
+                let older_than_days = match args.value("--older-than") {
+                    Some(days) => Some(days.parse::<i64>().map_err(|_| {
+                        format!("--older-than must be a number of days, got '{}'", days)
+                    })?),
+                    None => None,
+                };
+
+                Ok(Self::BranchesReport {
+                    remote: args.flag("--remote"),
+                    sort: args
+                        .value("--sort")
+                        .map_or(Ok(SortKey::Age), SortKey::try_from)?,
+                    filters: Filters {
+                        merged: args.flag("--merged"),
+                        unmerged: args.flag("--unmerged"),
+                        gone: args.flag("--gone"),
+                        author: args.value("--author").map(String::from),
+                        older_than_days,
+                    },
+                })
+            }
+            ["install-aliases"] => {
+                args.reject_unknown_options(&options_of(&["install-aliases"]))?;
+                match (args.value("--bin-dir"), args.flag("--git-config")) {
+                    (Some(bin_dir), false) => Ok(Self::InstallAliases {
+                        bin_dir: Some(bin_dir.to_string()),
+                    }),
+                    (None, true) => Ok(Self::InstallAliases { bin_dir: None }),
+                    _ => Err(
+                        "install-aliases needs either --bin-dir <directory> or --git-config"
+                            .to_string(),
+                    ),
+                }
+            }
+            ["help", words @ ..] => {
+                args.reject_unknown_options(&[])?;
+                Ok(Self::Help {
+                    words: words.iter().map(|word| word.to_string()).collect(),
+                })
+            }
+            ["man-pages", directory] => {
+                args.reject_unknown_options(&options_of(&["man-pages"]))?;
+                Ok(Self::ManPages {
+                    directory: directory.to_string(),
+                })
+            }
+            _ => Ok(Self::Invalid),
         }
     }
 }
 
-fn get_cwd() -> PathBuf {
-    match env::var("REPO") {
-        Ok(repo) => PathBuf::from(expand_path(repo)),
-        Err(_) => env::current_dir().expect("Couldn't get the current working directory"),
+// `--repo` wins over the REPO environment variable, which wins over the current directory
+fn get_cwd(repo_flag: Option<&str>) -> PathBuf {
+    let current_dir = || env::current_dir().expect("Couldn't get the current working directory");
+
+    match (repo_flag, env::var("REPO")) {
+        (Some(repo), _) => current_dir().join(repo),
+        (None, Ok(repo)) => PathBuf::from(expand_path(repo)),
+        (None, Err(_)) => current_dir(),
     }
 }
 
-fn get_command() -> AvailableCommands {
-    let args: Vec<String> = env::args().collect();
-    AvailableCommands::from(args[1].clone())
+// The command line flags that override the configuration
+fn config_overrides(args: &ParsedArguments) -> Result<Overrides, String> {
+    let output_format = match (args.value("--format"), args.flag("--json")) {
+        (Some(format), _) => Some((OutputFormat::try_from(format)?, "--format")),
+        (None, true) => Some((OutputFormat::Json, "--json")),
+        (None, false) => None,
+    };
+
+    Ok(Overrides {
+        main_branch: args.value("--main-branch").map(String::from),
+        worktree_path_template: args.value("--path-template").map(String::from),
+        output_format,
+    })
+}
+
+// Every setting, its value and where the value came from. The main branch is the one the
+// repository at `cwd` would use, when `cwd` is in one.
+fn config_show(cwd: &Path) -> Result<(), String> {
+    let config = Config::load(Some(cwd));
+    let detected_main_branch = RepoLocation::discover(cwd)
+        .ok()
+        .and_then(|location| all_branch_names(&location.root).ok())
+        .and_then(|branches| config.main_branch_in(&branches));
+    let entries = config.entries(detected_main_branch);
+
+    let headers = ["SETTING", "VALUE", "SOURCE"];
+    let rows = entries
+        .iter()
+        .map(|(name, value, source)| vec![name.to_string(), value.clone(), source.to_string()])
+        .collect::<Vec<Vec<String>>>();
+
+    match config.output_format.value {
+        OutputFormat::Table => println!("{}", table::render(&headers, &rows)),
+        OutputFormat::Csv => println!("{}", table::render_csv(&headers, &rows)),
+        OutputFormat::Json => println!(
+            "{}",
+            Json::object(
+                entries
+                    .iter()
+                    .map(|(name, value, source)| {
+                        (
+                            *name,
+                            Json::object(vec![
+                                ("value", Json::from(value.clone())),
+                                ("source", Json::from(source.to_string())),
+                            ]),
+                        )
+                    })
+                    .collect(),
+            )
+        ),
+    }
+
+    Ok(())
 }
 
-fn main() {
-    let cwd = get_cwd();
-    let repo = repository::Repository::at(&cwd)
-        .unwrap_or_else(|| panic!("{:#?} is not a valid git repository", cwd));
+fn branches_report(
+    repo: &Repository,
+    remote: bool,
+    sort: SortKey,
+    filters: &Filters,
+) -> Result<(), String> {
+    let now = now();
+    let mut reports = branch_reports(repo.root(), repo.main_branch_name(), remote)?
+        .into_iter()
+        .filter(|report| filters.matches(report, now))
+        .collect::<Vec<_>>();
+    sort_reports(&mut reports, sort);
+
+    println!(
+        "{}",
+        match Config::load(Some(repo.root())).output_format.value {
+            OutputFormat::Table => branch_report::render_table(&reports, now),
+            OutputFormat::Csv => branch_report::render_csv(&reports),
+            OutputFormat::Json => branch_report::render_json(&reports),
+        }
+    );
 
-    match get_command() {
-        AvailableCommands::CleanMergedBranches => match repo.clean_merged() {
-            Ok(_) => (),
-            Err(msg) => {
-                println!("Error: {}", msg);
-                exit(1);
+    Ok(())
+}
+
+// `git-clean-merged` and `git-wt` as symlinks in `bin_dir`, or as aliases in the global git config
+fn install_aliases(cwd: &Path, bin_dir: Option<String>) -> Result<(), String> {
+    let executable = env::current_exe()
+        .map_err(|err| format!("Couldn't find the path of git-tools: {}", err))?;
+    let installed = match bin_dir {
+        Some(bin_dir) => install_symlinks(&executable, &cwd.join(bin_dir))?,
+        None => install_git_aliases(&executable, &["--global"], cwd)?,
+    };
+
+    for line in installed {
+        println!("{}", line);
+    }
+
+    Ok(())
+}
+
+fn write_man_pages(directory: &Path) -> Result<(), String> {
+    fs::create_dir_all(directory)
+        .map_err(|err| format!("Couldn't create {:?}: {}", directory, err))?;
+
+    for (name, page) in man_pages() {
+        let path = directory.join(name);
+        fs::write(&path, page).map_err(|err| format!("Couldn't write {:?}: {}", path, err))?;
+        println!("{}", path.to_string_lossy());
+    }
+
+    Ok(())
+}
+
+fn exit_with_error(msg: String) -> ! {
+    eprintln!("Error: {}", msg);
+
+    if signals::interrupted() {
+        exit(signals::INTERRUPTED_EXIT_CODE);
+    }
+
+    exit(1);
+}
+
+fn open_repository(cwd: &Path) -> Repository {
+    Repository::at(cwd).unwrap_or_else(|msg| exit_with_error(msg))
+}
+
+fn bare_repository<'a>(repo: &'a Repository, command: &str) -> Result<&'a BareRepository, String> {
+    match repo {
+        Repository::Bare(bare) => Ok(bare),
+        Repository::Normal(_) => Err(format!(
+            "`{}` must be run inside a bare repository",
+            command
+        )),
+    }
+}
+
+fn worktree_add(
+    repo: &Repository,
+    branch: String,
+    start_point: Option<String>,
+    path_template: Option<String>,
+) -> Result<(), String> {
+    let bare = bare_repository(repo, "worktree add")?;
+    let path_template = path_template.unwrap_or_else(|| configured_path_template(bare.root()));
+    let worktree = Worktree::create(
+        bare,
+        &NewWorktree {
+            branch,
+            start_point,
+            path_template,
+        },
+    )?;
+
+    let failures = bootstrap_worktree(
+        bare,
+        &worktree,
+        &BootstrapConfig::from(&Config::load(Some(bare.root()))),
+    );
+
+    // Only the path goes to stdout so that it can be used with `cd "$(git-tools worktree add x)"`
+    println!("{}", worktree.path);
+
+    match failures.is_empty() {
+        true => Ok(()),
+        false => Err(format!(
+            "The worktree was created, but preparing it failed:\n  {}",
+            failures.join("\n  ")
+        )),
+    }
+}
+
+fn worktree_rename(repo: &Repository, old_branch: &str, new_branch: &str) -> Result<(), String> {
+    let bare = bare_repository(repo, "worktree rename")?;
+
+    if old_branch == bare.main_branch_name() {
+        return Err(format!(
+            "Refusing to rename the main branch '{}'",
+            old_branch
+        ));
+    }
+
+    let worktrees = bare.all_worktrees()?;
+    let worktree = worktrees
+        .iter()
+        .find(|worktree| worktree.name == old_branch)
+        .ok_or_else(|| format!("No worktree has the branch '{}' checked out", old_branch))?;
+    let renamed = worktree.rename(new_branch, &configured_path_template(bare.root()))?;
+
+    // Only the path goes to stdout, like `worktree add`
+    println!("{}", renamed.path);
+
+    Ok(())
+}
+
+fn convert_repository_to_bare(
+    repo: &Repository,
+    path_template: Option<String>,
+) -> Result<(), String> {
+    let root = match repo {
+        Repository::Normal(_) => repo.root(),
+        Repository::Bare(_) => return Err("The repository is already bare".to_string()),
+    };
+    let path_template = path_template.unwrap_or_else(|| configured_path_template(root));
+    let worktree_path = convert_to_bare(root, &path_template)?;
+
+    eprintln!(
+        "Converted {:?}, the checkout now lives in its own worktree",
+        root
+    );
+    println!("{}", worktree_path.to_string_lossy());
+
+    Ok(())
+}
+
+fn convert_repository_to_normal(repo: &Repository) -> Result<(), String> {
+    let root = bare_repository(repo, "convert-to-normal")?.root();
+
+    convert_to_normal(root)?;
+    eprintln!("Converted {:?} back into a normal repository", root);
+
+    Ok(())
+}
+
+fn clone(
+    cwd: &Path,
+    url: &str,
+    directory: Option<String>,
+    path_template: Option<String>,
+) -> Result<(), String> {
+    let directory = directory
+        .or_else(|| directory_name(url))
+        .ok_or_else(|| format!("Couldn't pick a directory name for '{}', pass one", url))?;
+    let target = cwd.join(directory);
+    let worktree_path = clone_bare(cwd, url, &target, path_template)?;
+
+    // Only the path goes to stdout, like `worktree add`
+    println!("{}", worktree_path.to_string_lossy());
+
+    Ok(())
+}
+
+// In a bare repository the path of the branch's worktree is printed (it is created when there is
+// none), in a normal repository the branch is checked out in place and nothing is printed, unless
+// another worktree has it checked out, which git doesn't allow, then that worktree's path is
+// printed
+fn switch(repo: &Repository, cwd: &Path, branch: String) -> Result<(), String> {
+    let bare = match repo {
+        Repository::Bare(bare) => bare,
+        Repository::Normal(_) => {
+            let elsewhere = worktree_entries(repo.root())?.into_iter().find(|entry| {
+                entry.branch.as_ref() == Some(&branch) && Path::new(&entry.path) != repo.root()
+            });
+            if let Some(entry) = elsewhere {
+                println!("{}", entry.path);
+                return Ok(());
+            }
+
+            let result = git_command(vec!["switch", &branch], cwd)
+                .map_err(|result| result.error_message())?;
+            eprint!("{}", String::from_utf8_lossy(&result.stderr));
+
+            return Ok(());
+        }
+    };
+
+    match bare
+        .all_worktrees()?
+        .into_iter()
+        .find(|worktree| worktree.name == branch)
+    {
+        Some(worktree) => {
+            println!("{}", worktree.path);
+            Ok(())
+        }
+        None => worktree_add(repo, branch, None, None),
+    }
+}
+
+// Prints the picked branch, or switches to it like `switch`
+fn pick_branch(
+    repo: &Repository,
+    cwd: &Path,
+    query: Option<String>,
+    switch_to_branch: bool,
+) -> Result<(), String> {
+    let picked = pick(repo, query.as_deref().unwrap_or_default())?
+        .ok_or_else(|| "Nothing was picked".to_string())?;
+
+    match switch_to_branch {
+        true => switch(repo, cwd, picked.branch),
+        false => {
+            println!("{}", picked.branch);
+            Ok(())
+        }
+    }
+}
+
+fn worktree_list(repo: &Repository) -> Result<(), String> {
+    let statuses = worktree_statuses(repo.root(), repo.main_branch_name())?;
+
+    match Config::load(Some(repo.root())).output_format.value {
+        OutputFormat::Json => out!("{}", render_json(&statuses)),
+        OutputFormat::Table => out!("{}", render_table(&statuses, repo.root(), now())),
+        OutputFormat::Csv => out!("{}", render_csv(&statuses, repo.root(), now())),
+    }
+
+    Ok(())
+}
+
+// The commands that `--all-under` can run in every repository
+fn run_in_repository(command: &AvailableCommands, repo: &Repository) -> Result<(), String> {
+    match command {
+        AvailableCommands::CleanMergedBranches => repo.clean_merged(),
+        AvailableCommands::WorktreeList => worktree_list(repo),
+        AvailableCommands::Sync => match repo {
+            Repository::Bare(bare) => sync(bare),
+            Repository::Normal(_) => {
+                out!("Skipped: `sync` only works in bare repositories");
+                Ok(())
             }
         },
-        _ => {
-            println!("Available commands: clean-merged-branches");
+        _ => Err("This command can't be run with --all-under".to_string()),
+    }
+}
+
+fn run_all_under(directory: &Path, command: &AvailableCommands) -> Result<(), String> {
+    let repositories = discover_repositories(directory);
+
+    if repositories.is_empty() {
+        return Err(format!("No git repositories found under {:?}", directory));
+    }
+
+    // a single JSON document instead of one per repository
+    if let (AvailableCommands::WorktreeList, OutputFormat::Json) =
+        (command, Config::load(None).output_format.value)
+    {
+        let entries = Mutex::new(vec![]);
+        let result = run_in_each(&repositories, jobs(), false, |repo| {
+            let statuses = worktree_statuses(repo.root(), repo.main_branch_name())?;
+
+            entries.lock().unwrap().push((
+                repo.root().clone(),
+                Json::object(vec![
+                    (
+                        "repository",
+                        Json::from(repo.root().to_string_lossy().to_string()),
+                    ),
+                    (
+                        "worktrees",
+                        Json::Array(statuses.iter().map(|status| status.to_json()).collect()),
+                    ),
+                ]),
+            ));
+            Ok(())
+        });
+        // repositories finish in any order
+        let mut entries = entries.into_inner().unwrap();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        println!(
+            "{}",
+            Json::Array(entries.into_iter().map(|(_, entry)| entry).collect())
+        );
+        return result;
+    }
+
+    run_in_each(&repositories, jobs(), true, |repo| {
+        run_in_repository(command, repo)
+    })
+}
+
+fn main() {
+    let mut argv = env::args();
+    let program = argv.next().unwrap_or_default();
+    // `git-wt add x` runs as `git-tools worktree add x`
+    let raw_args = aliases::arguments(&program, argv.collect());
+    let args = ParsedArguments::parse(&raw_args, &value_options()).unwrap_or_else(|msg| {
+        println!("Error: {}", msg);
+        exit(1);
+    });
+
+    if let Err(msg) = logging::init(args.verbosity) {
+        exit_with_error(msg);
+    }
+
+    signals::install_handler();
+
+    let command = AvailableCommands::try_from(&args).unwrap_or_else(|msg| exit_with_error(msg));
+    match config_overrides(&args) {
+        Ok(overrides) => set_overrides(overrides),
+        Err(msg) => exit_with_error(msg),
+    }
+
+    let cwd = get_cwd(args.value("--repo"));
+
+    if let Some(value) = args.value("--jobs") {
+        match parse_jobs(value) {
+            Ok(jobs) => set_jobs(jobs),
+            Err(msg) => exit_with_error(msg),
+        }
+    }
+
+    if let Some(directory) = args.value("--all-under") {
+        if let Err(msg) = run_all_under(&cwd.join(directory), &command) {
+            exit_with_error(msg);
+        }
+
+        return;
+    }
+
+    let result = match command {
+        AvailableCommands::CleanMergedBranches => open_repository(&cwd).clean_merged(),
+        AvailableCommands::WorktreeAdd {
+            branch,
+            start_point,
+            path_template,
+        } => worktree_add(&open_repository(&cwd), branch, start_point, path_template),
+        AvailableCommands::WorktreeRename {
+            old_branch,
+            new_branch,
+        } => worktree_rename(&open_repository(&cwd), &old_branch, &new_branch),
+        AvailableCommands::WorktreeList => worktree_list(&open_repository(&cwd)),
+        AvailableCommands::ConvertToBare { path_template } => {
+            convert_repository_to_bare(&open_repository(&cwd), path_template)
+        }
+        AvailableCommands::ConvertToNormal => convert_repository_to_normal(&open_repository(&cwd)),
+        AvailableCommands::Clone {
+            url,
+            directory,
+            path_template,
+        } => clone(&cwd, &url, directory, path_template),
+        AvailableCommands::WorkspaceSync => {
+            Manifest::find(&cwd).and_then(|manifest| sync_workspace(&manifest, jobs()))
+        }
+        AvailableCommands::Sync => {
+            let repo = open_repository(&cwd);
+            bare_repository(&repo, "sync").and_then(sync)
+        }
+        AvailableCommands::Switch { branch } => switch(&open_repository(&cwd), &cwd, branch),
+        AvailableCommands::Pick { query, switch } => {
+            pick_branch(&open_repository(&cwd), &cwd, query, switch)
+        }
+        AvailableCommands::ShellInit { shell } => {
+            init_script(&shell).map(|script| print!("{}", script))
+        }
+        AvailableCommands::Completions { shell } => {
+            completion_script(&shell).map(|script| print!("{}", script))
+        }
+        AvailableCommands::Complete { words } => {
+            for (candidate, description) in complete(&words, &cwd) {
+                println!("{}\t{}", candidate, description);
+            }
+            Ok(())
+        }
+        AvailableCommands::ConfigShow => config_show(&cwd),
+        AvailableCommands::BranchesReport {
+            remote,
+            sort,
+            filters,
+        } => branches_report(&open_repository(&cwd), remote, sort, &filters),
+        AvailableCommands::InstallAliases { bin_dir } => install_aliases(&cwd, bin_dir),
+        AvailableCommands::Help { words } => long_help(&words).map(|help| println!("{}", help)),
+        AvailableCommands::ManPages { directory } => write_man_pages(&cwd.join(directory)),
+        AvailableCommands::Invalid => {
+            println!("{}", usage());
             println!("repo path: {:?}", cwd);
             exit(1);
         }
     };
+
+    if let Err(msg) = result {
+        exit_with_error(msg);
+    }
 }
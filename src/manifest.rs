@@ -0,0 +1,196 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    clone::directory_name,
+    toml::{self, TomlTable, TomlValue},
+};
+
+#[cfg(test)]
+mod tests;
+
+pub const MANIFEST_FILE_NAME: &str = "git-tools.toml";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    Bare,
+    Normal,
+}
+
+// One `[[repository]]` of the manifest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManifestRepository {
+    pub url: String,
+    // Relative to the manifest's directory
+    pub path: PathBuf,
+    pub layout: Layout,
+    // Branches that get a worktree when the repository is cloned
+    pub worktrees: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Manifest {
+    // The directory holding the manifest
+    pub root: PathBuf,
+    pub repositories: Vec<ManifestRepository>,
+}
+
+const REPOSITORY_KEYS: [&str; 4] = ["url", "path", "layout", "worktrees"];
+
+fn string_field(table: &TomlTable, key: &str) -> Result<Option<String>, String> {
+    match toml::get(table, key) {
+        None => Ok(None),
+        Some(TomlValue::String(value)) => Ok(Some(value.clone())),
+        Some(value) => Err(format!(
+            "'{}' must be a string, not {}",
+            key,
+            value.type_name()
+        )),
+    }
+}
+
+// Paths like `a/./b` and `a/b/` name the same directory
+fn normalized(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+fn parse_repository(table: &TomlTable) -> Result<ManifestRepository, String> {
+    if let Some((key, _)) = table
+        .iter()
+        .find(|(key, _)| !REPOSITORY_KEYS.contains(&key.as_str()))
+    {
+        return Err(format!("unknown key '{}'", key));
+    }
+
+    let url = string_field(table, "url")?.ok_or_else(|| "'url' is missing".to_string())?;
+    let path =
+        match string_field(table, "path")? {
+            Some(path) => PathBuf::from(path),
+            None => PathBuf::from(directory_name(&url).ok_or_else(|| {
+                format!("couldn't pick a directory name for '{}', set 'path'", url)
+            })?),
+        };
+
+    if path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+        return Err(format!(
+            "'path' must stay inside the manifest's directory, got {:?}",
+            path
+        ));
+    }
+
+    let layout = match string_field(table, "layout")?.as_deref() {
+        None | Some("bare") => Layout::Bare,
+        Some("normal") => Layout::Normal,
+        Some(other) => {
+            return Err(format!(
+                "'layout' must be \"bare\" or \"normal\", got \"{}\"",
+                other
+            ))
+        }
+    };
+
+    let worktrees = match toml::get(table, "worktrees") {
+        None => vec![],
+        Some(TomlValue::Array(values)) => values
+            .iter()
+            .map(|value| {
+                value
+                    .as_str()
+                    .map(String::from)
+                    .ok_or_else(|| "'worktrees' must only contain strings".to_string())
+            })
+            .collect::<Result<Vec<String>, String>>()?,
+        Some(value) => {
+            return Err(format!(
+                "'worktrees' must be an array, not {}",
+                value.type_name()
+            ))
+        }
+    };
+
+    if layout == Layout::Normal && !worktrees.is_empty() {
+        return Err("'worktrees' needs the bare layout".to_string());
+    }
+
+    Ok(ManifestRepository {
+        url,
+        path: normalized(&path),
+        layout,
+        worktrees,
+    })
+}
+
+pub fn parse_manifest(text: &str) -> Result<Vec<ManifestRepository>, String> {
+    let document = toml::parse(text)?;
+
+    if let Some((key, _)) = document.iter().find(|(key, _)| key != "repository") {
+        return Err(format!("unknown key '{}'", key));
+    }
+
+    let tables = match toml::get(&document, "repository") {
+        None => return Ok(vec![]),
+        Some(TomlValue::Array(tables)) => tables,
+        Some(_) => return Err("'repository' must be written as [[repository]]".to_string()),
+    };
+
+    let mut repositories: Vec<ManifestRepository> = vec![];
+
+    for (index, table) in tables.iter().enumerate() {
+        let repository = table
+            .as_table()
+            .ok_or_else(|| "'repository' must be written as [[repository]]".to_string())
+            .and_then(parse_repository)
+            .map_err(|msg| format!("repository #{}: {}", index + 1, msg))?;
+
+        if repositories
+            .iter()
+            .any(|other| other.path == repository.path)
+        {
+            return Err(format!(
+                "repository #{}: {:?} is used by another repository",
+                index + 1,
+                repository.path
+            ));
+        }
+
+        repositories.push(repository);
+    }
+
+    Ok(repositories)
+}
+
+impl Manifest {
+    // The manifest in `directory` or the closest of its parents
+    pub fn find(directory: &Path) -> Result<Self, String> {
+        let path = directory
+            .ancestors()
+            .map(|ancestor| ancestor.join(MANIFEST_FILE_NAME))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                format!(
+                    "No {} found in {:?} or its parents",
+                    MANIFEST_FILE_NAME, directory
+                )
+            })?;
+
+        Self::load(&path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text =
+            fs::read_to_string(path).map_err(|err| format!("Couldn't read {:?}: {}", path, err))?;
+        let repositories =
+            parse_manifest(&text).map_err(|msg| format!("{}: {}", path.to_string_lossy(), msg))?;
+
+        Ok(Self {
+            root: path
+                .parent()
+                .map_or_else(|| PathBuf::from("."), Path::to_path_buf),
+            repositories,
+        })
+    }
+}
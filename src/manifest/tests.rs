@@ -0,0 +1,80 @@
+#[cfg(test)]
+use std::path::PathBuf;
+
+#[cfg(test)]
+use super::{parse_manifest, Layout, ManifestRepository};
+
+#[test]
+fn test_parses_repositories_with_defaults() {
+    let repositories = parse_manifest(
+        r#"
+[[repository]]
+url = "git@github.com:team/api.git"
+worktrees = ["main", "develop"]
+
+[[repository]]
+url = "https://github.com/team/web"
+path = "./frontend/web/"
+layout = "normal"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        repositories,
+        vec![
+            ManifestRepository {
+                url: "git@github.com:team/api.git".to_string(),
+                path: PathBuf::from("api"),
+                layout: Layout::Bare,
+                worktrees: vec!["main".to_string(), "develop".to_string()],
+            },
+            ManifestRepository {
+                url: "https://github.com/team/web".to_string(),
+                path: PathBuf::from("frontend/web"),
+                layout: Layout::Normal,
+                worktrees: vec![],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_an_empty_manifest_has_no_repositories() {
+    assert_eq!(parse_manifest("# nothing yet\n"), Ok(vec![]));
+}
+
+#[test]
+fn test_rejects_invalid_repositories() {
+    let error = |text: &str| parse_manifest(text).unwrap_err();
+
+    assert_eq!(
+        error("[[repository]]\npath = \"x\"\n"),
+        "repository #1: 'url' is missing"
+    );
+    assert_eq!(
+        error("[[repository]]\nurl = \"a\"\nbranch = \"main\"\n"),
+        "repository #1: unknown key 'branch'"
+    );
+    assert_eq!(
+        error("[[repository]]\nurl = \"a\"\nlayout = \"flat\"\n"),
+        "repository #1: 'layout' must be \"bare\" or \"normal\", got \"flat\""
+    );
+    assert_eq!(
+        error("[[repository]]\nurl = \"a\"\nlayout = \"normal\"\nworktrees = [\"main\"]\n"),
+        "repository #1: 'worktrees' needs the bare layout"
+    );
+    assert_eq!(
+        error("[[repository]]\nurl = \"a\"\npath = \"../a\"\n"),
+        "repository #1: 'path' must stay inside the manifest's directory, got \"../a\""
+    );
+    assert_eq!(
+        error("[[repository]]\nurl = \"x/a\"\n[[repository]]\nurl = \"y/a.git\"\n"),
+        "repository #2: \"a\" is used by another repository"
+    );
+    assert_eq!(
+        error("[repository]\nurl = \"a\"\n"),
+        "'repository' must be written as [[repository]]"
+    );
+    assert_eq!(error("name = \"x\"\n"), "unknown key 'name'");
+}
@@ -0,0 +1,47 @@
+use std::{cell::RefCell, fmt::Arguments, fmt::Write};
+
+#[cfg(test)]
+mod tests;
+
+thread_local! {
+    static CAPTURED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Prints a line to stdout, unless the current thread is capturing its output
+pub fn print_line(args: Arguments) {
+    CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(buffer) => {
+            let _ = writeln!(buffer, "{}", args);
+        }
+        None => println!("{}", args),
+    })
+}
+
+// Prints text that was captured before, as is
+pub fn print_captured(text: &str) {
+    CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(buffer) => buffer.push_str(text),
+        None => print!("{}", text),
+    })
+}
+
+// Runs `f` and returns what it printed with `out!` instead of printing it, so that output of work
+// running in parallel can be printed in a stable order. Output on stderr isn't captured.
+pub fn capture<R>(f: impl FnOnce() -> R) -> (R, String) {
+    let previous = CAPTURED.with(|captured| captured.replace(Some(String::new())));
+    let result = f();
+    let output = CAPTURED.with(|captured| captured.replace(previous));
+
+    (result, output.unwrap_or_default())
+}
+
+macro_rules! out {
+    () => {
+        $crate::output::print_line(format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::print_line(format_args!($($arg)*))
+    };
+}
+
+pub(crate) use out;
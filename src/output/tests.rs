@@ -0,0 +1,27 @@
+#[cfg(test)]
+use super::{capture, out, print_captured};
+
+#[test]
+fn test_captures_output_instead_of_printing_it() {
+    let (result, output) = capture(|| {
+        out!("first {}", 1);
+        out!();
+        print_captured("as is\n");
+        42
+    });
+
+    assert_eq!(result, 42);
+    assert_eq!(output, "first 1\n\nas is\n");
+}
+
+#[test]
+fn test_nested_captures_are_separate() {
+    let (inner, outer) = capture(|| {
+        out!("outer");
+        let (_, inner) = capture(|| out!("inner"));
+        inner
+    });
+
+    assert_eq!(inner, "inner\n");
+    assert_eq!(outer, "outer\n");
+}
@@ -0,0 +1,246 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    commands::git_command,
+    repository::Repository,
+    terminal::{Key, Terminal},
+    worktree_status::{format_age, now, worktree_entries},
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub branch: String,
+    // The worktree holding the branch, relative to the repository root when it is inside it and
+    // `.` for the root itself
+    pub worktree: Option<String>,
+    // Unix timestamp of the branch's last commit
+    pub last_commit: i64,
+}
+
+const PREVIEW_COMMITS: usize = 5;
+
+// Local branches, most recently committed to first
+pub fn candidates(repo: &Repository) -> Result<Vec<Candidate>, String> {
+    let root = repo.root();
+    let worktrees = worktree_entries(root)?
+        .into_iter()
+        .filter_map(|entry| {
+            let path = Path::new(&entry.path);
+            let relative = match path.strip_prefix(root) {
+                Ok(relative) if relative.as_os_str().is_empty() => Path::new("."),
+                Ok(relative) => relative,
+                Err(_) => path,
+            };
+
+            Some((entry.branch?, relative.to_string_lossy().to_string()))
+        })
+        .collect::<HashMap<String, String>>();
+
+    Ok(git_command(
+        vec![
+            "for-each-ref",
+            "--sort=-committerdate",
+            "--format=%(committerdate:unix) %(refname:lstrip=2)",
+            "refs/heads/",
+        ],
+        root,
+    )
+    .map_err(|result| result.error_message())?
+    .stdout_lines()
+    .into_iter()
+    .filter_map(|line| {
+        let (timestamp, branch) = line.split_once(' ')?;
+
+        Some(Candidate {
+            worktree: worktrees.get(branch).cloned(),
+            branch: branch.to_string(),
+            last_commit: timestamp.parse().unwrap_or_default(),
+        })
+    })
+    .collect())
+}
+
+// Scores `text` when every character of `query` appears in it in order (ignoring case). Matches
+// at the start of words and runs of consecutive characters score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text = text.to_lowercase().chars().collect::<Vec<char>>();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for query_char in query.to_lowercase().chars() {
+        let found = position + text[position..].iter().position(|c| *c == query_char)?;
+        let starts_word = found == 0 || matches!(text[found - 1], '/' | '-' | '_' | '.' | ' ');
+
+        score += 1;
+        if starts_word {
+            score += 8;
+        }
+        match previous_match {
+            Some(previous) if previous + 1 == found => score += 5,
+            Some(previous) => score -= (found - previous - 1).min(5) as i64,
+            None => score -= found.min(5) as i64,
+        }
+
+        previous_match = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
+
+// Without a query the candidates keep their order, otherwise the best matches come first and
+// ties keep their order
+pub fn rank<'a>(candidates: &'a [Candidate], query: &str) -> Vec<&'a Candidate> {
+    let mut scored = candidates
+        .iter()
+        .filter_map(|candidate| {
+            fuzzy_score(query, &candidate.branch).map(|score| (score, candidate))
+        })
+        .collect::<Vec<(i64, &Candidate)>>();
+
+    scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+    scored.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+fn truncate(line: &str, width: usize) -> String {
+    line.chars().take(width).collect()
+}
+
+pub struct Screen<'a> {
+    pub query: &'a str,
+    pub matches: &'a [&'a Candidate],
+    pub total: usize,
+    pub selected: usize,
+    pub preview: &'a [String],
+    pub rows: usize,
+    pub columns: usize,
+    pub now: i64,
+}
+
+// The whole screen: the query, as many matches as fit and a preview of the selected branch
+pub fn render(screen: &Screen) -> String {
+    let preview_height = (screen.rows / 3).min(PREVIEW_COMMITS + 1);
+    let list_height = screen.rows.saturating_sub(preview_height + 1).max(1);
+    let offset = (screen.selected + 1).saturating_sub(list_height);
+    let mut lines = vec![format!(
+        "> {}  ({}/{})",
+        screen.query,
+        screen.matches.len(),
+        screen.total
+    )];
+
+    for (index, candidate) in screen
+        .matches
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(list_height)
+    {
+        let worktree = candidate
+            .worktree
+            .as_ref()
+            .map_or(String::new(), |worktree| format!("  [{}]", worktree));
+        let line = truncate(
+            &format!(
+                "  {}{}  {}",
+                candidate.branch,
+                worktree,
+                format_age(screen.now - candidate.last_commit)
+            ),
+            screen.columns,
+        );
+
+        lines.push(match index == screen.selected {
+            true => format!("\x1b[7m{}\x1b[0m", line),
+            false => line,
+        });
+    }
+
+    while lines.len() < list_height + 1 {
+        lines.push(String::new());
+    }
+
+    if preview_height > 0 {
+        lines.push(truncate(&"-".repeat(screen.columns), screen.columns));
+        lines.extend(
+            screen
+                .preview
+                .iter()
+                .take(preview_height - 1)
+                .map(|line| truncate(line, screen.columns)),
+        );
+    }
+
+    format!("\x1b[H\x1b[2J{}", lines.join("\r\n"))
+}
+
+fn preview(root: &Path, branch: &str) -> Vec<String> {
+    let count = format!("-{}", PREVIEW_COMMITS);
+
+    git_command(
+        vec!["log", &count, "--format=%h %s (%cr, %an)", branch, "--"],
+        root,
+    )
+    .map(|result| result.stdout_lines())
+    .unwrap_or_default()
+}
+
+// Lets the user pick a branch, None when they cancel
+pub fn pick(repo: &Repository, initial_query: &str) -> Result<Option<Candidate>, String> {
+    let candidates = candidates(repo)?;
+    let mut previews: HashMap<String, Vec<String>> = HashMap::new();
+    let mut query = initial_query.to_string();
+    let mut selected = 0;
+    let mut terminal = Terminal::open()?;
+
+    loop {
+        let matches = rank(&candidates, &query);
+        selected = selected.min(matches.len().saturating_sub(1));
+
+        let preview_lines = match matches.get(selected) {
+            Some(candidate) => previews
+                .entry(candidate.branch.clone())
+                .or_insert_with(|| preview(repo.root(), &candidate.branch))
+                .clone(),
+            None => vec![],
+        };
+        let (rows, columns) = terminal.size();
+
+        terminal.write(&render(&Screen {
+            query: &query,
+            matches: &matches,
+            total: candidates.len(),
+            selected,
+            preview: &preview_lines,
+            rows,
+            columns,
+            now: now(),
+        }))?;
+
+        for key in terminal.read_keys()? {
+            match key {
+                Key::Char(c) => {
+                    query.push(c);
+                    selected = 0;
+                }
+                Key::Backspace => {
+                    query.pop();
+                    selected = 0;
+                }
+                Key::ClearQuery => {
+                    query.clear();
+                    selected = 0;
+                }
+                Key::Up => selected = selected.saturating_sub(1),
+                Key::Down => selected = (selected + 1).min(matches.len().saturating_sub(1)),
+                Key::Enter => return Ok(matches.get(selected).map(|c| (*c).clone())),
+                Key::Cancel => return Ok(None),
+                Key::Other => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+#[cfg(test)]
+use super::{candidates, fuzzy_score, rank, render, Candidate, Screen};
+
+#[cfg(test)]
+use crate::{
+    commands::git_command,
+    test_helpers::run_test,
+    test_setup::{BARE_REPO_NAME, CLEAN_NORMAL_REPO_NAME, DEFAULT_BRANCH_NAME},
+};
+
+#[cfg(test)]
+fn candidate(branch: &str, last_commit: i64) -> Candidate {
+    Candidate {
+        branch: branch.to_string(),
+        worktree: None,
+        last_commit,
+    }
+}
+
+#[test]
+fn test_fuzzy_score_needs_every_character_in_order() {
+    assert!(fuzzy_score("fl", "feature/login").is_some());
+    assert!(fuzzy_score("FL", "feature/login").is_some());
+    assert!(fuzzy_score("lf", "feature/login").is_none());
+    assert_eq!(Some(0), fuzzy_score("", "anything"));
+}
+
+#[test]
+fn test_fuzzy_score_prefers_word_starts_and_runs() {
+    let word_start = fuzzy_score("log", "feature/login").unwrap();
+    let scattered = fuzzy_score("log", "fix-a-long-bug").unwrap();
+
+    assert!(word_start > scattered);
+}
+
+#[test]
+fn test_ranking_keeps_recency_without_a_query() {
+    let candidates = vec![candidate("newest", 3), candidate("older", 2)];
+
+    assert_eq!(
+        vec!["newest", "older"],
+        rank(&candidates, "")
+            .iter()
+            .map(|c| c.branch.as_str())
+            .collect::<Vec<&str>>()
+    );
+}
+
+#[test]
+fn test_ranking_puts_the_best_match_first() {
+    let candidates = vec![
+        candidate("fix-a-long-bug", 3),
+        candidate("feature/login", 2),
+        candidate("main", 1),
+    ];
+
+    assert_eq!(
+        vec!["feature/login", "fix-a-long-bug"],
+        rank(&candidates, "log")
+            .iter()
+            .map(|c| c.branch.as_str())
+            .collect::<Vec<&str>>()
+    );
+}
+
+#[test]
+fn test_render_scrolls_to_the_selected_match_and_fits_the_screen() {
+    let candidates = (0..20)
+        .map(|index| candidate(&format!("branch-{}", index), 0))
+        .collect::<Vec<Candidate>>();
+    let matches = rank(&candidates, "");
+    let preview = vec!["abc1234 a commit message that is too long".to_string()];
+    let screen = render(&Screen {
+        query: "",
+        matches: &matches,
+        total: candidates.len(),
+        selected: 15,
+        preview: &preview,
+        rows: 12,
+        columns: 20,
+        now: 0,
+    });
+    let lines = screen.split("\r\n").collect::<Vec<&str>>();
+
+    assert!(lines.len() <= 12);
+    assert!(screen.contains("\x1b[7m  branch-15"));
+    assert!(!screen.contains("branch-0 "));
+    assert!(lines.contains(&"abc1234 a commit mes"));
+}
+
+#[test]
+fn test_candidates_know_which_worktree_holds_a_branch() {
+    run_test(
+        "test_candidates_know_which_worktree_holds_a_branch",
+        BARE_REPO_NAME,
+        |repo| {
+            let candidates = candidates(&repo).expect("Couldn't list candidates");
+            let worktree_of = |branch: &str| {
+                candidates
+                    .iter()
+                    .find(|candidate| candidate.branch == branch)
+                    .and_then(|candidate| candidate.worktree.clone())
+            };
+
+            assert_eq!(Some("merged".to_string()), worktree_of("merged"));
+            assert_eq!(
+                Some("origin/doesnt-match-name".to_string()),
+                worktree_of("wont-match-path")
+            );
+        },
+    );
+}
+
+#[test]
+fn test_candidates_know_the_worktrees_of_a_normal_repository() {
+    run_test(
+        "test_candidates_know_the_worktrees_of_a_normal_repository",
+        CLEAN_NORMAL_REPO_NAME,
+        |repo| {
+            let linked = repo.root().parent().unwrap().join("linked");
+            git_command(
+                vec!["worktree", "add", linked.to_str().unwrap(), "unmerged"],
+                repo.root(),
+            )
+            .expect("Couldn't add a worktree");
+
+            let candidates = candidates(&repo).expect("Couldn't list candidates");
+            let worktree_of = |branch: &str| {
+                candidates
+                    .iter()
+                    .find(|candidate| candidate.branch == branch)
+                    .and_then(|candidate| candidate.worktree.clone())
+            };
+
+            assert_eq!(Some(".".to_string()), worktree_of(DEFAULT_BRANCH_NAME));
+            assert_eq!(
+                Some(linked.to_str().unwrap().to_string()),
+                worktree_of("unmerged")
+            );
+            assert_eq!(None, worktree_of("merged"));
+        },
+    );
+}
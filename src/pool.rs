@@ -0,0 +1,114 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Mutex,
+    },
+    thread,
+};
+
+use crate::commands::must_run_sequentially;
+
+#[cfg(test)]
+mod tests;
+
+// 0 means `--jobs` wasn't given
+static JOBS: AtomicUsize = AtomicUsize::new(0);
+
+// More workers than this mostly wait on the disk
+const MAX_DEFAULT_JOBS: usize = 8;
+
+pub fn set_jobs(jobs: usize) {
+    JOBS.store(jobs, Ordering::Relaxed);
+}
+
+pub fn jobs() -> usize {
+    if must_run_sequentially() {
+        return 1;
+    }
+
+    match JOBS.load(Ordering::Relaxed) {
+        0 => thread::available_parallelism()
+            .map_or(1, |parallelism| parallelism.get())
+            .min(MAX_DEFAULT_JOBS),
+        jobs => jobs,
+    }
+}
+
+pub fn parse_jobs(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(jobs) if jobs > 0 => Ok(jobs),
+        _ => Err(format!("--jobs must be a positive number, got '{}'", value)),
+    }
+}
+
+// Runs `work` on every item with up to `jobs` threads and hands the results to `done` on the
+// calling thread in the order of `items`, each as soon as it and everything before it is finished.
+// With a single job everything runs on the calling thread.
+pub fn for_each_ordered<T, R, W, D>(items: Vec<T>, jobs: usize, work: W, mut done: D)
+where
+    T: Send,
+    R: Send,
+    W: Fn(T) -> R + Sync,
+    D: FnMut(R),
+{
+    let workers = jobs.min(items.len());
+
+    if workers <= 1 {
+        for item in items {
+            done(work(item));
+        }
+        return;
+    }
+
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let (sender, receiver) = mpsc::channel::<(usize, R)>();
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let queue = &queue;
+            let work = &work;
+
+            scope.spawn(move || loop {
+                let next = queue.lock().unwrap().next();
+
+                match next {
+                    Some((index, item)) => {
+                        if sender.send((index, work(item))).is_err() {
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            });
+        }
+        drop(sender);
+
+        let mut finished: HashMap<usize, R> = HashMap::new();
+        let mut next = 0;
+
+        for (index, result) in receiver {
+            finished.insert(index, result);
+
+            while let Some(result) = finished.remove(&next) {
+                done(result);
+                next += 1;
+            }
+        }
+    });
+}
+
+// `for_each_ordered` collecting the results
+pub fn parallel_map<T, R, W>(items: Vec<T>, jobs: usize, work: W) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    W: Fn(T) -> R + Sync,
+{
+    let mut results = Vec::with_capacity(items.len());
+
+    for_each_ordered(items, jobs, work, |result| results.push(result));
+
+    results
+}
@@ -0,0 +1,61 @@
+#[cfg(test)]
+use std::{
+    sync::{Barrier, Mutex},
+    thread,
+    time::Duration,
+};
+
+#[cfg(test)]
+use super::{for_each_ordered, parallel_map, parse_jobs};
+
+#[test]
+fn test_keeps_the_order_of_the_items() {
+    // later items finish first
+    let results = parallel_map((0..8).collect(), 4, |item: u64| {
+        thread::sleep(Duration::from_millis(40 - item * 5));
+        item * 10
+    });
+
+    assert_eq!(results, vec![0, 10, 20, 30, 40, 50, 60, 70]);
+}
+
+#[test]
+fn test_runs_items_concurrently_up_to_the_number_of_jobs() {
+    let running = Mutex::new((0, 0));
+    // every item waits for two others, so this only finishes when three run at once
+    let barrier = Barrier::new(3);
+
+    parallel_map((0..9).collect(), 3, |_: usize| {
+        {
+            let mut running = running.lock().unwrap();
+            running.0 += 1;
+            running.1 = running.1.max(running.0);
+        }
+        barrier.wait();
+        running.lock().unwrap().0 -= 1;
+    });
+
+    assert_eq!(running.into_inner().unwrap().1, 3);
+}
+
+#[test]
+fn test_runs_on_the_calling_thread_with_a_single_job() {
+    let caller = thread::current().id();
+    let mut seen = vec![];
+
+    for_each_ordered(
+        vec!["a", "b"],
+        1,
+        |item| (item, thread::current().id()),
+        |result| seen.push(result),
+    );
+
+    assert_eq!(seen, vec![("a", caller), ("b", caller)]);
+}
+
+#[test]
+fn test_parses_jobs() {
+    assert_eq!(parse_jobs("4"), Ok(4));
+    assert!(parse_jobs("0").is_err());
+    assert!(parse_jobs("many").is_err());
+}
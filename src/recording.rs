@@ -0,0 +1,216 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+use std::collections::VecDeque;
+
+#[cfg(test)]
+mod tests;
+
+// Every git invocation can be appended to a recording file by setting GIT_TOOLS_RECORD=<file>.
+// A recording can then be replayed in tests so that a bug report becomes a regression test.
+pub const RECORD_ENV_VAR: &str = "GIT_TOOLS_RECORD";
+
+const HEADER: &str = "# git-tools recording v1";
+
+// Each field is written as `<name> <byte length>\n<bytes>\n`, which keeps the file readable while
+// still allowing arguments and output that contain newlines or invalid UTF-8
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: PathBuf,
+    pub status: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl RecordedCommand {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = vec![];
+
+        write_field(&mut bytes, "program", self.program.as_bytes());
+        for arg in &self.args {
+            write_field(&mut bytes, "arg", arg.as_bytes());
+        }
+        write_field(&mut bytes, "cwd", self.cwd.to_string_lossy().as_bytes());
+        if let Some(status) = self.status {
+            write_field(&mut bytes, "status", status.to_string().as_bytes());
+        }
+        write_field(&mut bytes, "stdout", &self.stdout);
+        write_field(&mut bytes, "stderr", &self.stderr);
+        bytes.extend_from_slice(b"end\n");
+
+        bytes
+    }
+}
+
+fn write_field(bytes: &mut Vec<u8>, name: &str, value: &[u8]) {
+    bytes.extend_from_slice(format!("{} {}\n", name, value.len()).as_bytes());
+    bytes.extend_from_slice(value);
+    bytes.push(b'\n');
+}
+
+pub fn append_to_file(path: &Path, command: &RecordedCommand) -> Result<(), String> {
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| format!("Couldn't open recording file {:?}: {}", path, err))?;
+
+    if is_new {
+        file.write_all(format!("{}\n", HEADER).as_bytes())
+            .map_err(|err| format!("Couldn't write to recording file {:?}: {}", path, err))?;
+    }
+
+    file.write_all(&command.serialize())
+        .map_err(|err| format!("Couldn't write to recording file {:?}: {}", path, err))
+}
+
+#[cfg(test)]
+pub fn parse(bytes: &[u8]) -> Result<Vec<RecordedCommand>, String> {
+    let mut reader = Reader { bytes, position: 0 };
+    let mut commands: Vec<RecordedCommand> = vec![];
+
+    if reader.read_line()? != HEADER {
+        return Err("Recording is missing the header line".to_string());
+    }
+
+    while !reader.is_done() {
+        let mut command = RecordedCommand {
+            program: String::new(),
+            args: vec![],
+            cwd: PathBuf::new(),
+            status: None,
+            stdout: vec![],
+            stderr: vec![],
+        };
+
+        loop {
+            let line = reader.read_line()?;
+
+            if line == "end" {
+                break;
+            }
+
+            let (name, length) = line
+                .split_once(' ')
+                .ok_or_else(|| format!("Malformed field header: '{}'", line))?;
+            let length = length
+                .parse::<usize>()
+                .map_err(|_| format!("Malformed field length: '{}'", line))?;
+            let value = reader.read_value(length)?;
+
+            match name {
+                "program" => command.program = String::from_utf8_lossy(value).to_string(),
+                "arg" => command
+                    .args
+                    .push(String::from_utf8_lossy(value).to_string()),
+                "cwd" => command.cwd = PathBuf::from(String::from_utf8_lossy(value).to_string()),
+                "status" => {
+                    command.status = Some(
+                        String::from_utf8_lossy(value)
+                            .parse::<i32>()
+                            .map_err(|_| format!("Malformed status: '{:?}'", value))?,
+                    )
+                }
+                "stdout" => command.stdout = value.to_vec(),
+                "stderr" => command.stderr = value.to_vec(),
+                _ => return Err(format!("Unknown field '{}'", name)),
+            }
+        }
+
+        commands.push(command);
+    }
+
+    Ok(commands)
+}
+
+#[cfg(test)]
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+#[cfg(test)]
+impl<'a> Reader<'a> {
+    fn is_done(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn read_line(&mut self) -> Result<String, String> {
+        let remaining = &self.bytes[self.position..];
+        let end = remaining
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .ok_or("Unexpected end of recording")?;
+
+        self.position += end + 1;
+
+        Ok(String::from_utf8_lossy(&remaining[..end]).to_string())
+    }
+
+    fn read_value(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let end = self.position + length;
+
+        if end >= self.bytes.len() || self.bytes[end] != b'\n' {
+            return Err("Field value is shorter than its declared length".to_string());
+        }
+
+        let value = &self.bytes[self.position..end];
+        self.position = end + 1;
+
+        Ok(value)
+    }
+}
+
+// Serves recorded commands back in the order they were recorded. The working directory isn't
+// compared because recordings usually come from somebody else's machine.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct Replay {
+    commands: VecDeque<RecordedCommand>,
+}
+
+#[cfg(test)]
+impl Replay {
+    pub fn new(commands: Vec<RecordedCommand>) -> Self {
+        Self {
+            commands: commands.into(),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        Ok(Self::new(parse(bytes)?))
+    }
+
+    pub fn next_for(&mut self, program: &str, args: &[String]) -> Result<RecordedCommand, String> {
+        let command = self.commands.pop_front().ok_or_else(|| {
+            format!(
+                "Recording has no more commands, but '{} {}' was run",
+                program,
+                args.join(" ")
+            )
+        })?;
+
+        if command.program != program || command.args != args {
+            return Err(format!(
+                "Expected '{} {}' to be run next, but '{} {}' was run",
+                command.program,
+                command.args.join(" "),
+                program,
+                args.join(" ")
+            ));
+        }
+
+        Ok(command)
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.commands.len()
+    }
+}
@@ -0,0 +1,131 @@
+#[cfg(test)]
+use std::path::PathBuf;
+
+#[cfg(test)]
+use crate::commands::{git_command, start_replay, stop_replay};
+
+#[cfg(test)]
+use crate::pool::set_jobs;
+
+#[cfg(test)]
+use crate::repository::{BareRepository, NormalRepository, Repository};
+
+#[cfg(test)]
+use crate::test_setup::DEFAULT_BRANCH_NAME;
+
+#[cfg(test)]
+use super::{parse, RecordedCommand, Replay};
+
+#[cfg(test)]
+fn recorded(args: Vec<&str>, stdout: &[u8]) -> RecordedCommand {
+    RecordedCommand {
+        program: "git".to_string(),
+        args: args.into_iter().map(String::from).collect(),
+        cwd: PathBuf::from("/a/repo"),
+        status: Some(0),
+        stdout: stdout.to_vec(),
+        stderr: vec![],
+    }
+}
+
+#[test]
+fn test_recorded_commands_survive_a_round_trip() {
+    let commands = vec![
+        recorded(
+            vec!["commit", "-m", "a message\nwith a second line"],
+            b"[main 1234567] a message\n",
+        ),
+        RecordedCommand {
+            program: "git".to_string(),
+            args: vec!["branch".to_string()],
+            cwd: PathBuf::from("/a/repo with  spaces"),
+            status: None,
+            stdout: vec![0xff, 0xfe, b'\n'],
+            stderr: b"killed\n".to_vec(),
+        },
+    ];
+    let mut bytes = format!("{}\n", super::HEADER).into_bytes();
+    for command in &commands {
+        bytes.extend(command.serialize());
+    }
+
+    assert_eq!(commands, parse(&bytes).expect("Couldn't parse recording"));
+}
+
+#[test]
+fn test_recording_without_a_header_is_rejected() {
+    let bytes = recorded(vec!["branch"], b"").serialize();
+
+    assert!(parse(&bytes).is_err());
+}
+
+#[test]
+fn test_replay_serves_commands_in_order() {
+    start_replay(Replay::new(vec![
+        recorded(vec!["--no-pager", "branch"], b"  main\n* some-work\n"),
+        recorded(
+            vec!["--no-pager", "branch", "--show-current"],
+            b"some-work\n",
+        ),
+    ]));
+
+    let branches = git_command(vec!["branch"], &PathBuf::from("/does/not/exist"))
+        .expect("Replayed command failed");
+    let current = git_command(
+        vec!["branch", "--show-current"],
+        &PathBuf::from("/does/not/exist"),
+    )
+    .expect("Replayed command failed");
+    let replay = stop_replay().expect("Replay was not running");
+
+    assert_eq!(vec!["  main", "* some-work"], branches.stdout_lines());
+    assert_eq!(vec!["some-work"], current.stdout_lines());
+    assert_eq!(0, replay.remaining());
+}
+
+#[test]
+fn test_replay_rejects_unexpected_commands() {
+    let mut replay = Replay::new(vec![recorded(vec!["--no-pager", "branch"], b"")]);
+
+    assert!(replay
+        .next_for("git", &["--no-pager".to_string(), "status".to_string()])
+        .is_err());
+}
+
+#[test]
+fn test_clean_merged_replays_a_normal_repository_session() {
+    let replay = Replay::from_bytes(include_bytes!("fixtures/clean_merged_normal_repo.rec"))
+        .expect("Couldn't parse fixture");
+    let repo = Repository::Normal(NormalRepository::new(
+        DEFAULT_BRANCH_NAME.to_string(),
+        PathBuf::from("/a/repo"),
+    ));
+
+    start_replay(replay);
+    let result = repo.clean_merged();
+    let replay = stop_replay().expect("Replay was not running");
+
+    assert!(result.is_ok());
+    assert_eq!(0, replay.remaining());
+}
+
+// The worktrees are checked by worker threads when there are several jobs, which must not happen
+// while replaying
+#[test]
+fn test_clean_merged_replays_a_bare_repository_session() {
+    let replay = Replay::from_bytes(include_bytes!("fixtures/clean_merged_bare_repo.rec"))
+        .expect("Couldn't parse fixture");
+    let repo = Repository::Bare(BareRepository::new(
+        DEFAULT_BRANCH_NAME.to_string(),
+        PathBuf::from("/a/repo"),
+    ));
+
+    set_jobs(4);
+    start_replay(replay);
+    let result = repo.clean_merged();
+    let replay = stop_replay().expect("Replay was not running");
+    set_jobs(0);
+
+    assert!(result.is_ok());
+    assert_eq!(0, replay.remaining());
+}
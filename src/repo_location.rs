@@ -0,0 +1,201 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{commands::git_command, logging::debug, RepoType};
+
+#[cfg(test)]
+mod tests;
+
+// Where a path is in a repository, as git itself sees it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepoLocation {
+    pub repo_type: RepoType,
+    // Where commands run: the top level of the working tree in a normal repository, the directory
+    // holding the `.git` file in the `.bare` layout, or the repository itself when it is bare
+    pub root: PathBuf,
+    // Shared by every worktree, e.g. `.git` or `.bare`
+    pub common_dir: PathBuf,
+    // The git directory of the worktree the path is in, `common_dir` outside of linked worktrees
+    pub git_dir: PathBuf,
+    // The top level of the working tree the path is in, `None` outside of working trees
+    pub worktree: Option<PathBuf>,
+}
+
+// `--show-toplevel` goes last: outside of a working tree it fails after everything else is printed
+const DISCOVERY_ARGS: [&str; 6] = [
+    "rev-parse",
+    "--path-format=absolute",
+    "--git-common-dir",
+    "--is-bare-repository",
+    "--git-dir",
+    "--show-toplevel",
+];
+
+// git commands run without GIT_DIR and GIT_WORK_TREE (see `commands`), so when git-tools was started
+// with them, e.g. from a hook, they are passed to discovery as options, relative to the directory
+// git-tools was started in like git would resolve them
+fn environment_options() -> Vec<String> {
+    [("GIT_DIR", "--git-dir"), ("GIT_WORK_TREE", "--work-tree")]
+        .iter()
+        .filter_map(|(name, option)| {
+            env::var_os(name).map(|value| {
+                let path = match env::current_dir() {
+                    Ok(current_dir) => current_dir.join(value),
+                    Err(_) => PathBuf::from(value),
+                };
+
+                format!("{}={}", option, path.to_string_lossy())
+            })
+        })
+        .collect()
+}
+
+// git before 2.31 doesn't know `--path-format` and prints it back instead of failing, which shifts
+// every line
+fn is_discovery_output(lines: &[String]) -> bool {
+    match lines {
+        [common_dir, bare, git_dir, rest @ ..] if rest.len() <= 1 => {
+            matches!(bare.as_str(), "true" | "false")
+                && [common_dir, git_dir]
+                    .into_iter()
+                    .chain(rest)
+                    .all(|path| Path::new(path).is_absolute())
+        }
+        _ => false,
+    }
+}
+
+// In the `.bare` layout a `.git` file next to the repository points at it
+fn bare_root(common_dir: &Path) -> PathBuf {
+    let parent = match common_dir.parent() {
+        Some(parent) => parent,
+        None => return common_dir.to_path_buf(),
+    };
+    let points_at_repository = fs::read_to_string(parent.join(".git"))
+        .ok()
+        .and_then(|contents| {
+            contents
+                .trim()
+                .strip_prefix("gitdir:")
+                .and_then(|git_dir| parent.join(git_dir.trim()).canonicalize().ok())
+        })
+        .is_some_and(|git_dir| common_dir.canonicalize().is_ok_and(|dir| dir == git_dir));
+
+    match points_at_repository {
+        true => parent.to_path_buf(),
+        false => common_dir.to_path_buf(),
+    }
+}
+
+// Inside a linked worktree git reports whether the worktree is bare, which it never is, so
+// `core.bare` is read from the repository's config files instead of running git a second time.
+// With `extensions.worktreeConfig` it can be in `config.worktree`, which wins.
+fn is_bare_in_config(common_dir: &Path) -> bool {
+    let mut bare = false;
+
+    for file in ["config", "config.worktree"] {
+        let text = fs::read_to_string(common_dir.join(file)).unwrap_or_default();
+        let mut in_core = false;
+
+        for line in text.lines() {
+            let line = line.split(['#', ';']).next().unwrap_or_default().trim();
+
+            if let Some(section) = line.strip_prefix('[') {
+                in_core = section
+                    .split(']')
+                    .next()
+                    .is_some_and(|name| name.trim().eq_ignore_ascii_case("core"));
+                continue;
+            }
+
+            // a key without a value is true
+            let (key, value) = line.split_once('=').unwrap_or((line, "true"));
+            if in_core && key.trim().eq_ignore_ascii_case("bare") {
+                bare = matches!(
+                    value.trim().to_lowercase().as_str(),
+                    "true" | "yes" | "on" | "1"
+                );
+            }
+        }
+    }
+
+    bare
+}
+
+impl RepoLocation {
+    pub fn discover(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Err(format!("{:?} doesn't exist", path));
+        }
+
+        let options = environment_options();
+        let args = options
+            .iter()
+            .map(String::as_str)
+            .chain(DISCOVERY_ARGS)
+            .collect::<Vec<&str>>();
+        let output = match git_command(args, path) {
+            Ok(output) => output,
+            Err(output) => output,
+        };
+        let lines = output.stdout_lines();
+
+        let (common_dir, bare, git_dir) = match lines.as_slice() {
+            [common_dir, bare, git_dir, ..] => (
+                PathBuf::from(common_dir),
+                bare == "true",
+                PathBuf::from(git_dir),
+            ),
+            _ => {
+                return Err(format!(
+                    "{:?} is not in a git repository: {}",
+                    path,
+                    output.error_message()
+                ))
+            }
+        };
+        if !is_discovery_output(&lines) {
+            return Err(format!(
+                "Couldn't make sense of `git rev-parse` in {:?}, git 2.31 or newer is needed: {}",
+                path,
+                lines.join(", ")
+            ));
+        }
+        let worktree = lines.get(3).map(PathBuf::from);
+
+        let repo_type = match bare || (git_dir != common_dir && is_bare_in_config(&common_dir)) {
+            true => RepoType::Bare,
+            false => RepoType::Normal,
+        };
+        let root = match (&repo_type, &worktree) {
+            (RepoType::Bare, _) => bare_root(&common_dir),
+            (RepoType::Normal, Some(worktree)) => worktree.clone(),
+            (RepoType::Normal, None) => {
+                return Err(format!(
+                    "{:?} is in the git directory of a repository, not in its working tree",
+                    path
+                ))
+            }
+        };
+
+        debug!(
+            "{:?} is in the {} repository at {:?}",
+            path,
+            match repo_type {
+                RepoType::Bare => "bare",
+                RepoType::Normal => "normal",
+            },
+            root
+        );
+
+        Ok(Self {
+            repo_type,
+            root,
+            common_dir,
+            git_dir,
+            worktree,
+        })
+    }
+}
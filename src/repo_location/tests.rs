@@ -0,0 +1,183 @@
+#[cfg(test)]
+use std::{fs, path::Path};
+
+#[cfg(test)]
+use super::{is_bare_in_config, is_discovery_output, RepoLocation};
+
+#[cfg(test)]
+use crate::{
+    clone::clone_bare,
+    commands::git_command,
+    test_helpers::run_test,
+    test_setup::{BARE_REPO_NAME, CLEAN_NORMAL_REPO_NAME, DEFAULT_BRANCH_NAME},
+    RepoType,
+};
+
+#[cfg(test)]
+fn discover(path: &Path) -> RepoLocation {
+    RepoLocation::discover(path).unwrap_or_else(|msg| panic!("{}", msg))
+}
+
+#[test]
+fn test_finds_normal_repositories_and_their_linked_worktrees() {
+    run_test(
+        "test_finds_normal_repositories_and_their_linked_worktrees",
+        CLEAN_NORMAL_REPO_NAME,
+        |repo| {
+            let root = repo.root();
+            let subdirectory = root.join("sub").join("directory");
+            fs::create_dir_all(&subdirectory).unwrap();
+
+            let location = discover(&subdirectory);
+            assert_eq!(location.repo_type, RepoType::Normal);
+            assert_eq!(&location.root, root);
+            assert_eq!(location.git_dir, root.join(".git"));
+            assert_eq!(location.common_dir, root.join(".git"));
+            assert_eq!(location.worktree.as_ref(), Some(root));
+
+            let linked = root.parent().unwrap().join("linked");
+            git_command(
+                vec!["worktree", "add", linked.to_str().unwrap(), "unmerged"],
+                root,
+            )
+            .unwrap();
+
+            let location = discover(&linked);
+            assert_eq!(location.repo_type, RepoType::Normal);
+            assert_eq!(location.root, linked);
+            assert_eq!(location.common_dir, root.join(".git"));
+            assert_eq!(location.git_dir, root.join(".git/worktrees/linked"));
+
+            // there's no working tree to run commands in
+            assert!(RepoLocation::discover(&root.join(".git")).is_err());
+        },
+    );
+}
+
+#[test]
+fn test_finds_the_root_of_bare_repositories_from_their_worktrees() {
+    run_test(
+        "test_finds_the_root_of_bare_repositories_from_their_worktrees",
+        BARE_REPO_NAME,
+        |repo| {
+            let root = repo.root();
+
+            for path in [root.clone(), root.join("refs"), root.join("merged")] {
+                let location = discover(&path);
+                assert_eq!(location.repo_type, RepoType::Bare, "{:?}", path);
+                assert_eq!(&location.root, root, "{:?}", path);
+                assert_eq!(&location.common_dir, root, "{:?}", path);
+            }
+
+            assert_eq!(discover(root).worktree, None);
+            assert_eq!(
+                discover(&root.join("merged")).worktree,
+                Some(root.join("merged"))
+            );
+        },
+    );
+}
+
+#[test]
+fn test_finds_the_root_of_the_bare_layout() {
+    run_test(
+        "test_finds_the_root_of_the_bare_layout",
+        CLEAN_NORMAL_REPO_NAME,
+        |repo| {
+            let target = repo.root().parent().unwrap().join("layout");
+            let worktree =
+                clone_bare(repo.root(), repo.root().to_str().unwrap(), &target, None).unwrap();
+
+            for path in [target.clone(), target.join(".bare"), worktree.clone()] {
+                let location = discover(&path);
+                assert_eq!(location.repo_type, RepoType::Bare, "{:?}", path);
+                assert_eq!(location.root, target, "{:?}", path);
+                assert_eq!(location.common_dir, target.join(".bare"), "{:?}", path);
+            }
+
+            assert_eq!(worktree, target.join(DEFAULT_BRANCH_NAME));
+        },
+    );
+}
+
+#[test]
+fn test_submodules_are_repositories_of_their_own() {
+    run_test(
+        "test_submodules_are_repositories_of_their_own",
+        BARE_REPO_NAME,
+        |repo| {
+            let worktree = repo.root().join("merged");
+            let source = repo.root().parent().unwrap().join("bare_repo_source");
+            git_command(
+                vec![
+                    "-c",
+                    "protocol.file.allow=always",
+                    "submodule",
+                    "add",
+                    source.to_str().unwrap(),
+                    "module",
+                ],
+                &worktree,
+            )
+            .unwrap();
+
+            let location = discover(&worktree.join("module"));
+            assert_eq!(location.repo_type, RepoType::Normal);
+            assert_eq!(location.root, worktree.join("module"));
+        },
+    );
+}
+
+#[test]
+fn test_fails_outside_of_repositories() {
+    assert!(RepoLocation::discover(Path::new("/nonexistent/directory")).is_err());
+}
+
+#[test]
+fn test_output_of_git_without_path_format_is_rejected() {
+    let lines = |lines: &[&str]| {
+        lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert!(is_discovery_output(&lines(&[
+        "/repo/.git",
+        "false",
+        "/repo/.git",
+        "/repo"
+    ])));
+    assert!(is_discovery_output(&lines(&["/repo", "true", "/repo"])));
+    assert!(!is_discovery_output(&lines(&[
+        "--path-format=absolute",
+        ".git",
+        "false",
+        ".git",
+        "/repo"
+    ])));
+    assert!(!is_discovery_output(&lines(&[
+        ".git", "false", ".git", "/repo"
+    ])));
+}
+
+#[test]
+fn test_reads_core_bare_from_the_config_files() {
+    let common_dir = std::env::temp_dir().join("git-tools-is-bare-in-config");
+    fs::create_dir_all(&common_dir).unwrap();
+    let write = |file: &str, text: &str| fs::write(common_dir.join(file), text).unwrap();
+
+    write(
+        "config",
+        "[core]\n\trepositoryformatversion = 0\n\tbare = true\n[remote \"origin\"]\n\tbare = false\n",
+    );
+    assert!(is_bare_in_config(&common_dir));
+
+    write("config", "[Core]\n\tBare = false # converted\n");
+    assert!(!is_bare_in_config(&common_dir));
+
+    write("config.worktree", "[core]\n\tbare\n");
+    assert!(is_bare_in_config(&common_dir));
+
+    fs::remove_dir_all(&common_dir).unwrap();
+}
@@ -2,16 +2,22 @@ use std::path::{Path, PathBuf};
 
 use crate::{
     commands::git_command,
-    utils::{get_bare_root, get_current_branch_name, get_normal_root, is_bare_repo},
+    config::Config,
+    hooks::RemoveHooks,
+    logging::debug,
+    output::out,
+    pool::{jobs, parallel_map},
+    repo_location::RepoLocation,
+    signals::interrupted,
+    utils::get_current_branch_name,
     worktree::Worktree,
-    worktree_list_item::WorktreeListItem,
+    worktree_status::worktree_entries,
+    RepoType,
 };
 
 #[cfg(test)]
 mod tests;
 
-const MAIN_BRANCH_NAMES: [&str; 2] = ["main", "master"];
-
 pub enum Repository {
     Bare(BareRepository),
     Normal(NormalRepository),
@@ -38,80 +44,141 @@ impl BareRepository {
     }
 
     pub fn at(path: &Path) -> Option<Self> {
-        if !path.exists() {
-            return None;
-        }
-
-        if !is_repo(path) {
-            return None;
+        match Repository::at(path) {
+            Ok(Repository::Bare(bare)) => Some(bare),
+            _ => None,
         }
-
-        Some(Self {
-            main_branch_name: find_main_branch_name(path),
-            root: get_bare_root(path).expect("Expected to find a bare repo root, but didn't"),
-        })
     }
 
     fn clean_merged_impl(&self) -> Result<(), String> {
         let worktrees = self
             .merged_worktrees()
             .expect("Couldn't get the list of merged worktrees");
+        let config = Config::load(Some(&self.root));
+        let hooks = RemoveHooks::from(&config);
+        let mut deleted: Vec<String> = vec![];
+        // `git status` is the slow part, so every worktree is checked up front and concurrently
+        let clean = parallel_map(worktrees.iter().collect(), jobs(), |worktree: &Worktree| {
+            worktree.is_clean()
+        });
 
-        for worktree in worktrees {
-            if worktree.is_clean() {
-                if worktree.name != self.main_branch_name {
-                    match worktree.delete() {
-                        Ok(_) => println!("Deleted worktree: {}", worktree.path),
-                        Err(msg) => println!(
+        for (index, worktree) in worktrees.iter().enumerate() {
+            if interrupted() {
+                let not_processed = worktrees[index..]
+                    .iter()
+                    .map(|w| w.path.clone())
+                    .collect::<Vec<String>>();
+
+                return Err(interrupted_summary(
+                    "worktrees",
+                    "Deleted",
+                    &deleted,
+                    &not_processed,
+                ));
+            }
+
+            if config.is_protected(&worktree.name) {
+                debug!(
+                    "skipping worktree '{}' ({}): the branch is protected",
+                    worktree.name, worktree.path
+                );
+                out!(
+                    "Skipped worktree '{}' ({}) because its branch is protected",
+                    worktree.name,
+                    worktree.path
+                );
+            } else if clean[index] {
+                if worktree.name == self.main_branch_name {
+                    debug!(
+                        "skipping worktree '{}' ({}): it holds the main branch",
+                        worktree.name, worktree.path
+                    );
+                } else {
+                    debug!(
+                        "deleting worktree '{}' ({}): it is merged and clean",
+                        worktree.name, worktree.path
+                    );
+                    match worktree.delete(&hooks) {
+                        Ok(hook_failures) => {
+                            out!("Deleted worktree: {}", worktree.path);
+                            deleted.push(worktree.path.clone());
+
+                            for failure in hook_failures {
+                                out!("Deleted worktree '{}', but its {}", worktree.path, failure);
+                            }
+                        }
+                        Err(msg) => out!(
                             "Couldn't delete worktree '{}', error: {}",
-                            worktree.path, msg
+                            worktree.path,
+                            msg
                         ),
                     }
                 }
             } else {
-                println!(
-                    "Couldn't delete worktree '{}' ({}) because it contains unstaged changes",
+                debug!(
+                    "skipping worktree '{}' ({}): `git status` reported changes",
                     worktree.name, worktree.path
                 );
+                out!(
+                    "Couldn't delete worktree '{}' ({}) because it contains unstaged changes",
+                    worktree.name,
+                    worktree.path
+                );
             }
         }
 
         Ok(())
     }
 
-    pub fn all_worktrees(&self) -> Result<Vec<Worktree>, String> {
-        let worktrees = git_command(vec!["worktree", "list"], &self.root)
-            .expect("Couldn't get worktree names")
-            .output
+    // The worktrees that have a branch checked out, the bare repository and detached worktrees
+    // aren't included
+    pub fn all_worktrees(&self) -> Result<Vec<Worktree<'_>>, String> {
+        let worktrees = worktree_entries(&self.root)?
             .into_iter()
-            .map(|line| WorktreeListItem::new(self, line))
-            .filter_map(
-                |list_item| match list_item.is_bare() || list_item.is_detached() {
-                    true => None,
-                    false => Some(
-                        Worktree::try_from(list_item)
-                            .expect("Couldn't create Worktree from WorktreeListItem"),
-                    ),
-                },
-            )
+            .filter(|entry| !entry.is_bare)
+            .filter_map(|entry| {
+                entry.branch.map(|name| Worktree {
+                    path: entry.path,
+                    name,
+                    repository: self,
+                })
+            })
             .collect::<Vec<Worktree>>();
 
         Ok(worktrees)
     }
 
-    fn merged_worktrees(&self) -> Result<Vec<Worktree>, String> {
+    fn merged_worktrees(&self) -> Result<Vec<Worktree<'_>>, String> {
         let merged = merged_branches(&self.main_branch_name, &self.root)
             .expect("Couldn't get merged branches");
         let all = self.all_worktrees().expect("Couldn't get all worktrees");
         let not_merged = all
             .into_iter()
-            .filter(|w| merged.contains(&w.name))
+            .filter(|w| {
+                let is_merged = merged.contains(&w.name);
+
+                if !is_merged {
+                    debug!(
+                        "skipping worktree '{}' ({}): not merged into '{}'",
+                        w.name, w.path, self.main_branch_name
+                    );
+                }
+
+                is_merged
+            })
             .collect::<Vec<Worktree>>();
 
         Ok(not_merged)
     }
 
-    #[cfg(test)]
+    // The worktree that has the main branch checked out, if there is one
+    pub fn main_worktree(&self) -> Option<Worktree<'_>> {
+        self.all_worktrees()
+            .ok()?
+            .into_iter()
+            .find(|worktree| worktree.name == self.main_branch_name)
+    }
+
     pub fn main_branch_name(&self) -> &String {
         &self.main_branch_name
     }
@@ -122,110 +189,169 @@ impl BareRepository {
 }
 
 impl NormalRepository {
-    pub fn at(path: &Path) -> Option<Self> {
-        if !path.exists() {
-            return None;
+    #[cfg(test)]
+    pub fn new(main_branch_name: String, root: PathBuf) -> Self {
+        Self {
+            main_branch_name,
+            root,
         }
+    }
 
-        if !is_repo(path) {
-            return None;
+    #[cfg(test)]
+    pub fn at(path: &Path) -> Option<Self> {
+        match Repository::at(path) {
+            Ok(Repository::Normal(normal)) => Some(normal),
+            _ => None,
         }
-
-        Some(Self {
-            main_branch_name: find_main_branch_name(path),
-            root: get_normal_root(path).expect("Expected to find a repo root, but didn't"),
-        })
     }
 
+    // Merge status is computed against the main branch ref, so nothing has to be checked out and
+    // a dirty working tree only matters when the current branch itself is merged
     fn clean_merged_impl(&self) -> Result<(), String> {
-        self.validate_cleanliness()?;
-
-        let branches = merged_branches(&self.main_branch_name, &self.root)
-            .expect("couldn't get list of merged branches");
-        let current_branch = get_current_branch_name(&self.root);
+        let config = Config::load(Some(&self.root));
+        let branches = merged_branch_tips(&self.main_branch_name, &self.root)?
+            .into_iter()
+            .filter(|branch| {
+                let protected = config.is_protected(&branch.name);
 
-        let mut deleted_current_branch = false;
+                if protected {
+                    debug!("skipping branch '{}': it is protected", branch.name);
+                    out!("Skipped branch '{}' because it is protected", branch.name);
+                }
 
-        git_command(vec!["checkout", &self.main_branch_name], &self.root).unwrap_or_else(|_| {
-            panic!("Failed to checkout the '{}' branch", self.main_branch_name)
-        });
+                !protected
+            })
+            .collect::<Vec<BranchTip>>();
+        let current_branch = get_current_branch_name(&self.root);
+        let checked_out = checked_out_branch_names(&self.root);
+        let mut merged_current_branch: Option<&BranchTip> = None;
+        let mut deleted: Vec<String> = vec![];
+
+        for (index, branch) in branches.iter().enumerate() {
+            if interrupted() {
+                let not_processed = branches[index..]
+                    .iter()
+                    .map(|b| b.name.clone())
+                    .collect::<Vec<String>>();
+
+                return Err(interrupted_summary(
+                    "branches",
+                    "Deleted",
+                    &deleted,
+                    &not_processed,
+                ));
+            }
 
-        for branch in branches {
-            git_command(vec!["branch", "-d", branch.as_str()], &self.root).unwrap_or_else(|m| {
-                panic!(
-                    "An error occurred while deleting the '{}' branch\n\n{}",
-                    branch,
-                    m.output.join("\n")
-                )
-            });
-
-            if branch == current_branch {
-                deleted_current_branch = true;
+            if current_branch.as_ref() == Some(&branch.name) {
+                merged_current_branch = Some(branch);
+                continue;
             }
 
-            println!("Deleted branch: {}", branch);
-        }
+            if checked_out.contains(&branch.name) {
+                debug!(
+                    "skipping branch '{}': it is checked out in another worktree",
+                    branch.name
+                );
+                out!(
+                    "Couldn't delete branch '{}' because it is checked out in another worktree",
+                    branch.name
+                );
+                continue;
+            }
 
-        if !deleted_current_branch {
-            git_command(vec!["checkout", current_branch.as_str()], &self.root).unwrap_or_else(
-                |_| {
-                    panic!(
-                        "Failed to checkout the original branch ({})",
-                        current_branch
-                    )
-                },
+            debug!(
+                "deleting branch '{}': it is merged into '{}'",
+                branch.name, self.main_branch_name
             );
+            delete_branch_ref(branch, &self.root)?;
+            out!("Deleted branch: {}", branch.name);
+            deleted.push(branch.name.clone());
         }
 
-        Ok(())
+        match merged_current_branch {
+            Some(branch) => self.delete_current_branch(branch, &deleted),
+            None => Ok(()),
+        }
     }
 
-    fn validate_cleanliness(&self) -> Result<bool, String> {
-        match self.is_clean() {
-            Ok(true) => Ok(true),
-            Ok(false) => Err("Repository has uncommitted changes, please commit, stash, or delete these changes and then try again".to_string()),
-            Err(_) => Err("Repository has uncommitted changes, please commit, stash, or delete these changes and then try again".to_string())
+    // The current branch can only be deleted after switching to the main branch, which is only
+    // safe when there is nothing in the working tree that the switch could clobber. `deleted` are
+    // the branches deleted before it, for the summary when interrupted.
+    fn delete_current_branch(&self, branch: &BranchTip, deleted: &[String]) -> Result<(), String> {
+        if interrupted() {
+            return Err(interrupted_summary(
+                "branches",
+                "Deleted",
+                deleted,
+                std::slice::from_ref(&branch.name),
+            ));
         }
+
+        if !self.is_clean()? {
+            debug!(
+                "skipping branch '{}': it is checked out and `git status` reported changes",
+                branch.name
+            );
+            out!(
+                "Couldn't delete branch '{}' because it is checked out and the working tree has uncommitted changes",
+                branch.name
+            );
+            return Ok(());
+        }
+
+        debug!(
+            "checking out '{}' so that the merged current branch '{}' can be deleted",
+            self.main_branch_name, branch.name
+        );
+        git_command(vec!["checkout", &self.main_branch_name], &self.root).map_err(|m| {
+            format!(
+                "Failed to checkout the '{}' branch: {}",
+                self.main_branch_name,
+                m.error_message()
+            )
+        })?;
+        git_command(vec!["branch", "-d", &branch.name], &self.root).map_err(|m| {
+            format!(
+                "An error occurred while deleting the '{}' branch\n\n{}",
+                branch.name,
+                m.error_message()
+            )
+        })?;
+        out!("Deleted branch: {}", branch.name);
+
+        Ok(())
     }
 
     fn is_clean(&self) -> Result<bool, String> {
         let result = git_command(vec!["status", "--short"], &self.root);
 
         match result {
-            Ok(res) => Ok(res.output.is_empty()),
+            Ok(res) => Ok(res.is_stdout_empty()),
             Err(res) => Err(format!(
                 "An error occurred while checking if the repo was clean: {}",
-                res.output.join("")
+                res.error_message()
             )),
         }
     }
 }
 
 impl Repository {
-    pub fn at(path: &PathBuf) -> Option<Repository> {
-        if !path.exists() {
-            return None;
-        }
-
-        if !is_repo(path) {
-            return None;
-        }
-
-        let repo: Repository;
-
-        if is_bare_repo(path) {
-            repo = Repository::Bare(
-                BareRepository::at(path)
-                    .unwrap_or_else(|| panic!("{:#?} is not a valid git repository", path)),
-            );
-        } else {
-            repo = Repository::Normal(
-                NormalRepository::at(path)
-                    .unwrap_or_else(|| panic!("{:#?} is not a valid git repository", path)),
-            );
-        }
-
-        Some(repo)
+    // The repository `path` is in, found the way git finds it
+    pub fn at(path: &Path) -> Result<Repository, String> {
+        let location = RepoLocation::discover(path)?;
+        let main_branch_name = find_main_branch_name(&location.root)?;
+        let root = location.root;
+
+        Ok(match location.repo_type {
+            RepoType::Bare => Repository::Bare(BareRepository {
+                main_branch_name,
+                root,
+            }),
+            RepoType::Normal => Repository::Normal(NormalRepository {
+                main_branch_name,
+                root,
+            }),
+        })
     }
 
     pub fn clean_merged(&self) -> Result<(), String> {
@@ -235,56 +361,168 @@ impl Repository {
         }
     }
 
-    #[cfg(test)]
     pub fn root(&self) -> &PathBuf {
         match self {
             Repository::Normal(normal) => &normal.root,
             Repository::Bare(bare) => &bare.root,
         }
     }
+
+    pub fn main_branch_name(&self) -> &String {
+        match self {
+            Repository::Normal(normal) => &normal.main_branch_name,
+            Repository::Bare(bare) => &bare.main_branch_name,
+        }
+    }
 }
 
-fn is_repo(path: &Path) -> bool {
-    git_command(vec!["branch"], path).is_ok()
+pub fn interrupted_summary(
+    kind: &str,
+    done_label: &str,
+    done: &[String],
+    not_processed: &[String],
+) -> String {
+    let list = |items: &[String]| match items.is_empty() {
+        true => "none".to_string(),
+        false => items.join(", "),
+    };
+
+    format!(
+        "Interrupted before all {} were processed\n  {}: {}\n  Not processed: {}",
+        kind,
+        done_label,
+        list(done),
+        list(not_processed)
+    )
 }
 
-fn clean_branch_name(branch: &String) -> String {
-    branch.split_whitespace().last().unwrap().to_string()
+// `for-each-ref` prints exactly one full branch name per line regardless of the user's config,
+// unlike `git branch` which adds markers, colors and columns
+const BRANCH_NAME_FORMAT: &str = "--format=%(refname:lstrip=2)";
+
+struct BranchTip {
+    name: String,
+    oid: String,
 }
 
-fn merged_branches(main_branch_name: &String, repo_path: &Path) -> Result<Vec<String>, String> {
+fn merged_branch_tips(
+    main_branch_name: &String,
+    repo_path: &Path,
+) -> Result<Vec<BranchTip>, String> {
+    let merged_arg = format!("--merged={}", main_branch_name);
+
     match git_command(
-        vec!["branch", "--merged", main_branch_name.as_str()],
+        vec![
+            "for-each-ref",
+            "--format=%(objectname) %(refname:lstrip=2)",
+            &merged_arg,
+            "refs/heads/",
+        ],
         repo_path,
     ) {
         Ok(result) => Ok(result
-            .output
-            .iter()
-            .map(clean_branch_name)
-            .filter(|branch| branch.to_string() != *main_branch_name)
-            .collect::<Vec<String>>()),
+            .stdout_lines()
+            .into_iter()
+            .filter_map(|line| {
+                line.split_once(' ').map(|(oid, name)| BranchTip {
+                    name: name.to_string(),
+                    oid: oid.to_string(),
+                })
+            })
+            .filter(|branch| branch.name != *main_branch_name)
+            .collect::<Vec<BranchTip>>()),
         Err(res) => Err(format!(
             "An error occurred while getting merged branch list: {}",
-            res.output.join("")
+            res.error_message()
         )),
     }
 }
 
-fn find_main_branch_name(repo_path: &Path) -> String {
-    all_branch_names(repo_path)
+// Deleting the ref directly (instead of `git branch -d`) doesn't require the branch to be merged
+// into HEAD, and passing the expected tip makes the deletion fail if the branch moved since its
+// merge status was checked
+fn delete_branch_ref(branch: &BranchTip, repo_path: &Path) -> Result<(), String> {
+    let ref_name = format!("refs/heads/{}", branch.name);
+
+    git_command(vec!["update-ref", "-d", &ref_name, &branch.oid], repo_path).map_err(|m| {
+        format!(
+            "An error occurred while deleting the '{}' branch\n\n{}",
+            branch.name,
+            m.error_message()
+        )
+    })?;
+
+    // `git branch -d` also removes the branch's tracking configuration, which may not exist
+    let section = format!("branch.{}", branch.name);
+    let _ = git_command(vec!["config", "--remove-section", &section], repo_path);
+
+    Ok(())
+}
+
+// Branches that are checked out in any worktree, including the main one
+fn checked_out_branch_names(repo_path: &Path) -> Vec<String> {
+    worktree_entries(repo_path)
+        .unwrap_or_default()
         .into_iter()
-        .filter(|branch| MAIN_BRANCH_NAMES.contains(&branch.as_str()))
-        .collect::<Vec<String>>()
-        .first()
-        .expect("No main branch found")
-        .to_owned()
+        .filter_map(|entry| entry.branch)
+        .collect()
 }
 
-pub fn all_branch_names(repo_path: &Path) -> Vec<String> {
-    git_command(vec!["branch"], repo_path)
-        .expect("Couldn't get branch names")
-        .output
-        .iter()
-        .map(clean_branch_name)
-        .collect::<Vec<String>>()
+pub fn merged_branches(main_branch_name: &String, repo_path: &Path) -> Result<Vec<String>, String> {
+    let merged_arg = format!("--merged={}", main_branch_name);
+
+    match git_command(
+        vec![
+            "for-each-ref",
+            BRANCH_NAME_FORMAT,
+            &merged_arg,
+            "refs/heads/",
+        ],
+        repo_path,
+    ) {
+        Ok(result) => Ok(result
+            .stdout_lines()
+            .into_iter()
+            .filter(|branch| branch != main_branch_name)
+            .collect::<Vec<String>>()),
+        Err(res) => Err(format!(
+            "An error occurred while getting merged branch list: {}",
+            res.error_message()
+        )),
+    }
+}
+
+fn find_main_branch_name(repo_path: &Path) -> Result<String, String> {
+    let config = Config::load(Some(repo_path));
+    let main_branch_name = match config.main_branch_in(&all_branch_names(repo_path)?) {
+        Some(main_branch_name) => main_branch_name,
+        None => {
+            return Err(format!(
+                "No main branch found, none of {} exist. Set it with `git config git-tools.mainBranch <branch>` or --main-branch",
+                config.main_branch_candidates.value.join(", ")
+            ))
+        }
+    };
+
+    match config.main_branch.value {
+        Some(_) => debug!(
+            "using '{}' as the main branch ({})",
+            main_branch_name, config.main_branch.source
+        ),
+        None => debug!(
+            "using '{}' as the main branch (candidates: {:?})",
+            main_branch_name, config.main_branch_candidates.value
+        ),
+    }
+
+    Ok(main_branch_name)
+}
+
+pub fn all_branch_names(repo_path: &Path) -> Result<Vec<String>, String> {
+    git_command(
+        vec!["for-each-ref", BRANCH_NAME_FORMAT, "refs/heads/"],
+        repo_path,
+    )
+    .map(|result| result.stdout_lines())
+    .map_err(|result| format!("Couldn't get branch names: {}", result.error_message()))
 }
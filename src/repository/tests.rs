@@ -7,6 +7,9 @@ use std::fs::create_dir;
 #[cfg(test)]
 use std::path::PathBuf;
 
+#[cfg(test)]
+use crate::commands::git_command;
+
 #[cfg(test)]
 use crate::repository::all_branch_names;
 
@@ -31,7 +34,7 @@ fn test_repository_at_returns_a_bare_repository_for_the_bare_root_path() {
         "test_repository_at_returns_a_bare_repository_for_the_bare_root_path",
         BARE_REPO_NAME,
         |repo| match Repository::at(repo.root()) {
-            Some(r) => assert!(matches!(r, Repository::Bare(_))),
+            Ok(r) => assert!(matches!(r, Repository::Bare(_))),
             _ => panic!("Should have returned a BareRepository, but didn't"),
         },
     );
@@ -43,7 +46,7 @@ fn test_repository_at_returns_a_bare_repository_for_a_valid_repo_subdirectory_pa
         "test_repository_at_returns_a_bare_repository_for_a_valid_repo_subdirectory_path",
         BARE_REPO_NAME,
         |repo| match Repository::at(&repo.root().join("merged")) {
-            Some(r) => assert!(matches!(r, Repository::Bare(_))),
+            Ok(r) => assert!(matches!(r, Repository::Bare(_))),
             _ => panic!("Should have returned a BareRepository, but didn't"),
         },
     );
@@ -55,27 +58,27 @@ fn test_repository_at_returns_a_normal_repository() {
         "test_repository_at_returns_a_normal_repository",
         CLEAN_NORMAL_REPO_NAME,
         |repo| match Repository::at(repo.root()) {
-            Some(r) => assert!(!matches!(r, Repository::Bare(_))),
+            Ok(r) => assert!(!matches!(r, Repository::Bare(_))),
             _ => panic!("Should have returned a BareRepository, but didn't"),
         },
     );
 }
 
 #[test]
-fn test_repository_at_returns_none_for_an_invalid_path() {
+fn test_repository_at_fails_for_an_invalid_path() {
     let path = PathBuf::from("/tmp/invalid-repo-path");
     let repo = Repository::at(&path);
 
-    assert!(repo.is_none());
+    assert!(repo.is_err());
 }
 
 #[test]
-fn test_repository_at_returns_none_for_a_non_repo_path() {
+fn test_repository_at_fails_for_a_non_repo_path() {
     // use /tmp or equivalent because it is guaranteed to exist and also will not be a repo path
     let path = temp_dir();
     let repo = Repository::at(&path);
 
-    assert!(repo.is_none());
+    assert!(repo.is_err());
 }
 
 #[test]
@@ -236,10 +239,64 @@ fn test_all_branch_names_returns_correct_list() {
         "test_all_branch_names_returns_correct_list",
         CLEAN_NORMAL_REPO_NAME,
         |repo| {
-            let names = all_branch_names(repo.root());
+            let names = all_branch_names(repo.root()).expect("Couldn't get branch names");
             println!("names: {:?}", names);
 
             assert_eq!(vec![DEFAULT_BRANCH_NAME, "merged", "unmerged"], names);
         },
     );
 }
+
+#[test]
+fn test_all_branch_names_fails_outside_of_a_repository() {
+    assert!(all_branch_names(&temp_dir()).is_err());
+}
+
+#[test]
+fn test_all_worktrees_skips_the_bare_repository_and_detached_worktrees() {
+    run_test(
+        "test_all_worktrees_skips_the_bare_repository_and_detached_worktrees",
+        BARE_REPO_NAME,
+        |repo| {
+            let bare = match &repo {
+                Repository::Bare(bare) => bare,
+                Repository::Normal(_) => panic!("Expected a bare repository"),
+            };
+            git_command(
+                vec![
+                    "worktree",
+                    "add",
+                    "--detach",
+                    "detached",
+                    DEFAULT_BRANCH_NAME,
+                ],
+                repo.root(),
+            )
+            .expect("Couldn't add a detached worktree");
+
+            let worktrees = bare.all_worktrees().expect("Couldn't list worktrees");
+            let mut names = worktrees
+                .iter()
+                .map(|worktree| worktree.name.as_str())
+                .collect::<Vec<&str>>();
+            names.sort();
+
+            assert_eq!(
+                vec![
+                    "dirty",
+                    DEFAULT_BRANCH_NAME,
+                    "merged",
+                    "other-branch",
+                    "unmerged",
+                    "wont-match-path"
+                ],
+                names
+            );
+            let merged = worktrees.iter().find(|worktree| worktree.name == "merged");
+            assert_eq!(
+                Some(repo.root().join("merged").to_str().unwrap()),
+                merged.map(|worktree| worktree.path.as_str())
+            );
+        },
+    );
+}
@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests;
+
+pub const SUPPORTED_SHELLS: [&str; 3] = ["bash", "zsh", "fish"];
+
+// A process can't change its parent shell's directory, so `switch` and `pick --switch` print the
+// directory and these wrappers `cd` into it. Branch names can't start with a slash, so any other
+// output is printed as-is. Every other command is passed through untouched.
+const POSIX_INIT: &str = r#"git-tools() {
+    case "$1" in
+        switch|pick)
+            local output
+            output="$(command git-tools "$@")" || return $?
+            case "$output" in
+                /*) cd -- "$output" ;;
+                ?*) printf '%s\n' "$output" ;;
+            esac
+            ;;
+        *)
+            command git-tools "$@"
+            ;;
+    esac
+}
+"#;
+
+const FISH_INIT: &str = r#"function git-tools --wraps git-tools
+    switch "$argv[1]"
+        case switch pick
+            set -l output (command git-tools $argv); or return $status
+            if string match -q -- '/*' "$output"
+                cd -- $output
+            else if test -n "$output"
+                printf '%s\n' $output
+            end
+        case '*'
+            command git-tools $argv
+    end
+end
+"#;
+
+pub fn init_script(shell: &str) -> Result<&'static str, String> {
+    match shell {
+        "bash" | "zsh" => Ok(POSIX_INIT),
+        "fish" => Ok(FISH_INIT),
+        _ => Err(format!(
+            "Unsupported shell '{}', expected one of: {}",
+            shell,
+            SUPPORTED_SHELLS.join(", ")
+        )),
+    }
+}
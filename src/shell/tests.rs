@@ -0,0 +1,17 @@
+#[cfg(test)]
+use super::{init_script, SUPPORTED_SHELLS};
+
+#[test]
+fn test_every_supported_shell_has_a_wrapper() {
+    for shell in SUPPORTED_SHELLS {
+        let script = init_script(shell).expect("Missing init script");
+
+        assert!(script.contains("command git-tools"));
+        assert!(script.contains("switch"));
+    }
+}
+
+#[test]
+fn test_unknown_shells_are_rejected() {
+    assert!(init_script("powershell").is_err());
+}
@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(test)]
+use std::cell::Cell;
+
+// Number of times SIGINT/SIGTERM has been received. The first one asks the current operation to
+// stop after its current step, the second one exits immediately.
+static INTERRUPTS: AtomicUsize = AtomicUsize::new(0);
+
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+#[cfg(unix)]
+mod unix {
+    use std::{os::raw::c_int, sync::atomic::Ordering};
+
+    use super::{INTERRUPTED_EXIT_CODE, INTERRUPTS};
+
+    const SIGINT: c_int = 2;
+    const SIGTERM: c_int = 15;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+        fn _exit(status: c_int) -> !;
+    }
+
+    // Only async-signal-safe operations are allowed in here
+    extern "C" fn handle(_signum: c_int) {
+        if INTERRUPTS.fetch_add(1, Ordering::SeqCst) > 0 {
+            unsafe { _exit(INTERRUPTED_EXIT_CODE) }
+        }
+    }
+
+    pub fn install() {
+        unsafe {
+            signal(SIGINT, handle);
+            signal(SIGTERM, handle);
+        }
+    }
+}
+
+pub fn install_handler() {
+    #[cfg(unix)]
+    unix::install();
+}
+
+#[cfg(test)]
+thread_local! {
+    // Number of `interrupted()` checks that pass before the current thread pretends Ctrl-C was
+    // pressed
+    static SIMULATED_INTERRUPT: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+#[cfg(test)]
+pub fn simulate_interrupt_after(checks: Option<usize>) {
+    SIMULATED_INTERRUPT.with(|s| s.set(checks));
+}
+
+#[cfg(test)]
+fn simulated_interrupt() -> bool {
+    SIMULATED_INTERRUPT.with(|s| match s.get() {
+        Some(0) => true,
+        Some(remaining) => {
+            s.set(Some(remaining - 1));
+            false
+        }
+        None => false,
+    })
+}
+
+#[cfg(not(test))]
+fn simulated_interrupt() -> bool {
+    false
+}
+
+// Long running operations should check this between steps and wind down when it returns true
+pub fn interrupted() -> bool {
+    INTERRUPTS.load(Ordering::SeqCst) > 0 || simulated_interrupt()
+}
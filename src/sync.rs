@@ -0,0 +1,198 @@
+use std::path::Path;
+
+use crate::{
+    commands::{git_command, git_remote_command},
+    logging::debug,
+    output::out,
+    repository::{interrupted_summary, BareRepository},
+    signals::interrupted,
+    worktree::Worktree,
+};
+
+#[cfg(test)]
+mod tests;
+
+// A local branch and the ref it tracks, e.g. `refs/remotes/origin/main`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackedBranch {
+    pub name: String,
+    pub oid: String,
+    pub upstream: String,
+}
+
+// Parses `for-each-ref --format='%(refname:lstrip=2) %(objectname) %(upstream)'`, branches without an
+// upstream are left out
+pub fn parse_tracked_branches(lines: &[String]) -> Vec<TrackedBranch> {
+    lines
+        .iter()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(name), Some(oid), Some(upstream)) if !upstream.is_empty() => {
+                    Some(TrackedBranch {
+                        name: name.to_string(),
+                        oid: oid.to_string(),
+                        upstream: upstream.to_string(),
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum SyncOutcome {
+    UpToDate,
+    FastForwarded(usize),
+    Diverged { ahead: usize, behind: usize },
+    Dirty,
+    UpstreamGone,
+}
+
+// Fetches every remote once, then fast-forwards each branch whose upstream moved ahead of it.
+// Branches checked out in a worktree are only touched when the worktree is clean.
+pub fn sync(repository: &BareRepository) -> Result<(), String> {
+    let root = repository.root();
+
+    eprintln!("Fetching all remotes");
+    git_remote_command(vec!["fetch", "--all", "--prune", "--quiet"], root)
+        .map_err(|result| format!("Couldn't fetch: {}", result.error_message()))?;
+
+    let branches = parse_tracked_branches(
+        &git_command(
+            vec![
+                "for-each-ref",
+                "--format=%(refname:lstrip=2) %(objectname) %(upstream)",
+                "refs/heads/",
+            ],
+            root,
+        )
+        .map_err(|result| result.error_message())?
+        .stdout_lines(),
+    );
+    let worktrees = repository.all_worktrees()?;
+    let mut synced: Vec<String> = vec![];
+
+    for (index, branch) in branches.iter().enumerate() {
+        if interrupted() {
+            let not_processed = branches[index..]
+                .iter()
+                .map(|branch| branch.name.clone())
+                .collect::<Vec<String>>();
+
+            return Err(interrupted_summary(
+                "branches",
+                "Synced",
+                &synced,
+                &not_processed,
+            ));
+        }
+
+        let worktree = worktrees
+            .iter()
+            .find(|worktree| worktree.name == branch.name);
+        let upstream = branch
+            .upstream
+            .strip_prefix("refs/remotes/")
+            .unwrap_or(&branch.upstream);
+
+        match sync_branch(root, branch, worktree) {
+            Ok(SyncOutcome::UpToDate) => debug!("'{}' is up to date", branch.name),
+            Ok(SyncOutcome::FastForwarded(commits)) => {
+                out!(
+                    "Fast-forwarded '{}' by {} commit(s) to {}",
+                    branch.name,
+                    commits,
+                    upstream
+                );
+                synced.push(branch.name.clone());
+            }
+            Ok(SyncOutcome::Diverged { ahead, behind }) => out!(
+                "Skipped '{}': it has diverged from {} ({} ahead, {} behind)",
+                branch.name,
+                upstream,
+                ahead,
+                behind
+            ),
+            Ok(SyncOutcome::Dirty) => out!(
+                "Skipped '{}': its worktree ({}) contains changes",
+                branch.name,
+                worktree.map_or("", |worktree| &worktree.path)
+            ),
+            Ok(SyncOutcome::UpstreamGone) => out!(
+                "Skipped '{}': its upstream {} is gone",
+                branch.name,
+                upstream
+            ),
+            Err(msg) => out!("Couldn't sync '{}', error: {}", branch.name, msg),
+        }
+    }
+
+    Ok(())
+}
+
+fn sync_branch(
+    root: &Path,
+    branch: &TrackedBranch,
+    worktree: Option<&Worktree>,
+) -> Result<SyncOutcome, String> {
+    let upstream_oid = match git_command(
+        vec!["rev-parse", "--verify", "--quiet", &branch.upstream],
+        root,
+    ) {
+        Ok(result) => result.stdout_value().unwrap_or_default(),
+        Err(_) => return Ok(SyncOutcome::UpstreamGone),
+    };
+
+    let range = format!("{}...{}", branch.oid, upstream_oid);
+    let counts = git_command(vec!["rev-list", "--left-right", "--count", &range], root)
+        .map_err(|result| result.error_message())?
+        .stdout_value()
+        .unwrap_or_default();
+    let (ahead, behind) = match counts.split_once('\t') {
+        Some((ahead, behind)) => (
+            ahead.parse::<usize>().map_err(|err| err.to_string())?,
+            behind.parse::<usize>().map_err(|err| err.to_string())?,
+        ),
+        None => return Err(format!("Unexpected rev-list output '{}'", counts)),
+    };
+
+    if behind == 0 {
+        return Ok(SyncOutcome::UpToDate);
+    }
+
+    if ahead > 0 {
+        return Ok(SyncOutcome::Diverged { ahead, behind });
+    }
+
+    match worktree {
+        Some(worktree) => {
+            if !worktree.is_clean() {
+                return Ok(SyncOutcome::Dirty);
+            }
+
+            git_command(
+                vec!["merge", "--ff-only", "--quiet", &upstream_oid],
+                &root.join(&worktree.path),
+            )
+            .map_err(|result| result.error_message())?;
+        }
+        None => {
+            // only moves the branch if nothing else moved it since it was read
+            git_command(
+                vec![
+                    "update-ref",
+                    &format!("refs/heads/{}", branch.name),
+                    &upstream_oid,
+                    &branch.oid,
+                ],
+                root,
+            )
+            .map_err(|result| result.error_message())?;
+        }
+    }
+
+    Ok(SyncOutcome::FastForwarded(behind))
+}
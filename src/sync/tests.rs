@@ -0,0 +1,148 @@
+#[cfg(test)]
+use std::{fs, path::Path};
+
+#[cfg(test)]
+use super::{parse_tracked_branches, sync, TrackedBranch};
+
+#[cfg(test)]
+use crate::{
+    commands::git_command,
+    repository::{BareRepository, Repository},
+    test_helpers::run_test,
+    test_setup::{BARE_REPO_NAME, DEFAULT_BRANCH_NAME},
+};
+
+#[cfg(test)]
+fn bare(repo: &Repository) -> &BareRepository {
+    match repo {
+        Repository::Bare(bare) => bare,
+        _ => panic!("repo is not bare"),
+    }
+}
+
+#[cfg(test)]
+fn git(args: Vec<&str>, cwd: &Path) -> String {
+    git_command(args, cwd)
+        .expect("git command failed")
+        .stdout_value()
+        .unwrap_or_default()
+}
+
+// Gives the fixture remote-tracking branches, lets `branches` track origin's main branch and moves
+// origin's main branch one commit ahead of the local one
+#[cfg(test)]
+fn advance_upstream(repo: &Repository, branches: &[&str]) -> String {
+    let root = repo.root();
+    let source = root.join("../bare_repo_source");
+
+    git(
+        vec![
+            "config",
+            "remote.origin.fetch",
+            "+refs/heads/*:refs/remotes/origin/*",
+        ],
+        root,
+    );
+    for branch in branches {
+        git(
+            vec!["config", &format!("branch.{}.remote", branch), "origin"],
+            root,
+        );
+        git(
+            vec![
+                "config",
+                &format!("branch.{}.merge", branch),
+                "refs/heads/main",
+            ],
+            root,
+        );
+    }
+
+    git(vec!["checkout", "--quiet", DEFAULT_BRANCH_NAME], &source);
+    git(
+        vec![
+            "fetch",
+            "--quiet",
+            root.to_str().unwrap(),
+            DEFAULT_BRANCH_NAME,
+        ],
+        &source,
+    );
+    git(vec!["reset", "--quiet", "--hard", "FETCH_HEAD"], &source);
+    git(
+        vec!["commit", "--quiet", "--allow-empty", "-m", "upstream work"],
+        &source,
+    );
+    git(vec!["rev-parse", "HEAD"], &source)
+}
+
+#[test]
+fn test_tracked_branches_are_parsed() {
+    let lines = vec![
+        "main 1111 refs/remotes/origin/main".to_string(),
+        "local-only 2222 ".to_string(),
+    ];
+
+    assert_eq!(
+        vec![TrackedBranch {
+            name: "main".to_string(),
+            oid: "1111".to_string(),
+            upstream: "refs/remotes/origin/main".to_string(),
+        }],
+        parse_tracked_branches(&lines)
+    );
+}
+
+#[test]
+fn test_sync_fast_forwards_clean_worktrees_and_other_branches() {
+    run_test(
+        "test_sync_fast_forwards_clean_worktrees_and_other_branches",
+        BARE_REPO_NAME,
+        |repo| {
+            git(
+                vec!["branch", "not-checked-out", DEFAULT_BRANCH_NAME],
+                repo.root(),
+            );
+            let upstream = advance_upstream(&repo, &[DEFAULT_BRANCH_NAME, "not-checked-out"]);
+
+            sync(bare(&repo)).expect("Couldn't sync");
+
+            let main_worktree = bare(&repo).main_worktree().expect("No main worktree");
+            assert_eq!(
+                upstream,
+                git(vec!["rev-parse", "HEAD"], Path::new(&main_worktree.path))
+            );
+            assert_eq!(
+                upstream,
+                git(vec!["rev-parse", "not-checked-out"], repo.root())
+            );
+        },
+    );
+}
+
+#[test]
+fn test_sync_skips_dirty_worktrees_and_diverged_branches() {
+    run_test(
+        "test_sync_skips_dirty_worktrees_and_diverged_branches",
+        BARE_REPO_NAME,
+        |repo| {
+            let main_worktree = bare(&repo).main_worktree().expect("No main worktree");
+            fs::write(Path::new(&main_worktree.path).join("new-file"), "")
+                .expect("Couldn't write file");
+            let main_before = git(vec!["rev-parse", DEFAULT_BRANCH_NAME], repo.root());
+            let unmerged_before = git(vec!["rev-parse", "unmerged"], repo.root());
+            advance_upstream(&repo, &[DEFAULT_BRANCH_NAME, "unmerged"]);
+
+            sync(bare(&repo)).expect("Couldn't sync");
+
+            assert_eq!(
+                main_before,
+                git(vec!["rev-parse", DEFAULT_BRANCH_NAME], repo.root())
+            );
+            assert_eq!(
+                unmerged_before,
+                git(vec!["rev-parse", "unmerged"], repo.root())
+            );
+        },
+    );
+}
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests;
+
+// Lays out rows in left aligned columns separated by two spaces, without trailing whitespace
+pub fn render(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = headers
+        .iter()
+        .map(|header| header.chars().count())
+        .collect::<Vec<usize>>();
+
+    for row in rows {
+        for (index, cell) in row.iter().enumerate() {
+            if index < widths.len() {
+                widths[index] = widths[index].max(cell.chars().count());
+            }
+        }
+    }
+
+    let header_row = headers
+        .iter()
+        .map(|header| header.to_string())
+        .collect::<Vec<String>>();
+
+    std::iter::once(&header_row)
+        .chain(rows.iter())
+        .map(|row| {
+            row.iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| {
+                    let padding = width - cell.chars().count().min(*width);
+                    format!("{}{}", cell, " ".repeat(padding))
+                })
+                .collect::<Vec<String>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// The same rows as comma separated values, quoting cells that need it
+pub fn render_csv(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let cell = |value: &str| match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    };
+    let header_row = headers
+        .iter()
+        .map(|header| header.to_string())
+        .collect::<Vec<String>>();
+
+    std::iter::once(&header_row)
+        .chain(rows.iter())
+        .map(|row| {
+            row.iter()
+                .map(|value| cell(value))
+                .collect::<Vec<String>>()
+                .join(",")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -0,0 +1,40 @@
+#[cfg(test)]
+use super::{render, render_csv};
+
+#[test]
+fn test_columns_are_as_wide_as_their_widest_cell() {
+    let rows = vec![
+        vec!["main".to_string(), "/repo/main".to_string(), "".to_string()],
+        vec![
+            "some-work".to_string(),
+            "/w".to_string(),
+            "dirty".to_string(),
+        ],
+    ];
+
+    assert_eq!(
+        "BRANCH     PATH        STATE\nmain       /repo/main\nsome-work  /w          dirty",
+        render(&["BRANCH", "PATH", "STATE"], &rows)
+    );
+}
+
+#[test]
+fn test_non_ascii_cells_are_padded_by_characters() {
+    let rows = vec![vec!["café".to_string(), "x".to_string()]];
+
+    assert_eq!("A     B\ncafé  x", render(&["A", "B"], &rows));
+}
+
+#[test]
+fn test_csv_cells_are_quoted_when_needed() {
+    let rows = vec![vec![
+        "plain".to_string(),
+        "a, b".to_string(),
+        "say \"hi\"".to_string(),
+    ]];
+
+    assert_eq!(
+        "A,B,C\nplain,\"a, b\",\"say \"\"hi\"\"\"",
+        render_csv(&["A", "B", "C"], &rows)
+    );
+}
@@ -0,0 +1,121 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    process::{Command, Stdio},
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Backspace,
+    ClearQuery,
+    Up,
+    Down,
+    Enter,
+    Cancel,
+    Other,
+}
+
+// One read from the terminal can hold several keys, e.g. when text is pasted
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    match bytes {
+        [] => vec![],
+        [0x1b] => vec![Key::Cancel],
+        [0x1b, b'[', b'A', ..] | [0x1b, b'O', b'A', ..] => vec![Key::Up],
+        [0x1b, b'[', b'B', ..] | [0x1b, b'O', b'B', ..] => vec![Key::Down],
+        [0x1b, ..] => vec![Key::Other],
+        _ => String::from_utf8_lossy(bytes)
+            .chars()
+            .map(|c| match c {
+                '\r' | '\n' => Key::Enter,
+                '\u{7f}' | '\u{8}' => Key::Backspace,
+                '\u{15}' => Key::ClearQuery,
+                '\u{3}' | '\u{7}' => Key::Cancel,
+                '\u{10}' | '\u{b}' => Key::Up,
+                '\u{e}' => Key::Down,
+                c if c.is_control() => Key::Other,
+                c => Key::Char(c),
+            })
+            .collect(),
+    }
+}
+
+// The controlling terminal in raw mode on an alternate screen. Talking to /dev/tty directly keeps
+// stdout free for the result. Everything is restored when it is dropped.
+pub struct Terminal {
+    tty: File,
+    saved_mode: String,
+}
+
+fn stty(tty: &File, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(tty.try_clone().map_err(|err| err.to_string())?)
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|err| format!("Couldn't run stty: {}", err))?;
+
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        false => Err(format!("stty {} failed", args.join(" "))),
+    }
+}
+
+impl Terminal {
+    pub fn open() -> Result<Self, String> {
+        let tty = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .map_err(|err| format!("This command needs a terminal: {}", err))?;
+        let saved_mode = stty(&tty, &["-g"])?;
+
+        // -isig delivers Ctrl-C as a key instead of interrupting us
+        stty(
+            &tty,
+            &["-icanon", "-echo", "-isig", "min", "1", "time", "0"],
+        )?;
+
+        let mut terminal = Self { tty, saved_mode };
+        terminal.write("\x1b[?1049h")?;
+
+        Ok(terminal)
+    }
+
+    // (rows, columns)
+    pub fn size(&self) -> (usize, usize) {
+        stty(&self.tty, &["size"])
+            .ok()
+            .and_then(|size| {
+                let (rows, columns) = size.split_once(' ')?;
+                Some((rows.parse().ok()?, columns.parse().ok()?))
+            })
+            // pseudo terminals without a size report 0 0
+            .filter(|(rows, columns)| *rows > 0 && *columns > 0)
+            .unwrap_or((24, 80))
+    }
+
+    pub fn write(&mut self, text: &str) -> Result<(), String> {
+        self.tty
+            .write_all(text.as_bytes())
+            .and_then(|_| self.tty.flush())
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn read_keys(&mut self) -> Result<Vec<Key>, String> {
+        let mut buffer = [0u8; 64];
+        let read = self.tty.read(&mut buffer).map_err(|err| err.to_string())?;
+
+        Ok(parse_keys(&buffer[..read]))
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.write("\x1b[?1049l");
+        let _ = stty(&self.tty, &[&self.saved_mode]);
+    }
+}
@@ -0,0 +1,22 @@
+#[cfg(test)]
+use super::{parse_keys, Key};
+
+#[test]
+fn test_arrow_keys_are_parsed() {
+    assert_eq!(vec![Key::Up], parse_keys(b"\x1b[A"));
+    assert_eq!(vec![Key::Down], parse_keys(b"\x1bOB"));
+}
+
+#[test]
+fn test_escape_on_its_own_cancels() {
+    assert_eq!(vec![Key::Cancel], parse_keys(b"\x1b"));
+    assert_eq!(vec![Key::Cancel], parse_keys(b"\x03"));
+}
+
+#[test]
+fn test_pasted_text_becomes_several_keys() {
+    assert_eq!(
+        vec![Key::Char('f'), Key::Char('é'), Key::Backspace, Key::Enter],
+        parse_keys("fé\x7f\r".as_bytes())
+    );
+}
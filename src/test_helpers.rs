@@ -8,15 +8,15 @@ use crate::{
 };
 
 pub fn assert_branches(repo: &Repository, branches: Vec<String>) {
-    assert_eq!(all_branch_names(repo.root()), branches);
+    assert_eq!(all_branch_names(repo.root()), Ok(branches));
 }
 
 pub fn assert_branch_exists(repo: &Repository, branch: String) {
-    assert!(all_branch_names(repo.root()).contains(&branch));
+    assert!(all_branch_names(repo.root()).unwrap().contains(&branch));
 }
 
 pub fn assert_branch_does_not_exist(repo: &Repository, branch: String) {
-    assert!(!all_branch_names(repo.root()).contains(&branch));
+    assert!(!all_branch_names(repo.root()).unwrap().contains(&branch));
 }
 
 pub fn assert_worktree_does_not_exist(repo: &Repository, worktree_name: String) {
@@ -26,7 +26,7 @@ pub fn assert_worktree_does_not_exist(repo: &Repository, worktree_name: String)
 pub fn assert_current_branch(repo: &Repository, branch: String) {
     let current_branch = get_current_branch_name(repo.root());
 
-    assert_eq!(branch, current_branch);
+    assert_eq!(Some(branch), current_branch);
 }
 
 pub fn assert_worktree_exists(repo: &Repository, worktree_name: String) {
@@ -37,7 +37,7 @@ pub fn run_setup(test_name: &str, bare_repo: bool) {
     match setup(test_name, bare_repo) {
         Ok(_) => (),
         Err(msg) => {
-            assert!(false, "Test setup failed with error: {}", msg)
+            panic!("Test setup failed with error: {}", msg)
         }
     }
 }
@@ -46,7 +46,7 @@ pub fn run_teardown(test_name: &str) {
     match teardown(test_name) {
         Ok(_) => (),
         Err(msg) => {
-            assert!(false, "Test teardown failed with error: {}", msg)
+            panic!("Test teardown failed with error: {}", msg)
         }
     }
 }
@@ -55,14 +55,13 @@ pub fn run_test(test_name: &str, repo_directory: &str, test: fn(Repository)) {
     // setup must be run before we create the Repository struct or else the repo doesn't exist
     run_setup(test_name, repo_directory == BARE_REPO_NAME);
 
-    let cwd = get_cwd();
+    let cwd = get_cwd(None);
     let cwd_str = cwd.to_str().expect("Couldn't convert cwd to str");
     let repo_path = PathBuf::from(format!(
         "{}/{}/{}/{}",
         cwd_str, DUMMY_REPOS_DIRECTORY, test_name, repo_directory
     ));
-    let repository = Repository::at(&repo_path)
-        .unwrap_or_else(|| panic!("{:#?} is not a valid git repository", repo_path));
+    let repository = Repository::at(&repo_path).unwrap_or_else(|msg| panic!("{}", msg));
 
     test(repository);
     run_teardown(test_name);
@@ -1,26 +1,89 @@
+use std::{fs::File, process::Command};
+
 use crate::{commands::git_command, test_setup::DEFAULT_BRANCH_NAME, worktree::Worktree};
 
 use self::test_helpers::run_test;
 use super::*;
 
 #[test]
-fn test_merged_branches_are_not_deleted_if_working_tree_is_not_clean() {
+fn test_merged_branches_are_deleted_if_working_tree_is_not_clean() {
     run_test(
-        "test_merged_branches_are_not_deleted_if_working_tree_is_not_clean",
+        "test_merged_branches_are_deleted_if_working_tree_is_not_clean",
         "dirty_repo",
         |repo| {
             let result = repo.clean_merged();
 
-            assert!(result.is_err());
+            assert!(result.is_ok());
 
             test_helpers::assert_branches(
                 &repo,
+                vec![DEFAULT_BRANCH_NAME.to_string(), "unmerged".to_string()],
+            );
+            assert!(repo.root().join("dirty-file").exists());
+        },
+    );
+}
+
+#[test]
+fn test_merged_current_branch_is_not_deleted_if_working_tree_is_not_clean() {
+    run_test(
+        "test_merged_current_branch_is_not_deleted_if_working_tree_is_not_clean",
+        "dirty_repo",
+        |repo| {
+            git_command(vec!["checkout", "merged"], repo.root())
+                .expect("Failed to checkout merged branch");
+
+            repo.clean_merged()
+                .expect("failed to clean merged branches");
+
+            test_helpers::assert_branch_exists(&repo, "merged".to_string());
+            test_helpers::assert_current_branch(&repo, "merged".to_string());
+        },
+    );
+}
+
+#[test]
+fn test_cleaning_other_branches_does_not_change_the_checked_out_branch() {
+    run_test(
+        "test_cleaning_other_branches_does_not_change_the_checked_out_branch",
+        "clean_repo",
+        |repo| {
+            git_command(vec!["checkout", "unmerged"], repo.root())
+                .expect("Failed to checkout unmerged branch");
+
+            repo.clean_merged()
+                .expect("failed to clean merged branches");
+
+            // `merged` was never checked out, so files it doesn't contain must not be rewritten
+            assert!(repo.root().join("unmerged-file").exists());
+            test_helpers::assert_branch_does_not_exist(&repo, "merged".to_string());
+            test_helpers::assert_current_branch(&repo, "unmerged".to_string());
+        },
+    );
+}
+
+#[test]
+fn test_merged_branches_checked_out_in_another_worktree_are_not_deleted() {
+    run_test(
+        "test_merged_branches_checked_out_in_another_worktree_are_not_deleted",
+        "clean_repo",
+        |repo| {
+            let worktree_path = repo.root().join("../merged-worktree");
+            git_command(
                 vec![
-                    DEFAULT_BRANCH_NAME.to_string(),
-                    "merged".to_string(),
-                    "unmerged".to_string(),
+                    "worktree",
+                    "add",
+                    worktree_path.to_str().expect("Invalid worktree path"),
+                    "merged",
                 ],
-            );
+                repo.root(),
+            )
+            .expect("Couldn't create worktree");
+
+            repo.clean_merged()
+                .expect("failed to clean merged branches");
+
+            test_helpers::assert_branch_exists(&repo, "merged".to_string());
         },
     );
 }
@@ -237,3 +300,303 @@ fn test_worktree_list_is_parsed_correctly() {
         },
     );
 }
+
+#[test]
+fn test_user_git_config_does_not_change_parsed_output() {
+    run_test(
+        "test_user_git_config_does_not_change_parsed_output",
+        "clean_repo",
+        |repo| {
+            for (key, value) in [
+                ("color.ui", "always"),
+                ("column.ui", "always"),
+                ("status.showUntrackedFiles", "no"),
+            ] {
+                Command::new("git")
+                    .args(["config", key, value])
+                    .current_dir(repo.root())
+                    .output()
+                    .expect("Couldn't set git config");
+            }
+
+            test_helpers::assert_branches(
+                &repo,
+                vec![
+                    DEFAULT_BRANCH_NAME.to_string(),
+                    "merged".to_string(),
+                    "unmerged".to_string(),
+                ],
+            );
+
+            git_command(vec!["checkout", "merged"], repo.root())
+                .expect("Failed to checkout merged branch");
+            File::create(repo.root().join("untracked-file")).expect("Couldn't create file");
+
+            repo.clean_merged()
+                .expect("failed to clean merged branches");
+
+            test_helpers::assert_current_branch(&repo, "merged".to_string());
+        },
+    );
+}
+
+#[test]
+fn test_merged_branches_with_single_character_names_are_deleted() {
+    run_test(
+        "test_merged_branches_with_single_character_names_are_deleted",
+        "clean_repo",
+        |repo| {
+            git_command(vec!["branch", "x"], repo.root()).expect("Couldn't create branch");
+
+            test_helpers::assert_branch_exists(&repo, "x".to_string());
+
+            repo.clean_merged()
+                .expect("failed to clean merged branches");
+
+            test_helpers::assert_branch_does_not_exist(&repo, "x".to_string());
+        },
+    );
+}
+
+#[test]
+fn test_interrupting_branch_cleanup_keeps_the_current_branch_checked_out() {
+    run_test(
+        "test_interrupting_branch_cleanup_keeps_the_current_branch_checked_out",
+        "clean_repo",
+        |repo| {
+            git_command(vec!["branch", "x"], repo.root()).expect("Couldn't create branch");
+            git_command(vec!["checkout", "unmerged"], repo.root())
+                .expect("Failed to checkout unmerged branch");
+
+            signals::simulate_interrupt_after(Some(1));
+            let result = repo.clean_merged();
+            signals::simulate_interrupt_after(None);
+
+            let msg = result.expect_err("Cleanup should report the interruption");
+            assert!(msg.contains("Deleted: merged"));
+            assert!(msg.contains("Not processed: x"));
+            test_helpers::assert_branch_does_not_exist(&repo, "merged".to_string());
+            test_helpers::assert_branch_exists(&repo, "x".to_string());
+            test_helpers::assert_current_branch(&repo, "unmerged".to_string());
+        },
+    );
+}
+
+#[test]
+fn test_interrupting_before_the_current_branch_lists_the_deleted_branches() {
+    run_test(
+        "test_interrupting_before_the_current_branch_lists_the_deleted_branches",
+        "clean_repo",
+        |repo| {
+            git_command(vec!["branch", "x"], repo.root()).expect("Couldn't create branch");
+            git_command(vec!["checkout", "merged"], repo.root())
+                .expect("Failed to checkout merged branch");
+
+            signals::simulate_interrupt_after(Some(2));
+            let result = repo.clean_merged();
+            signals::simulate_interrupt_after(None);
+
+            let msg = result.expect_err("Cleanup should report the interruption");
+            assert!(msg.contains("Deleted: x"));
+            assert!(msg.contains("Not processed: merged"));
+            test_helpers::assert_branch_exists(&repo, "merged".to_string());
+            test_helpers::assert_current_branch(&repo, "merged".to_string());
+        },
+    );
+}
+
+#[test]
+fn test_interrupting_worktree_cleanup_leaves_remaining_worktrees() {
+    run_test(
+        "test_interrupting_worktree_cleanup_leaves_remaining_worktrees",
+        test_setup::BARE_REPO_NAME,
+        |repo| {
+            signals::simulate_interrupt_after(Some(0));
+            let result = repo.clean_merged();
+            signals::simulate_interrupt_after(None);
+
+            assert!(result.is_err());
+            test_helpers::assert_worktree_exists(&repo, "merged".to_string());
+        },
+    );
+}
+
+#[cfg(test)]
+fn add_git_config(repo: &Repository, key: &str, value: &str) {
+    git_command(vec!["config", "--add", key, value], repo.root()).expect("Couldn't set git config");
+}
+
+#[test]
+fn test_failing_pre_remove_hook_keeps_the_worktree() {
+    run_test(
+        "test_failing_pre_remove_hook_keeps_the_worktree",
+        test_setup::BARE_REPO_NAME,
+        |repo| {
+            add_git_config(
+                &repo,
+                "git-tools.hooks.preRemove",
+                "test \"$GIT_TOOLS_BRANCH\" != merged",
+            );
+
+            repo.clean_merged()
+                .expect("failed to clean merged worktrees");
+
+            test_helpers::assert_worktree_exists(&repo, "merged".to_string());
+            test_helpers::assert_branch_exists(&repo, "merged".to_string());
+            test_helpers::assert_worktree_does_not_exist(&repo, "wont-match-path".to_string());
+        },
+    );
+}
+
+#[test]
+fn test_remove_hooks_run_around_the_removal() {
+    run_test(
+        "test_remove_hooks_run_around_the_removal",
+        test_setup::BARE_REPO_NAME,
+        |repo| {
+            add_git_config(
+                &repo,
+                "git-tools.hooks.preRemove",
+                "test -d \"$GIT_TOOLS_WORKTREE_PATH\" && echo \"$GIT_TOOLS_BRANCH\" >> \"$GIT_TOOLS_ROOT/pre-remove.log\"",
+            );
+            add_git_config(
+                &repo,
+                "git-tools.hooks.postRemove",
+                "test ! -e \"$GIT_TOOLS_WORKTREE_PATH\" && echo \"$GIT_TOOLS_BRANCH\" >> post-remove.log",
+            );
+
+            repo.clean_merged()
+                .expect("failed to clean merged worktrees");
+
+            for log in ["pre-remove.log", "post-remove.log"] {
+                let contents = std::fs::read_to_string(repo.root().join(log))
+                    .expect("Hook didn't write its log");
+                let mut branches = contents.lines().collect::<Vec<&str>>();
+                branches.sort();
+
+                assert_eq!(vec!["merged", "other-branch", "wont-match-path"], branches);
+            }
+        },
+    );
+}
+
+#[test]
+fn test_switch_creates_a_missing_worktree_in_a_bare_repository() {
+    run_test(
+        "test_switch_creates_a_missing_worktree_in_a_bare_repository",
+        test_setup::BARE_REPO_NAME,
+        |repo| {
+            switch(&repo, repo.root(), "brand-new".to_string()).expect("Couldn't switch");
+            test_helpers::assert_worktree_exists(&repo, "brand-new".to_string());
+
+            // switching again reuses the worktree
+            switch(&repo, repo.root(), "brand-new".to_string()).expect("Couldn't switch");
+        },
+    );
+}
+
+#[test]
+fn test_switch_checks_out_the_branch_in_a_normal_repository() {
+    run_test(
+        "test_switch_checks_out_the_branch_in_a_normal_repository",
+        "clean_repo",
+        |repo| {
+            switch(&repo, repo.root(), "unmerged".to_string()).expect("Couldn't switch");
+
+            test_helpers::assert_current_branch(&repo, "unmerged".to_string());
+        },
+    );
+}
+
+#[test]
+fn test_switch_leaves_branches_checked_out_elsewhere_in_a_normal_repository() {
+    run_test(
+        "test_switch_leaves_branches_checked_out_elsewhere_in_a_normal_repository",
+        "clean_repo",
+        |repo| {
+            let linked = repo.root().parent().unwrap().join("linked");
+            git_command(
+                vec!["worktree", "add", linked.to_str().unwrap(), "unmerged"],
+                repo.root(),
+            )
+            .expect("Couldn't add a worktree");
+
+            switch(&repo, repo.root(), "unmerged".to_string()).expect("Couldn't switch");
+
+            test_helpers::assert_current_branch(&repo, DEFAULT_BRANCH_NAME.to_string());
+        },
+    );
+}
+
+#[test]
+fn test_config_show_works_outside_of_a_repository() {
+    assert!(config_show(&std::env::temp_dir()).is_ok());
+}
+
+#[test]
+fn test_protected_branches_are_not_deleted() {
+    run_test(
+        "test_protected_branches_are_not_deleted",
+        "clean_repo",
+        |repo| {
+            git_command(
+                vec!["config", "git-tools.protectedBranches", "merg*"],
+                repo.root(),
+            )
+            .expect("Couldn't set the protected branches");
+
+            repo.clean_merged()
+                .expect("failed to clean merged branches");
+
+            test_helpers::assert_branch_exists(&repo, "merged".to_string());
+        },
+    );
+}
+
+#[test]
+fn test_worktrees_of_protected_branches_are_not_deleted() {
+    run_test(
+        "test_worktrees_of_protected_branches_are_not_deleted",
+        test_setup::BARE_REPO_NAME,
+        |repo| {
+            git_command(
+                vec!["config", "git-tools.protectedBranches", "merged"],
+                repo.root(),
+            )
+            .expect("Couldn't set the protected branches");
+
+            repo.clean_merged()
+                .expect("failed to clean merged branches");
+
+            test_helpers::assert_worktree_exists(&repo, "merged".to_string());
+        },
+    );
+}
+
+#[test]
+fn test_every_command_accepts_the_options_of_its_spec() {
+    for command in cli::COMMANDS.iter() {
+        let mut raw_args: Vec<String> = command.words.iter().map(|word| word.to_string()).collect();
+        for argument in command
+            .arguments
+            .iter()
+            .filter(|argument| argument.required)
+        {
+            raw_args.push(argument.name.to_string());
+        }
+        for option in command.options {
+            raw_args.push(option.name.to_string());
+            if option.value.is_some() {
+                raw_args.push("1".to_string());
+            }
+        }
+
+        let args =
+            ParsedArguments::parse(&raw_args, &value_options()).expect("Couldn't parse arguments");
+
+        // invalid combinations like `--bin-dir x --git-config` may still fail
+        if let Err(msg) = AvailableCommands::try_from(&args) {
+            assert!(!msg.starts_with("Unknown option"), "{}", msg);
+        }
+    }
+}
@@ -0,0 +1,488 @@
+#[cfg(test)]
+mod tests;
+
+// The part of TOML that our files need: tables, inline tables, arrays of tables, dotted keys,
+// strings, integers, booleans and arrays. Dates, floats and multi-line strings aren't supported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TomlValue {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<TomlValue>),
+    Table(TomlTable),
+}
+
+// Keys keep the order of the file
+pub type TomlTable = Vec<(String, TomlValue)>;
+
+impl TomlValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&TomlTable> {
+        match self {
+            Self::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) => "a string",
+            Self::Integer(_) => "an integer",
+            Self::Boolean(_) => "a boolean",
+            Self::Array(_) => "an array",
+            Self::Table(_) => "a table",
+        }
+    }
+}
+
+pub fn get<'a>(table: &'a TomlTable, key: &str) -> Option<&'a TomlValue> {
+    table
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value)
+}
+
+pub fn parse(text: &str) -> Result<TomlTable, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+    };
+
+    parser.parse_document(None)
+}
+
+// Like `parse`, but an entry that can't be parsed is left out and reported instead of failing the
+// whole document. The entries of a table whose header can't be parsed are left out with it.
+pub fn parse_skipping_errors(text: &str) -> (TomlTable, Vec<String>) {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+    };
+    let mut errors = vec![];
+    let table = parser
+        .parse_document(Some(&mut errors))
+        .expect("errors are collected");
+
+    (table, errors)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+// The table that `path` leads to, created when it is missing. Arrays of tables lead to their last
+// table.
+fn table_at<'a>(table: &'a mut TomlTable, path: &[String]) -> Result<&'a mut TomlTable, String> {
+    let (key, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Ok(table),
+    };
+
+    let index = match table.iter().position(|(name, _)| name == key) {
+        Some(index) => index,
+        None => {
+            table.push((key.clone(), TomlValue::Table(vec![])));
+            table.len() - 1
+        }
+    };
+
+    let next = match &mut table[index].1 {
+        TomlValue::Table(next) => next,
+        TomlValue::Array(values) => match values.last_mut() {
+            Some(TomlValue::Table(next)) => next,
+            _ => return Err(format!("'{}' is not an array of tables", key)),
+        },
+        _ => return Err(format!("'{}' is not a table", key)),
+    };
+
+    table_at(next, rest)
+}
+
+// Sets a possibly dotted key, creating the tables on the way
+fn insert(table: &mut TomlTable, key: &[String], value: TomlValue) -> Result<(), String> {
+    let (last, path) = key.split_last().expect("keys aren't empty");
+    let table = table_at(table, path)?;
+
+    if get(table, last).is_some() {
+        return Err(format!("duplicate key '{}'", key.join(".")));
+    }
+    table.push((last.clone(), value));
+
+    Ok(())
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.chars[..self.position.min(self.chars.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+            + 1
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected '{}', found '{}'", expected, c)),
+            None => Err(format!(
+                "expected '{}', found the end of the file",
+                expected
+            )),
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.position += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), Some('\n') | None) {
+                self.position += 1;
+            }
+        }
+    }
+
+    // Whitespace, newlines and comments
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+
+            match self.peek() {
+                Some('\n' | '\r') => self.position += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while !matches!(self.next(), Some('\n') | None) {}
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_spaces();
+        self.skip_comment();
+
+        match self.next() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.peek() == Some('\n') => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => Err(format!("unexpected '{}' after a value", c)),
+        }
+    }
+
+    // Fails on the first error, unless `errors` collects them
+    fn parse_document(
+        &mut self,
+        mut errors: Option<&mut Vec<String>>,
+    ) -> Result<TomlTable, String> {
+        let mut root: TomlTable = vec![];
+        // None after a header that couldn't be parsed, until the next one
+        let mut current: Option<Vec<String>> = Some(vec![]);
+
+        loop {
+            self.skip_blank();
+
+            let is_header = self.peek() == Some('[');
+            let result = match (self.peek(), &current) {
+                (None, _) => return Ok(root),
+                (Some('['), _) => self
+                    .parse_header(&mut root)
+                    .map(|path| current = Some(path)),
+                (Some(_), Some(path)) => {
+                    let path = path.clone();
+                    self.parse_entry(&mut root, &path)
+                }
+                (Some(_), None) => {
+                    self.skip_line();
+                    Ok(())
+                }
+            };
+
+            if let Err(msg) = result {
+                let msg = format!("line {}: {}", self.line(), msg);
+
+                match errors.as_deref_mut() {
+                    Some(errors) => errors.push(msg),
+                    None => return Err(msg),
+                }
+
+                // a string or array that isn't terminated already ends on the next line
+                if self.chars.get(self.position.saturating_sub(1)) != Some(&'\n') {
+                    self.skip_line();
+                }
+                if is_header {
+                    current = None;
+                }
+            }
+        }
+    }
+
+    // `key = value`, where the key can be dotted
+    fn parse_entry(&mut self, root: &mut TomlTable, current: &[String]) -> Result<(), String> {
+        let key = self.parse_dotted_key()?;
+        self.skip_spaces();
+        self.expect('=')?;
+        self.skip_spaces();
+        let value = self.parse_value()?;
+
+        insert(table_at(root, current)?, &key, value)?;
+        self.end_of_line()
+    }
+
+    // `a`, `a.b` or `a . "b"`
+    fn parse_dotted_key(&mut self) -> Result<Vec<String>, String> {
+        let mut key = vec![self.parse_key()?];
+
+        loop {
+            self.skip_spaces();
+            if self.peek() != Some('.') {
+                return Ok(key);
+            }
+
+            self.position += 1;
+            self.skip_spaces();
+            key.push(self.parse_key()?);
+        }
+    }
+
+    // `[a.b]` or `[[a.b]]`, returns the path of the table that follows
+    fn parse_header(&mut self, root: &mut TomlTable) -> Result<Vec<String>, String> {
+        self.expect('[')?;
+        let is_array = self.peek() == Some('[');
+        if is_array {
+            self.position += 1;
+        }
+
+        self.skip_spaces();
+        let path = self.parse_dotted_key()?;
+        self.expect(']')?;
+        if is_array {
+            self.expect(']')?;
+        }
+
+        match is_array {
+            true => {
+                let (last, parent) = path.split_last().expect("headers have a key");
+                let parent = table_at(root, parent)?;
+
+                match parent.iter_mut().find(|(name, _)| name == last) {
+                    Some((_, TomlValue::Array(values))) => values.push(TomlValue::Table(vec![])),
+                    Some(_) => return Err(format!("'{}' is not an array of tables", last)),
+                    None => parent.push((
+                        last.clone(),
+                        TomlValue::Array(vec![TomlValue::Table(vec![])]),
+                    )),
+                }
+            }
+            false => {
+                table_at(root, &path)?;
+            }
+        }
+        self.end_of_line()?;
+
+        Ok(path)
+    }
+
+    fn parse_key(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some('"') => self.parse_basic_string(),
+            Some('\'') => self.parse_literal_string(),
+            _ => {
+                let start = self.position;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    self.position += 1;
+                }
+
+                match self.position > start {
+                    true => Ok(self.chars[start..self.position].iter().collect()),
+                    false => Err(match self.peek() {
+                        Some(c) => format!("expected a key, found '{}'", c),
+                        None => "expected a key".to_string(),
+                    }),
+                }
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<TomlValue, String> {
+        match self.peek() {
+            Some('"') => self.parse_basic_string().map(TomlValue::String),
+            Some('\'') => self.parse_literal_string().map(TomlValue::String),
+            Some('[') => self.parse_array(),
+            Some('{') => self.parse_inline_table(),
+            Some('t' | 'f') => {
+                let word = self.parse_word();
+                match word.as_str() {
+                    "true" => Ok(TomlValue::Boolean(true)),
+                    "false" => Ok(TomlValue::Boolean(false)),
+                    _ => Err(format!("invalid value '{}'", word)),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '+' || c == '-' => {
+                let word = self.parse_word();
+                word.replace('_', "")
+                    .parse::<i64>()
+                    .map(TomlValue::Integer)
+                    .map_err(|_| format!("invalid or unsupported number '{}'", word))
+            }
+            Some(c) => Err(format!("expected a value, found '{}'", c)),
+            None => Err("expected a value".to_string()),
+        }
+    }
+
+    fn parse_word(&mut self) -> String {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !matches!(c, ',' | ']' | '#'))
+        {
+            self.position += 1;
+        }
+
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn parse_array(&mut self) -> Result<TomlValue, String> {
+        self.expect('[')?;
+        let mut values = vec![];
+
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.position += 1;
+                return Ok(TomlValue::Array(values));
+            }
+
+            values.push(self.parse_value()?);
+            self.skip_blank();
+
+            match self.next() {
+                Some(',') => {}
+                Some(']') => return Ok(TomlValue::Array(values)),
+                Some(c) => return Err(format!("expected ',' or ']', found '{}'", c)),
+                None => return Err("unterminated array".to_string()),
+            }
+        }
+    }
+
+    // `{ a = 1, b.c = "d" }`, on a single line
+    fn parse_inline_table(&mut self) -> Result<TomlValue, String> {
+        self.expect('{')?;
+        let mut table: TomlTable = vec![];
+
+        self.skip_spaces();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(TomlValue::Table(table));
+        }
+
+        loop {
+            self.skip_spaces();
+            let key = self.parse_dotted_key()?;
+            self.skip_spaces();
+            self.expect('=')?;
+            self.skip_spaces();
+            let value = self.parse_value()?;
+            insert(&mut table, &key, value)?;
+            self.skip_spaces();
+
+            match self.next() {
+                Some(',') => {}
+                Some('}') => return Ok(TomlValue::Table(table)),
+                Some('\n') | None => {
+                    self.position -= 1;
+                    return Err("unterminated inline table".to_string());
+                }
+                Some(c) => return Err(format!("expected ',' or '}}', found '{}'", c)),
+            }
+        }
+    }
+
+    fn parse_literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let mut value = String::new();
+
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(value),
+                Some('\n') => {
+                    self.position -= 1;
+                    return Err("unterminated string".to_string());
+                }
+                None => return Err("unterminated string".to_string()),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn parse_basic_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        if self.peek() == Some('"') && self.chars.get(self.position + 1) == Some(&'"') {
+            return Err("multi-line strings aren't supported".to_string());
+        }
+
+        let mut value = String::new();
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => value.push(self.parse_escape()?),
+                Some('\n') => {
+                    self.position -= 1;
+                    return Err("unterminated string".to_string());
+                }
+                None => return Err("unterminated string".to_string()),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<char, String> {
+        match self.next() {
+            Some('"') => Ok('"'),
+            Some('\\') => Ok('\\'),
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('r') => Ok('\r'),
+            Some('b') => Ok('\u{8}'),
+            Some('f') => Ok('\u{c}'),
+            Some(kind @ ('u' | 'U')) => {
+                let length = if kind == 'u' { 4 } else { 8 };
+                let digits: String = (0..length).filter_map(|_| self.next()).collect();
+
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid escape '\\{}{}'", kind, digits))
+            }
+            Some(c) => Err(format!("invalid escape '\\{}'", c)),
+            None => Err("unterminated string".to_string()),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+#[cfg(test)]
+use super::{get, parse, parse_skipping_errors, TomlValue};
+
+#[cfg(test)]
+fn string(value: &str) -> TomlValue {
+    TomlValue::String(value.to_string())
+}
+
+#[test]
+fn test_parses_keys_and_values() {
+    let table = parse(
+        r#"
+# a comment
+name = "api" # trailing comment
+"quoted key" = 'C:\literal'
+jobs = 1_000
+negative = -3
+enabled = true
+escaped = "tab\there \"quoted\" \u00e9"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        table,
+        vec![
+            ("name".to_string(), string("api")),
+            ("quoted key".to_string(), string("C:\\literal")),
+            ("jobs".to_string(), TomlValue::Integer(1000)),
+            ("negative".to_string(), TomlValue::Integer(-3)),
+            ("enabled".to_string(), TomlValue::Boolean(true)),
+            ("escaped".to_string(), string("tab\there \"quoted\" é")),
+        ]
+    );
+}
+
+#[test]
+fn test_parses_arrays_over_several_lines() {
+    let table =
+        parse("branches = [\n  \"main\", # the main one\n  \"develop\",\n]\nempty = []\n").unwrap();
+
+    assert_eq!(
+        get(&table, "branches"),
+        Some(&TomlValue::Array(vec![string("main"), string("develop")]))
+    );
+    assert_eq!(get(&table, "empty"), Some(&TomlValue::Array(vec![])));
+}
+
+#[test]
+fn test_parses_tables_and_arrays_of_tables() {
+    let table = parse(
+        r#"
+[defaults]
+layout = "bare"
+
+[defaults.hooks]
+post = "make"
+
+[[repository]]
+url = "a"
+
+[[repository]]
+url = "b"
+"#,
+    )
+    .unwrap();
+
+    let defaults = get(&table, "defaults").unwrap().as_table().unwrap();
+    assert_eq!(get(defaults, "layout"), Some(&string("bare")));
+    assert_eq!(
+        get(get(defaults, "hooks").unwrap().as_table().unwrap(), "post"),
+        Some(&string("make"))
+    );
+
+    let repositories = match get(&table, "repository") {
+        Some(TomlValue::Array(repositories)) => repositories,
+        other => panic!("expected an array of tables, got {:?}", other),
+    };
+    let urls = repositories
+        .iter()
+        .map(|repository| get(repository.as_table().unwrap(), "url").unwrap().clone())
+        .collect::<Vec<TomlValue>>();
+    assert_eq!(urls, vec![string("a"), string("b")]);
+}
+
+#[test]
+fn test_reports_errors_with_their_line() {
+    assert_eq!(
+        parse("a = 1\na = 2\n"),
+        Err("line 2: duplicate key 'a'".to_string())
+    );
+    assert_eq!(
+        parse("\nname = \"unterminated\n"),
+        Err("line 2: unterminated string".to_string())
+    );
+    assert_eq!(
+        parse("point = { x = 1\ny = 2 }"),
+        Err("line 1: unterminated inline table".to_string())
+    );
+    assert_eq!(
+        parse("a = 1 b = 2"),
+        Err("line 1: unexpected 'b' after a value".to_string())
+    );
+    assert!(parse("a = 1.5").is_err());
+    assert!(parse("a = 1\n[a]\nb = 2").is_err());
+}
+
+#[test]
+fn test_parses_dotted_keys_and_inline_tables() {
+    let table = parse(
+        r#"
+hooks.preRemove = ["make clean"]
+"output" . 'format' = "json"
+point = { x = 1, "y" = 2, z.label = "up" }
+empty = {}
+
+[hooks]
+postRemove = "done"
+"#,
+    )
+    .unwrap();
+
+    let hooks = get(&table, "hooks").unwrap().as_table().unwrap();
+    assert_eq!(
+        get(hooks, "preRemove"),
+        Some(&TomlValue::Array(vec![string("make clean")]))
+    );
+    assert_eq!(get(hooks, "postRemove"), Some(&string("done")));
+    assert_eq!(
+        get(get(&table, "output").unwrap().as_table().unwrap(), "format"),
+        Some(&string("json"))
+    );
+    assert_eq!(
+        get(&table, "point"),
+        Some(&TomlValue::Table(vec![
+            ("x".to_string(), TomlValue::Integer(1)),
+            ("y".to_string(), TomlValue::Integer(2)),
+            (
+                "z".to_string(),
+                TomlValue::Table(vec![("label".to_string(), string("up"))])
+            ),
+        ]))
+    );
+    assert_eq!(get(&table, "empty"), Some(&TomlValue::Table(vec![])));
+    assert_eq!(
+        parse("a.b = 1\na.b = 2"),
+        Err("line 2: duplicate key 'a.b'".to_string())
+    );
+}
+
+#[test]
+fn test_skips_only_the_entries_that_cant_be_parsed() {
+    let (table, errors) = parse_skipping_errors(
+        r#"
+first = 1
+broken = "unterminated
+second = 2
+also broken
+[bad header
+inside = 3
+[good]
+third = 3
+"#,
+    );
+
+    assert_eq!(get(&table, "first"), Some(&TomlValue::Integer(1)));
+    assert_eq!(get(&table, "second"), Some(&TomlValue::Integer(2)));
+    assert_eq!(get(&table, "inside"), None);
+    assert_eq!(
+        get(get(&table, "good").unwrap().as_table().unwrap(), "third"),
+        Some(&TomlValue::Integer(3))
+    );
+    assert_eq!(
+        errors,
+        vec![
+            "line 3: unterminated string".to_string(),
+            "line 5: expected '=', found 'b'".to_string(),
+            "line 6: expected ']', found 'h'".to_string(),
+        ]
+    );
+}
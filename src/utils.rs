@@ -15,60 +15,36 @@ pub fn expand_path(path: String) -> String {
     .to_string()
 }
 
-pub fn get_current_branch_name(repo_path: &Path) -> String {
+// None when HEAD is detached
+pub fn get_current_branch_name(repo_path: &Path) -> Option<String> {
     git_command(vec!["branch", "--show-current"], repo_path)
         .expect("Couldn't get current branch")
-        .output
-        .get(0)
-        .expect("No output found")
-        .to_string()
+        .stdout_value()
+        .filter(|name| !name.is_empty())
 }
 
-pub fn is_bare_repo(cwd: &Path) -> bool {
-    get_bare_root(cwd).is_ok()
+pub fn ref_exists(repo_path: &Path, ref_name: &str) -> bool {
+    git_command(
+        vec!["rev-parse", "--verify", "--quiet", ref_name],
+        repo_path,
+    )
+    .is_ok()
 }
 
-pub fn get_bare_root(cwd: &Path) -> Result<PathBuf, String> {
-    let mut node = Some(cwd.to_path_buf());
-
-    while let Some(n) = node {
-        if n.to_str().expect("Expected to find a path string") == "/" {
-            return Err(format!("{:?} is not a bare repository", cwd.to_path_buf()));
-        }
-
-        if is_bare_root(&n) {
-            return Ok(n);
-        }
-
-        match n.parent() {
-            Some(path) => {
-                node = Some(path.to_path_buf());
-            }
-            None => {
-                return Err(format!("{:?} is not a bare repository", cwd.to_path_buf()));
-            }
-        }
-    }
+// `origin` when it exists, otherwise the first configured remote
+pub fn get_default_remote(repo_path: &Path) -> Option<String> {
+    let remotes = git_command(vec!["remote"], repo_path)
+        .map(|result| result.stdout_lines())
+        .unwrap_or_default();
 
-    match node {
-        Some(n) => Ok(n),
-        None => Err(format!("{:?} is not a bare repository", cwd.to_path_buf())),
+    match remotes.iter().find(|remote| *remote == "origin") {
+        Some(origin) => Some(origin.to_owned()),
+        None => remotes.first().cloned(),
     }
 }
 
-fn is_bare_root(path: &Path) -> bool {
-    match git_command(vec!["rev-parse", "--is-bare-repository"], path) {
-        Ok(result) => result.output.join("") == "true",
-        Err(_) => false,
-    }
-}
-
-pub fn get_normal_root(cwd: &Path) -> Result<PathBuf, String> {
-    match git_command(vec!["rev-parse", "--show-toplevel"], cwd) {
-        Ok(result) => Ok(PathBuf::from(result.output.join(""))),
-        Err(_) => Err(format!(
-            "Error: command must be run within a normal repository (it was run from {:?})",
-            cwd
-        )),
-    }
+pub fn get_git_config_value(repo_path: &Path, key: &str) -> Option<String> {
+    git_command(vec!["config", "--get", key], repo_path)
+        .ok()
+        .and_then(|result| result.stdout_value())
 }
@@ -0,0 +1,442 @@
+use std::{
+    fs,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    bootstrap::{bootstrap_worktree, BootstrapConfig},
+    clone::clone_bare,
+    commands::git_remote_command,
+    config::Config,
+    logging::debug,
+    manifest::{Layout, Manifest, ManifestRepository},
+    output::{capture, out, print_captured},
+    pool::for_each_ordered,
+    repository::{BareRepository, Repository},
+    signals::interrupted,
+    utils::get_git_config_value,
+    worktree::{NewWorktree, Worktree},
+    worktree_path::configured_path_template,
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, PartialEq, Eq)]
+enum DirectoryKind {
+    Repository,
+    // a linked worktree or a submodule, which belong to a repository found elsewhere
+    Checkout,
+    Other,
+}
+
+fn directory_kind(directory: &Path) -> DirectoryKind {
+    let dot_git = directory.join(".git");
+
+    if dot_git.is_dir() {
+        return DirectoryKind::Repository;
+    }
+
+    if dot_git.is_file() {
+        let git_dir = fs::read_to_string(&dot_git).ok().and_then(|contents| {
+            contents
+                .trim()
+                .strip_prefix("gitdir:")
+                .map(|path| directory.join(path.trim()))
+        });
+
+        return match git_dir {
+            // linked worktrees have a `commondir` file, submodules live in `modules/`
+            Some(git_dir)
+                if git_dir.join("commondir").exists()
+                    || git_dir
+                        .components()
+                        .any(|component| component.as_os_str() == "modules") =>
+            {
+                DirectoryKind::Checkout
+            }
+            // e.g. the `.bare` layout
+            Some(_) => DirectoryKind::Repository,
+            None => DirectoryKind::Other,
+        };
+    }
+
+    // a bare repository whose root is the git directory itself
+    if directory.join("HEAD").is_file()
+        && directory.join("objects").is_dir()
+        && directory.join("refs").is_dir()
+    {
+        return DirectoryKind::Repository;
+    }
+
+    DirectoryKind::Other
+}
+
+// Finds the repositories under `directory` (including itself), sorted by path. Repositories aren't
+// searched any further, so their worktrees and submodules aren't listed separately, and symlinks
+// aren't followed.
+pub fn discover_repositories(directory: &Path) -> Vec<PathBuf> {
+    let mut repositories: Vec<PathBuf> = vec![];
+    // so that `--all-under .` doesn't list the repositories as `/some/path/./repo`
+    let directory = directory
+        .canonicalize()
+        .unwrap_or_else(|_| directory.to_path_buf());
+
+    discover_in(&directory, &mut repositories);
+    repositories.sort();
+
+    repositories
+}
+
+fn discover_in(directory: &Path, repositories: &mut Vec<PathBuf>) {
+    match directory_kind(directory) {
+        DirectoryKind::Repository => {
+            repositories.push(directory.to_path_buf());
+            return;
+        }
+        DirectoryKind::Checkout => {
+            debug!(
+                "skipping {:?}: it is a linked worktree or submodule",
+                directory
+            );
+            return;
+        }
+        DirectoryKind::Other => {}
+    }
+
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(err) => {
+            debug!("skipping {:?}: {}", directory, err);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            discover_in(&entry.path(), repositories);
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|msg| msg.to_string()))
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+// Runs `run` for every item, `jobs` at a time, and keeps going when it fails (or panics) for one of
+// them. With `headers` every item's output is preceded by its path and a summary is printed at the
+// end. Running one at a time, output appears as it is printed; otherwise each item's output is
+// held back until it is done and everything is printed in the order of `items`.
+fn run_for_each<T, P, F>(
+    items: &[T],
+    path_of: P,
+    jobs: usize,
+    headers: bool,
+    run: F,
+) -> Result<(), String>
+where
+    T: Sync,
+    P: Fn(&T) -> PathBuf + Sync,
+    F: Fn(&T) -> Result<(), String> + Sync,
+{
+    let live = jobs <= 1;
+    let run_one = |item: &T| {
+        catch_unwind(AssertUnwindSafe(|| run(item)))
+            .unwrap_or_else(|payload| Err(panic_message(payload)))
+    };
+    let mut failures: Vec<(PathBuf, String)> = vec![];
+    let mut not_processed: Vec<PathBuf> = vec![];
+
+    for_each_ordered(
+        items.iter().collect(),
+        jobs,
+        |item: &T| {
+            let path = path_of(item);
+
+            // items that haven't been started when interrupted are left alone
+            if interrupted() {
+                return (path, None);
+            }
+
+            if live {
+                if headers {
+                    out!("==> {}", path.to_string_lossy());
+                }
+                return (path, Some((run_one(item), String::new())));
+            }
+
+            (path, Some(capture(|| run_one(item))))
+        },
+        |(path, outcome)| {
+            let (result, output) = match outcome {
+                Some(outcome) => outcome,
+                None => {
+                    not_processed.push(path);
+                    return;
+                }
+            };
+
+            if headers && !live {
+                out!("==> {}", path.to_string_lossy());
+            }
+            print_captured(&output);
+
+            if let Err(msg) = result {
+                if headers {
+                    out!("Error: {}", msg);
+                }
+                failures.push((path, msg));
+            }
+
+            if headers {
+                out!();
+            }
+        },
+    );
+
+    let processed = items.len() - not_processed.len();
+    let summary = format!(
+        "{} of {} repositories succeeded",
+        processed - failures.len(),
+        items.len()
+    );
+
+    if headers {
+        out!("{}", summary);
+    }
+
+    if failures.is_empty() && not_processed.is_empty() {
+        return Ok(());
+    }
+
+    let mut lines = failures
+        .iter()
+        .map(|(path, msg)| format!("{}: {}", path.to_string_lossy(), msg))
+        .collect::<Vec<String>>();
+
+    if !not_processed.is_empty() {
+        lines.push(format!(
+            "Interrupted, not processed: {}",
+            not_processed
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+
+    Err(format!("{}\n  {}", summary, lines.join("\n  ")))
+}
+
+// `run_for_each` for the repositories at `repositories`
+pub fn run_in_each<F>(
+    repositories: &[PathBuf],
+    jobs: usize,
+    headers: bool,
+    run: F,
+) -> Result<(), String>
+where
+    F: Fn(&Repository) -> Result<(), String> + Sync,
+{
+    run_for_each(
+        repositories,
+        |path| path.clone(),
+        jobs,
+        headers,
+        |path| Repository::at(path).and_then(|repository| run(&repository)),
+    )
+}
+
+// Relative local URLs in the manifest are relative to its directory
+fn resolved_url(root: &Path, url: &str) -> String {
+    if !url.starts_with("./") && !url.starts_with("../") {
+        return url.to_string();
+    }
+
+    let mut path = root.to_path_buf();
+    for component in Path::new(url).components() {
+        match component {
+            Component::ParentDir => {
+                path.pop();
+            }
+            Component::CurDir => {}
+            component => path.push(component),
+        }
+    }
+
+    path.to_string_lossy().to_string()
+}
+
+fn is_empty_directory(path: &Path) -> bool {
+    fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+}
+
+fn clone_repository(
+    target: &Path,
+    url: &str,
+    repository: &ManifestRepository,
+) -> Result<(), String> {
+    if repository.layout == Layout::Normal {
+        let target_str = target
+            .to_str()
+            .ok_or_else(|| format!("{:?} is not a valid path", target))?;
+
+        eprintln!("Cloning {} into {:?}", url, target);
+        git_remote_command(
+            vec!["clone", "--quiet", url, target_str],
+            target.parent().unwrap_or(target),
+        )
+        .map_err(|result| result.error_message())?;
+        out!("Cloned {}", url);
+
+        return Ok(());
+    }
+
+    clone_bare(target.parent().unwrap_or(target), url, target, None)?;
+    out!("Cloned {}", url);
+
+    let bare = BareRepository::at(target)
+        .ok_or_else(|| format!("{:?} isn't a bare repository after cloning", target))?;
+    let existing = bare.all_worktrees()?;
+    let path_template = configured_path_template(target);
+    let bootstrap_config = BootstrapConfig::from(&Config::load(Some(target)));
+    let mut failures: Vec<String> = vec![];
+
+    for branch in &repository.worktrees {
+        if existing.iter().any(|worktree| &worktree.name == branch) {
+            continue;
+        }
+
+        let new_worktree = NewWorktree {
+            branch: branch.clone(),
+            start_point: None,
+            path_template: path_template.clone(),
+        };
+
+        match Worktree::create(&bare, &new_worktree) {
+            Ok(worktree) => {
+                out!("Added worktree: {}", worktree.path);
+                failures.extend(bootstrap_worktree(&bare, &worktree, &bootstrap_config));
+            }
+            Err(msg) => failures.push(format!("couldn't add a worktree for '{}': {}", branch, msg)),
+        }
+    }
+
+    match failures.is_empty() {
+        true => Ok(()),
+        false => Err(failures.join("\n  ")),
+    }
+}
+
+// Where an existing repository doesn't match its manifest entry
+fn drift(
+    repository: &Repository,
+    url: &str,
+    wanted: &ManifestRepository,
+) -> Result<Vec<String>, String> {
+    let mut drift: Vec<String> = vec![];
+
+    match (repository, wanted.layout) {
+        (Repository::Bare(_), Layout::Normal) => {
+            drift.push("it is a bare repository, the manifest says normal".to_string())
+        }
+        (Repository::Normal(_), Layout::Bare) => {
+            drift.push("it is a normal repository, the manifest says bare".to_string())
+        }
+        _ => {}
+    }
+
+    match get_git_config_value(repository.root(), "remote.origin.url") {
+        Some(origin) if origin == url => {}
+        Some(origin) => drift.push(format!("origin is {}, the manifest says {}", origin, url)),
+        None => drift.push("it has no origin remote".to_string()),
+    }
+
+    if let Repository::Bare(bare) = repository {
+        let worktrees = bare.all_worktrees()?;
+
+        for branch in &wanted.worktrees {
+            if !worktrees.iter().any(|worktree| &worktree.name == branch) {
+                drift.push(format!("'{}' has no worktree", branch));
+            }
+        }
+    }
+
+    Ok(drift)
+}
+
+fn sync_repository(root: &Path, wanted: &ManifestRepository) -> Result<(), String> {
+    let target = root.join(&wanted.path);
+    let url = resolved_url(root, &wanted.url);
+
+    if !target.exists() || is_empty_directory(&target) {
+        return clone_repository(&target, &url, wanted);
+    }
+
+    // `Repository::at` would find the repository that a plain directory is in
+    if directory_kind(&target) != DirectoryKind::Repository {
+        return Err("it exists, but isn't a git repository".to_string());
+    }
+
+    let repository = Repository::at(&target)?;
+
+    git_remote_command(
+        vec!["fetch", "--all", "--prune", "--quiet"],
+        repository.root(),
+    )
+    .map_err(|result| format!("Couldn't fetch: {}", result.error_message()))?;
+
+    let drift = drift(&repository, &url, wanted)?;
+
+    match drift.is_empty() {
+        true => out!("Fetched, matches the manifest"),
+        false => {
+            out!("Fetched, differs from the manifest:");
+            for line in drift {
+                out!("  {}", line);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Clones the manifest's repositories that are missing, fetches the others and reports where they
+// don't match the manifest, as well as repositories under the manifest's directory that it
+// doesn't list
+pub fn sync_workspace(manifest: &Manifest, jobs: usize) -> Result<(), String> {
+    let result = run_for_each(
+        &manifest.repositories,
+        |repository| manifest.root.join(&repository.path),
+        jobs,
+        true,
+        |repository| sync_repository(&manifest.root, repository),
+    );
+
+    // the manifest could be in a repository of its own, which is all discovery would find
+    if directory_kind(&manifest.root) != DirectoryKind::Repository {
+        let unlisted = discover_repositories(&manifest.root)
+            .into_iter()
+            .filter(|path| {
+                !manifest
+                    .repositories
+                    .iter()
+                    .any(|repository| manifest.root.join(&repository.path) == *path)
+            })
+            .collect::<Vec<PathBuf>>();
+
+        if !unlisted.is_empty() {
+            out!("Not listed in the manifest:");
+            for path in unlisted {
+                out!("  {}", path.to_string_lossy());
+            }
+        }
+    }
+
+    result
+}
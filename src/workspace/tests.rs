@@ -0,0 +1,197 @@
+#[cfg(test)]
+use std::{fs, path::PathBuf};
+
+#[cfg(test)]
+use super::{discover_repositories, run_in_each, sync_workspace};
+
+#[cfg(test)]
+use crate::output::{capture, out};
+
+#[cfg(test)]
+use crate::{
+    commands::git_command,
+    manifest::{Manifest, MANIFEST_FILE_NAME},
+    repository::Repository,
+    test_helpers::run_test,
+    test_setup::{BARE_REPO_NAME, DEFAULT_BRANCH_NAME},
+};
+
+#[test]
+fn test_discovers_bare_and_normal_repositories_but_not_their_worktrees() {
+    run_test(
+        "test_discovers_bare_and_normal_repositories_but_not_their_worktrees",
+        BARE_REPO_NAME,
+        |repo| {
+            let parent = repo.root().parent().unwrap();
+
+            assert_eq!(
+                discover_repositories(parent),
+                vec![parent.join(BARE_REPO_NAME), parent.join("bare_repo_source")]
+            );
+            assert_eq!(
+                discover_repositories(&parent.join(".")),
+                discover_repositories(parent)
+            );
+        },
+    );
+}
+
+#[test]
+fn test_skips_linked_worktrees_outside_of_their_repository() {
+    run_test(
+        "test_skips_linked_worktrees_outside_of_their_repository",
+        "clean_repo",
+        |repo| {
+            let parent = repo.root().parent().unwrap();
+
+            git_command(
+                vec!["worktree", "add", "-b", "outside", "../outside"],
+                repo.root(),
+            )
+            .expect("git worktree add failed");
+
+            assert!(parent.join("outside/.git").is_file());
+            assert_eq!(
+                discover_repositories(parent),
+                vec![parent.join("clean_repo"), parent.join("dirty_repo")]
+            );
+        },
+    );
+}
+
+#[test]
+fn test_keeps_going_when_a_repository_fails() {
+    run_test(
+        "test_keeps_going_when_a_repository_fails",
+        BARE_REPO_NAME,
+        |repo| {
+            let parent = repo.root().parent().unwrap();
+            let repositories: Vec<PathBuf> = vec![
+                parent.join("bare_repo_source"),
+                parent.join("missing"),
+                parent.join(BARE_REPO_NAME),
+            ];
+
+            let result = run_in_each(&repositories, 1, false, |repo| match repo {
+                Repository::Bare(_) => panic!("boom"),
+                Repository::Normal(_) if repo.main_branch_name() == DEFAULT_BRANCH_NAME => Ok(()),
+                Repository::Normal(_) => Err("unexpected main branch".to_string()),
+            });
+            let msg = result.expect_err("failures should be reported");
+
+            assert!(msg.starts_with("1 of 3 repositories succeeded"));
+            assert!(msg.contains("missing: ") && msg.contains("doesn't exist"));
+            assert!(msg.contains(&format!("{}: boom", BARE_REPO_NAME)));
+        },
+    );
+}
+
+#[test]
+fn test_reports_a_repository_without_a_main_branch_as_a_failure() {
+    run_test(
+        "test_reports_a_repository_without_a_main_branch_as_a_failure",
+        "clean_repo",
+        |repo| {
+            git_command(
+                vec!["branch", "-m", DEFAULT_BRANCH_NAME, "trunk"],
+                repo.root(),
+            )
+            .expect("Failed to rename the main branch");
+
+            let result = run_in_each(&[repo.root().to_path_buf()], 1, false, |_| Ok(()));
+            let msg = result.expect_err("the repository should fail");
+
+            assert!(msg.starts_with("0 of 1 repositories succeeded"));
+            assert!(msg.contains("No main branch found"));
+        },
+    );
+}
+
+#[test]
+fn test_prints_the_output_of_parallel_runs_in_order() {
+    run_test(
+        "test_prints_the_output_of_parallel_runs_in_order",
+        "clean_repo",
+        |repo| {
+            let parent = repo.root().parent().unwrap();
+            let repositories = vec![parent.join("clean_repo"), parent.join("dirty_repo")];
+
+            let (result, output) = capture(|| {
+                run_in_each(&repositories, 2, true, |repo| {
+                    // the first repository finishes last
+                    if repo.root().ends_with("clean_repo") {
+                        std::thread::sleep(std::time::Duration::from_millis(200));
+                    }
+                    out!("in {}", repo.root().file_name().unwrap().to_string_lossy());
+                    Ok(())
+                })
+            });
+
+            assert!(result.is_ok());
+            assert_eq!(
+                output,
+                format!(
+                    "==> {}\nin clean_repo\n\n==> {}\nin dirty_repo\n\n2 of 2 repositories succeeded\n",
+                    repositories[0].to_string_lossy(),
+                    repositories[1].to_string_lossy()
+                )
+            );
+        },
+    );
+}
+
+#[test]
+fn test_workspace_sync_clones_missing_repositories_and_reports_drift() {
+    run_test(
+        "test_workspace_sync_clones_missing_repositories_and_reports_drift",
+        BARE_REPO_NAME,
+        |repo| {
+            let root = repo.root().parent().unwrap().join("workspace");
+            let manifest_path = root.join(MANIFEST_FILE_NAME);
+            let manifest = |normal_layout: &str| {
+                fs::write(
+                    &manifest_path,
+                    format!(
+                        "[[repository]]\nurl = \"../bare_repo_source\"\npath = \"bare\"\n\
+                         worktrees = [\"other-branch\"]\n\n\
+                         [[repository]]\nurl = \"../bare_repo_source\"\npath = \"normal\"\n\
+                         layout = \"{}\"\n",
+                        normal_layout
+                    ),
+                )
+                .unwrap();
+                Manifest::load(&manifest_path).unwrap()
+            };
+
+            fs::create_dir_all(root.join("unlisted")).unwrap();
+            git_command(vec!["init", "--quiet"], &root.join("unlisted")).unwrap();
+
+            let (result, output) = capture(|| sync_workspace(&manifest("normal"), 1));
+
+            assert_eq!(result, Ok(()));
+            assert!(root.join("bare/.bare").is_dir());
+            assert!(root.join("bare/other-branch").is_dir());
+            assert!(root.join("normal/.git").is_dir());
+            assert!(
+                output.contains(&format!(
+                    "Added worktree: {}",
+                    root.join("bare/other-branch").to_string_lossy()
+                )),
+                "{}",
+                output
+            );
+            assert!(output.contains(&format!(
+                "Not listed in the manifest:\n  {}",
+                root.join("unlisted").to_string_lossy()
+            )));
+
+            let (result, output) = capture(|| sync_workspace(&manifest("bare"), 2));
+
+            assert_eq!(result, Ok(()));
+            assert!(output.contains("Fetched, matches the manifest"));
+            assert!(output.contains(
+                "Fetched, differs from the manifest:\n  it is a normal repository, the manifest says bare"
+            ));
+        },
+    );
+}
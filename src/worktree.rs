@@ -1,5 +1,13 @@
+use std::{fs, path::Path};
+
 use crate::{
-    commands::git_command, repository::BareRepository, worktree_list_item::WorktreeListItem,
+    commands::{git_command, git_remote_command},
+    hooks::{run_hook, HookContext, RemoveHooks},
+    logging::{debug, warning},
+    repository::BareRepository,
+    signals::interrupted,
+    utils::{get_default_remote, get_git_config_value, ref_exists},
+    worktree_path::{expand_path_template, renamed_path},
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -9,40 +17,357 @@ pub struct Worktree<'a> {
     pub repository: &'a BareRepository,
 }
 
-impl<'a> TryFrom<WorktreeListItem<'a>> for Worktree<'a> {
-    type Error = &'static str;
+// What `git-tools worktree add` was asked to create
+#[derive(Clone, Debug)]
+pub struct NewWorktree {
+    pub branch: String,
+    pub start_point: Option<String>,
+    pub path_template: String,
+}
+
+// Where a new branch starts and, when the branch already exists on a remote, which remote it
+// should track
+struct StartPoint {
+    revision: String,
+    upstream_remote: Option<String>,
+}
+
+impl<'a> Worktree<'a> {
+    // An existing branch is checked out as-is, a new branch starts from `start_point`, the
+    // branch of the same name on the default remote, or an up-to-date main branch (in that order)
+    pub fn create(
+        repository: &'a BareRepository,
+        new_worktree: &NewWorktree,
+    ) -> Result<Self, String> {
+        let root = repository.root();
+        let branch = &new_worktree.branch;
+
+        git_command(vec!["check-ref-format", "--branch", branch], root)
+            .map_err(|_| format!("'{}' is not a valid branch name", branch))?;
+
+        let path = expand_path_template(&new_worktree.path_template, root, branch);
+        let path_str = path
+            .to_str()
+            .ok_or_else(|| format!("{:?} is not a valid path", path))?
+            .to_string();
 
-    fn try_from(list_item: WorktreeListItem<'a>) -> Result<Self, Self::Error> {
-        if list_item.is_bare() {
-            return Err("Can't create a Worktree from a bare WorktreeListItem");
+        if path.exists() {
+            return Err(format!("{} already exists", path_str));
         }
 
-        if list_item.is_detached() {
-            return Err("Can't create a Worktree from a detached WorktreeListItem");
+        if ref_exists(root, &format!("refs/heads/{}", branch)) {
+            debug!(
+                "checking out the existing branch '{}' in {}",
+                branch, path_str
+            );
+            git_command(vec!["worktree", "add", &path_str, branch], root)
+                .map_err(|result| result.error_message())?;
+        } else {
+            let start_point = Self::find_start_point(repository, new_worktree)?;
+
+            debug!(
+                "creating branch '{}' from '{}' in {}",
+                branch, start_point.revision, path_str
+            );
+            git_command(
+                vec![
+                    "worktree",
+                    "add",
+                    "--no-track",
+                    "-b",
+                    branch,
+                    &path_str,
+                    &start_point.revision,
+                ],
+                root,
+            )
+            .map_err(|result| result.error_message())?;
+
+            // Written directly because `--track` only works with remote-tracking branches, which
+            // bare clones don't have
+            if let Some(remote) = start_point.upstream_remote {
+                let merge_ref = format!("refs/heads/{}", branch);
+
+                git_command(
+                    vec!["config", &format!("branch.{}.remote", branch), &remote],
+                    root,
+                )
+                .and_then(|_| {
+                    git_command(
+                        vec!["config", &format!("branch.{}.merge", branch), &merge_ref],
+                        root,
+                    )
+                })
+                .map_err(|result| result.error_message())?;
+            }
         }
 
         Ok(Self {
-            name: list_item.name().expect("Couldn't get list item name"),
-            path: list_item.path().expect("Couldn't get list item path"),
-            repository: list_item.repository,
+            name: branch.to_owned(),
+            path: path_str,
+            repository,
         })
     }
-}
 
-impl<'a> Worktree<'a> {
-    pub fn delete(&self) -> Result<(), String> {
-        match git_command(
-            vec!["worktree", "remove", &self.path],
-            self.repository.root(),
-        ) {
+    fn find_start_point(
+        repository: &BareRepository,
+        new_worktree: &NewWorktree,
+    ) -> Result<StartPoint, String> {
+        let root = repository.root();
+        let remote = get_default_remote(root);
+
+        if let Some(remote) = &remote {
+            if let Err(result) = git_remote_command(vec!["fetch", "--quiet", remote], root) {
+                if interrupted() {
+                    return Err(format!("Interrupted while fetching '{}'", remote));
+                }
+
+                warning!(
+                    "Couldn't fetch '{}', using local branches instead: {}",
+                    remote,
+                    result.error_message()
+                );
+            }
+        }
+
+        if let Some(start_point) = &new_worktree.start_point {
+            if !ref_exists(root, start_point) {
+                return Err(format!("'{}' does not exist", start_point));
+            }
+
+            let upstream_remote = start_point
+                .split_once('/')
+                .filter(|_| ref_exists(root, &format!("refs/remotes/{}", start_point)))
+                .filter(|(_, remote_branch)| *remote_branch == new_worktree.branch)
+                .map(|(remote, _)| remote.to_string());
+
+            return Ok(StartPoint {
+                revision: start_point.to_owned(),
+                upstream_remote,
+            });
+        }
+
+        let local_main = StartPoint {
+            revision: repository.main_branch_name().to_owned(),
+            upstream_remote: None,
+        };
+        let remote = match remote {
+            Some(remote) => remote,
+            None => return Ok(local_main),
+        };
+
+        let remote_branch = format!("{}/{}", remote, new_worktree.branch);
+        if ref_exists(root, &format!("refs/remotes/{}", remote_branch)) {
+            return Ok(StartPoint {
+                revision: remote_branch,
+                upstream_remote: Some(remote),
+            });
+        }
+
+        let remote_main = format!("{}/{}", remote, repository.main_branch_name());
+        if ref_exists(root, &format!("refs/remotes/{}", remote_main)) {
+            return Ok(StartPoint {
+                revision: remote_main,
+                upstream_remote: None,
+            });
+        }
+
+        // Bare clones don't have remote-tracking branches unless their fetch refspec was fixed, so
+        // fall back to fetching the branches directly
+        let fetch_head = |branch: &str| {
+            git_remote_command(
+                vec![
+                    "fetch",
+                    "--quiet",
+                    &remote,
+                    &format!("refs/heads/{}", branch),
+                ],
+                root,
+            )
+            .is_ok()
+        };
+
+        if fetch_head(&new_worktree.branch) {
+            return Ok(StartPoint {
+                revision: "FETCH_HEAD".to_string(),
+                upstream_remote: Some(remote),
+            });
+        }
+
+        if fetch_head(repository.main_branch_name()) {
+            return Ok(StartPoint {
+                revision: "FETCH_HEAD".to_string(),
+                upstream_remote: None,
+            });
+        }
+
+        Ok(local_main)
+    }
+
+    // Renames the branch (git moves its config section along), moves the worktree when its
+    // directory mirrors the old branch name and points a same-named upstream at the new name
+    pub fn rename(&self, new_branch: &str, path_template: &str) -> Result<Worktree<'a>, String> {
+        let root = self.repository.root();
+
+        git_command(vec!["check-ref-format", "--branch", new_branch], root)
+            .map_err(|_| format!("'{}' is not a valid branch name", new_branch))?;
+
+        if ref_exists(root, &format!("refs/heads/{}", new_branch)) {
+            return Err(format!("A branch named '{}' already exists", new_branch));
+        }
+
+        let new_path = renamed_path(
+            Path::new(&self.path),
+            path_template,
+            root,
+            &self.name,
+            new_branch,
+        )
+        .filter(|new_path| new_path != Path::new(&self.path));
+        let new_path = match new_path {
+            Some(new_path) => {
+                let path_str = new_path
+                    .to_str()
+                    .ok_or_else(|| format!("{:?} is not a valid path", new_path))?
+                    .to_string();
+
+                if new_path.symlink_metadata().is_ok() {
+                    return Err(format!("{} already exists", path_str));
+                }
+
+                Some(path_str)
+            }
+            None => {
+                debug!(
+                    "not moving {}: its directory doesn't mirror the branch name",
+                    self.path
+                );
+                None
+            }
+        };
+
+        git_command(vec!["branch", "-m", &self.name, new_branch], root)
+            .map_err(|result| result.error_message())?;
+        eprintln!("Renamed branch '{}' to '{}'", self.name, new_branch);
+
+        let merge_key = format!("branch.{}.merge", new_branch);
+        let upstream_renamed =
+            get_git_config_value(root, &merge_key) == Some(format!("refs/heads/{}", self.name));
+        if upstream_renamed {
+            let merge_ref = format!("refs/heads/{}", new_branch);
+
+            git_command(vec!["config", &merge_key, &merge_ref], root)
+                .map_err(|result| result.error_message())?;
+            eprintln!(
+                "Set the upstream to '{}', the remote branch '{}' still exists under its old name",
+                merge_ref, self.name
+            );
+        }
+
+        let path = match new_path {
+            Some(new_path) => {
+                if let Err(msg) = self.move_to(&new_path) {
+                    return Err(match self.undo_branch_rename(new_branch, upstream_renamed) {
+                        Ok(()) => format!("Moving the worktree failed: {}", msg),
+                        Err(undo_msg) => format!(
+                            "Moving the worktree failed: {}\nRenaming the branch back to '{}' failed as well: {}",
+                            msg, self.name, undo_msg
+                        ),
+                    });
+                }
+                eprintln!("Moved worktree: {} -> {}", self.path, new_path);
+
+                remove_empty_parents(Path::new(&self.path), root);
+                new_path
+            }
+            None => self.path.clone(),
+        };
+
+        Ok(Self {
+            name: new_branch.to_string(),
+            path,
+            repository: self.repository,
+        })
+    }
+
+    fn move_to(&self, new_path: &str) -> Result<(), String> {
+        let root = self.repository.root();
+
+        if let Some(parent) = Path::new(new_path).parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+
+        git_command(vec!["worktree", "move", &self.path, new_path], root).map_err(|result| {
+            remove_empty_parents(Path::new(new_path), root);
+            result.error_message()
+        })?;
+
+        Ok(())
+    }
+
+    // Puts the branch and its upstream back the way they were before `rename` changed them.
+    // `branch -m` carries the branch's config along, including the updated upstream.
+    fn undo_branch_rename(&self, new_branch: &str, upstream_renamed: bool) -> Result<(), String> {
+        let root = self.repository.root();
+
+        git_command(vec!["branch", "-m", new_branch, &self.name], root)
+            .map_err(|result| result.error_message())?;
+
+        if upstream_renamed {
+            git_command(
+                vec![
+                    "config",
+                    &format!("branch.{}.merge", self.name),
+                    &format!("refs/heads/{}", self.name),
+                ],
+                root,
+            )
+            .map_err(|result| result.error_message())?;
+        }
+
+        eprintln!("Renamed branch '{}' back to '{}'", new_branch, self.name);
+
+        Ok(())
+    }
+
+    // Returns what went wrong once the worktree was removed, i.e. deleting its branch and any
+    // post-remove hooks. The hooks run even when the branch couldn't be deleted.
+    pub fn delete(&self, hooks: &RemoveHooks) -> Result<Vec<String>, String> {
+        let root = self.repository.root();
+        let context = HookContext {
+            worktree_path: &self.path,
+            branch: &self.name,
+            root,
+        };
+
+        for command in &hooks.pre_remove {
+            run_hook(command, &root.join(&self.path), &context)
+                .map_err(|msg| format!("pre-remove hook {}", msg))?;
+        }
+
+        match git_command(vec!["worktree", "remove", &self.path], root) {
             Ok(_) => Ok(()),
-            Err(result) => Err(result.output.join(",")),
+            Err(result) => Err(result.error_message()),
         }?;
 
-        match git_command(vec!["branch", "-d", &self.name], self.repository.root()) {
-            Ok(_) => Ok(()),
-            Err(result) => Err(result.output.join(",")),
-        }
+        let branch_failure = git_command(vec!["branch", "-d", &self.name], root)
+            .err()
+            .map(|result| {
+                format!(
+                    "branch '{}' couldn't be deleted: {}",
+                    self.name,
+                    result.error_message()
+                )
+            });
+
+        Ok(branch_failure
+            .into_iter()
+            .chain(hooks.post_remove.iter().filter_map(|command| {
+                run_hook(command, root, &context)
+                    .err()
+                    .map(|msg| format!("post-remove hook {}", msg))
+            }))
+            .collect())
     }
 
     pub fn is_clean(&self) -> bool {
@@ -52,10 +377,19 @@ impl<'a> Worktree<'a> {
         );
 
         match result {
-            Ok(res) => res.output.is_empty(),
+            Ok(res) => res.is_stdout_empty(),
             Err(_) => false,
         }
     }
 }
 
+// Nested paths like `{root}/{branch}` leave empty directories behind when a worktree moves away
+fn remove_empty_parents(path: &Path, root: &Path) {
+    for parent in path.ancestors().skip(1) {
+        if parent == root || !parent.starts_with(root) || fs::remove_dir(parent).is_err() {
+            break;
+        }
+    }
+}
+
 mod tests;
@@ -1,38 +1,293 @@
 #[cfg(test)]
-use std::path::PathBuf;
+use crate::repository::BareRepository;
 
 #[cfg(test)]
-use crate::repository::BareRepository;
+use crate::{
+    commands::git_command, hooks::RemoveHooks, repository::Repository, test_helpers,
+    test_helpers::run_test, test_setup::BARE_REPO_NAME, utils::get_git_config_value,
+    worktree_path::DEFAULT_PATH_TEMPLATE,
+};
+
+#[cfg(test)]
+fn new_worktree(branch: &str) -> super::NewWorktree {
+    super::NewWorktree {
+        branch: branch.to_string(),
+        start_point: None,
+        path_template: DEFAULT_PATH_TEMPLATE.to_string(),
+    }
+}
 
 #[cfg(test)]
-use crate::test_setup::DEFAULT_BRANCH_NAME;
+fn bare(repo: &Repository) -> &BareRepository {
+    match repo {
+        Repository::Bare(bare) => bare,
+        _ => panic!("repo is not bare"),
+    }
+}
+
+#[test]
+fn test_worktree_create_places_new_branches_at_a_safe_path() {
+    run_test(
+        "test_worktree_create_places_new_branches_at_a_safe_path",
+        BARE_REPO_NAME,
+        |repo| {
+            let worktree = super::Worktree::create(bare(&repo), &new_worktree("feature/some-work"))
+                .expect("Couldn't create worktree");
+
+            assert_eq!(
+                repo.root().join("feature-some-work").to_str().unwrap(),
+                worktree.path
+            );
+            test_helpers::assert_worktree_exists(&repo, "feature/some-work".to_string());
+        },
+    );
+}
+
+#[test]
+fn test_worktree_create_uses_the_path_template() {
+    run_test(
+        "test_worktree_create_uses_the_path_template",
+        BARE_REPO_NAME,
+        |repo| {
+            let mut new = new_worktree("some-work");
+            new.path_template = "{root}/worktrees/{branch}".to_string();
+            let worktree =
+                super::Worktree::create(bare(&repo), &new).expect("Couldn't create worktree");
+
+            assert_eq!(
+                repo.root().join("worktrees/some-work").to_str().unwrap(),
+                worktree.path
+            );
+            assert!(repo.root().join("worktrees/some-work").exists());
+        },
+    );
+}
+
+#[test]
+fn test_worktree_create_tracks_a_remote_branch_with_the_same_name() {
+    run_test(
+        "test_worktree_create_tracks_a_remote_branch_with_the_same_name",
+        BARE_REPO_NAME,
+        |repo| {
+            git_command(
+                vec!["branch", "remote-only"],
+                &repo.root().join("../bare_repo_source"),
+            )
+            .expect("Couldn't create remote branch");
+
+            super::Worktree::create(bare(&repo), &new_worktree("remote-only"))
+                .expect("Couldn't create worktree");
+
+            assert_eq!(
+                Some("origin".to_string()),
+                get_git_config_value(repo.root(), "branch.remote-only.remote")
+            );
+            assert_eq!(
+                Some("refs/heads/remote-only".to_string()),
+                get_git_config_value(repo.root(), "branch.remote-only.merge")
+            );
+        },
+    );
+}
+
+#[test]
+fn test_worktree_create_does_not_track_new_branches() {
+    run_test(
+        "test_worktree_create_does_not_track_new_branches",
+        BARE_REPO_NAME,
+        |repo| {
+            super::Worktree::create(bare(&repo), &new_worktree("brand-new"))
+                .expect("Couldn't create worktree");
+
+            assert_eq!(
+                None,
+                get_git_config_value(repo.root(), "branch.brand-new.remote")
+            );
+        },
+    );
+}
+
+#[test]
+fn test_worktree_create_fails_for_a_branch_checked_out_elsewhere() {
+    run_test(
+        "test_worktree_create_fails_for_a_branch_checked_out_elsewhere",
+        BARE_REPO_NAME,
+        |repo| {
+            let mut new = new_worktree("merged");
+            new.path_template = "{root}/elsewhere/{branch}".to_string();
+
+            assert!(super::Worktree::create(bare(&repo), &new).is_err());
+        },
+    );
+}
+
+#[test]
+fn test_worktree_create_fails_for_an_invalid_branch_name() {
+    run_test(
+        "test_worktree_create_fails_for_an_invalid_branch_name",
+        BARE_REPO_NAME,
+        |repo| {
+            assert!(super::Worktree::create(bare(&repo), &new_worktree("not valid")).is_err());
+        },
+    );
+}
 
 #[cfg(test)]
-use crate::worktree::WorktreeListItem;
+fn find_worktree<'a>(repo: &'a Repository, branch: &str) -> super::Worktree<'a> {
+    bare(repo)
+        .all_worktrees()
+        .expect("Couldn't list worktrees")
+        .into_iter()
+        .find(|worktree| worktree.name == branch)
+        .unwrap_or_else(|| panic!("No worktree for '{}'", branch))
+}
 
 #[test]
-fn test_worktree_can_be_created_from_a_worktree_list_item() {
-    let repo = BareRepository::new(DEFAULT_BRANCH_NAME.to_string(), PathBuf::from("/a/repo"));
-    let item = WorktreeListItem::new(
-        &repo,
-        "/a/repo/origin/some-work     f9e08b4 [some-work]".to_string(),
+fn test_worktree_delete_runs_post_remove_hooks_when_the_branch_is_kept() {
+    run_test(
+        "test_worktree_delete_runs_post_remove_hooks_when_the_branch_is_kept",
+        BARE_REPO_NAME,
+        |repo| {
+            let hooks = RemoveHooks {
+                pre_remove: vec![],
+                post_remove: vec!["echo \"$GIT_TOOLS_BRANCH\" > post-remove.log".to_string()],
+            };
+
+            // `branch -d` refuses to delete a branch that isn't merged
+            let failures = find_worktree(&repo, "unmerged")
+                .delete(&hooks)
+                .expect("Couldn't remove the worktree");
+
+            assert_eq!(1, failures.len());
+            assert!(failures[0].starts_with("branch 'unmerged' couldn't be deleted"));
+            assert_eq!(
+                "unmerged\n",
+                std::fs::read_to_string(repo.root().join("post-remove.log"))
+                    .expect("The post-remove hook didn't run")
+            );
+            test_helpers::assert_worktree_does_not_exist(&repo, "unmerged".to_string());
+            test_helpers::assert_branch_exists(&repo, "unmerged".to_string());
+        },
     );
-    let worktree = super::Worktree::try_from(item).expect("Couldn't create a worktree");
+}
 
-    assert_eq!("/a/repo/origin/some-work", worktree.path);
-    assert_eq!("some-work", worktree.name);
+#[test]
+fn test_worktree_rename_moves_a_worktree_named_after_its_branch() {
+    run_test(
+        "test_worktree_rename_moves_a_worktree_named_after_its_branch",
+        BARE_REPO_NAME,
+        |repo| {
+            let renamed = find_worktree(&repo, "unmerged")
+                .rename("feature/renamed", DEFAULT_PATH_TEMPLATE)
+                .expect("Couldn't rename worktree");
+
+            assert_eq!(
+                repo.root().join("feature-renamed").to_str().unwrap(),
+                renamed.path
+            );
+            assert!(!repo.root().join("unmerged").exists());
+            test_helpers::assert_branch_does_not_exist(&repo, "unmerged".to_string());
+            test_helpers::assert_worktree_exists(&repo, "feature/renamed".to_string());
+        },
+    );
 }
 
 #[test]
-fn test_worktree_cannot_be_created_from_a_bare_worktree_list_item() {
-    let repo = BareRepository::new(DEFAULT_BRANCH_NAME.to_string(), PathBuf::from("/a/repo"));
-    let item = super::WorktreeListItem::new(&repo, "/a/repo  (bare)".to_string());
-    super::Worktree::try_from(item).expect_err("Shouldn't have created a worktree, but did");
+fn test_worktree_rename_keeps_paths_that_dont_match_the_branch() {
+    run_test(
+        "test_worktree_rename_keeps_paths_that_dont_match_the_branch",
+        BARE_REPO_NAME,
+        |repo| {
+            let worktree = find_worktree(&repo, "wont-match-path");
+            let renamed = worktree
+                .rename("renamed", DEFAULT_PATH_TEMPLATE)
+                .expect("Couldn't rename worktree");
+
+            assert_eq!(worktree.path, renamed.path);
+            assert_eq!("renamed", find_worktree(&repo, "renamed").name);
+        },
+    );
+}
+
+#[test]
+fn test_worktree_rename_points_a_same_named_upstream_at_the_new_name() {
+    run_test(
+        "test_worktree_rename_points_a_same_named_upstream_at_the_new_name",
+        BARE_REPO_NAME,
+        |repo| {
+            for (key, value) in [
+                ("branch.unmerged.remote", "origin"),
+                ("branch.unmerged.merge", "refs/heads/unmerged"),
+            ] {
+                git_command(vec!["config", key, value], repo.root())
+                    .expect("Couldn't set git config");
+            }
+
+            find_worktree(&repo, "unmerged")
+                .rename("renamed", DEFAULT_PATH_TEMPLATE)
+                .expect("Couldn't rename worktree");
+
+            assert_eq!(
+                Some("origin".to_string()),
+                get_git_config_value(repo.root(), "branch.renamed.remote")
+            );
+            assert_eq!(
+                Some("refs/heads/renamed".to_string()),
+                get_git_config_value(repo.root(), "branch.renamed.merge")
+            );
+            assert_eq!(
+                None,
+                get_git_config_value(repo.root(), "branch.unmerged.merge")
+            );
+        },
+    );
 }
 
 #[test]
-fn test_worktree_cannot_be_created_from_a_detached_worktree_list_item() {
-    let repo = BareRepository::new(DEFAULT_BRANCH_NAME.to_string(), PathBuf::from("/a/repo"));
-    let item = super::WorktreeListItem::new(&repo, "/a/repo  (detached HEAD)".to_string());
-    super::Worktree::try_from(item).expect_err("Shouldn't have created a worktree, but did");
+fn test_worktree_rename_renames_the_branch_back_when_the_move_fails() {
+    run_test(
+        "test_worktree_rename_renames_the_branch_back_when_the_move_fails",
+        BARE_REPO_NAME,
+        |repo| {
+            for args in [
+                vec!["config", "branch.unmerged.remote", "origin"],
+                vec!["config", "branch.unmerged.merge", "refs/heads/unmerged"],
+                // locked worktrees can't be moved
+                vec!["worktree", "lock", "unmerged"],
+            ] {
+                git_command(args, repo.root()).expect("Couldn't set up the worktree");
+            }
+
+            let msg = find_worktree(&repo, "unmerged")
+                .rename("feature/renamed", DEFAULT_PATH_TEMPLATE)
+                .expect_err("The rename should have failed");
+
+            assert!(msg.starts_with("Moving the worktree failed"), "{}", msg);
+            test_helpers::assert_worktree_exists(&repo, "unmerged".to_string());
+            test_helpers::assert_branch_does_not_exist(&repo, "feature/renamed".to_string());
+            assert_eq!(
+                Some("refs/heads/unmerged".to_string()),
+                get_git_config_value(repo.root(), "branch.unmerged.merge")
+            );
+            assert_eq!(
+                Some("origin".to_string()),
+                get_git_config_value(repo.root(), "branch.unmerged.remote")
+            );
+            assert!(!repo.root().join("feature-renamed").exists());
+        },
+    );
+}
+
+#[test]
+fn test_worktree_rename_fails_when_the_new_branch_exists() {
+    run_test(
+        "test_worktree_rename_fails_when_the_new_branch_exists",
+        BARE_REPO_NAME,
+        |repo| {
+            assert!(find_worktree(&repo, "unmerged")
+                .rename("merged", DEFAULT_PATH_TEMPLATE)
+                .is_err());
+            test_helpers::assert_worktree_exists(&repo, "unmerged".to_string());
+        },
+    );
 }
@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+
+#[cfg(test)]
+mod tests;
+
+// Placeholders:
+//   {root}        the bare repository root
+//   {repo}        the name of the bare repository root directory
+//   {branch}      the branch name as-is (slashes create nested directories)
+//   {branch_slug} the branch name turned into a single safe directory name
+pub const DEFAULT_PATH_TEMPLATE: &str = "{root}/{branch_slug}";
+
+// Only ASCII letters, digits, `.`, `_` and `-` are kept, every other run of characters (including
+// slashes) becomes a single `-`
+pub fn branch_slug(branch: &str) -> String {
+    let mut slug = String::new();
+
+    for c in branch.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    // leading dots would create hidden directories
+    let slug = slug.trim_matches(|c| c == '-' || c == '.');
+
+    match slug.is_empty() {
+        true => "worktree".to_string(),
+        false => slug.to_string(),
+    }
+}
+
+// The template from the configuration, e.g. `git config git-tools.worktreePathTemplate`
+pub fn configured_path_template(root: &Path) -> String {
+    Config::load(Some(root)).worktree_path_template.value
+}
+
+// Relative results are resolved against the repository root
+pub fn expand_path_template(template: &str, root: &Path, branch: &str) -> PathBuf {
+    let repo_name = root
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let expanded = template
+        .replace("{root}", &root.to_string_lossy())
+        .replace("{repo}", &repo_name)
+        .replace("{branch_slug}", &branch_slug(branch))
+        .replace("{branch}", branch);
+
+    root.join(expanded)
+}
+
+// Where a worktree should move to when its branch is renamed. Only worktrees whose directory
+// mirrors the old branch name (through the template, the name as-is or its slug) are moved.
+pub fn renamed_path(
+    path: &Path,
+    template: &str,
+    root: &Path,
+    old_branch: &str,
+    new_branch: &str,
+) -> Option<PathBuf> {
+    if path == expand_path_template(template, root, old_branch) {
+        return Some(expand_path_template(template, root, new_branch));
+    }
+
+    let old_components = Path::new(old_branch).components().count();
+    if path.ends_with(old_branch) {
+        let prefix = path.ancestors().nth(old_components)?;
+        return Some(prefix.join(new_branch));
+    }
+
+    if path.file_name()?.to_string_lossy() == branch_slug(old_branch) {
+        return Some(path.parent()?.join(branch_slug(new_branch)));
+    }
+
+    None
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+use std::path::PathBuf;
+
+#[cfg(test)]
+use super::{branch_slug, expand_path_template, renamed_path, DEFAULT_PATH_TEMPLATE};
+
+#[test]
+fn test_slug_keeps_simple_branch_names() {
+    assert_eq!("some-work_2.0", branch_slug("some-work_2.0"));
+}
+
+#[test]
+fn test_slug_replaces_slashes() {
+    assert_eq!("feature-some-work", branch_slug("feature/some-work"));
+}
+
+#[test]
+fn test_slug_collapses_runs_of_unsafe_characters() {
+    assert_eq!("fix-a-b", branch_slug("fix//a$$\\b"));
+}
+
+#[test]
+fn test_slug_does_not_start_with_a_dot_or_dash() {
+    assert_eq!("hidden", branch_slug(".hidden"));
+    assert_eq!("work", branch_slug("/work/"));
+}
+
+#[test]
+fn test_slug_replaces_non_ascii_characters() {
+    assert_eq!("caf-au-lait", branch_slug("café-au-lait"));
+}
+
+#[test]
+fn test_slug_is_never_empty() {
+    assert_eq!("worktree", branch_slug("///"));
+}
+
+#[test]
+fn test_default_template_places_the_worktree_in_the_root() {
+    let path = expand_path_template(
+        DEFAULT_PATH_TEMPLATE,
+        &PathBuf::from("/a/repo"),
+        "feature/some-work",
+    );
+
+    assert_eq!(PathBuf::from("/a/repo/feature-some-work"), path);
+}
+
+#[test]
+fn test_template_can_use_the_repo_name_and_raw_branch_name() {
+    let path = expand_path_template(
+        "/worktrees/{repo}/{branch}",
+        &PathBuf::from("/a/repo"),
+        "feature/some-work",
+    );
+
+    assert_eq!(PathBuf::from("/worktrees/repo/feature/some-work"), path);
+}
+
+#[test]
+fn test_relative_templates_are_resolved_against_the_root() {
+    let path = expand_path_template(
+        "worktrees/{branch_slug}",
+        &PathBuf::from("/a/repo"),
+        "some-work",
+    );
+
+    assert_eq!(PathBuf::from("/a/repo/worktrees/some-work"), path);
+}
+
+#[test]
+fn test_renamed_path_follows_the_template() {
+    let root = PathBuf::from("/repos/app");
+
+    assert_eq!(
+        Some(PathBuf::from("/repos/app/fix-login")),
+        renamed_path(
+            &root.join("feature-login"),
+            DEFAULT_PATH_TEMPLATE,
+            &root,
+            "feature/login",
+            "fix/login"
+        )
+    );
+}
+
+#[test]
+fn test_renamed_path_replaces_a_trailing_branch_name() {
+    let root = PathBuf::from("/repos/app");
+
+    assert_eq!(
+        Some(PathBuf::from("/repos/app/origin/new-work")),
+        renamed_path(
+            &root.join("origin/other-branch"),
+            DEFAULT_PATH_TEMPLATE,
+            &root,
+            "other-branch",
+            "new-work"
+        )
+    );
+}
+
+#[test]
+fn test_renamed_path_keeps_unrelated_directories() {
+    let root = PathBuf::from("/repos/app");
+
+    assert_eq!(
+        None,
+        renamed_path(
+            &root.join("origin/doesnt-match-name"),
+            DEFAULT_PATH_TEMPLATE,
+            &root,
+            "wont-match-path",
+            "new-work"
+        )
+    );
+}
@@ -0,0 +1,397 @@
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    commands::git_command,
+    json::Json,
+    logging::debug,
+    pool::{jobs, parallel_map},
+    repository::merged_branches,
+    table,
+};
+
+#[cfg(test)]
+mod tests;
+
+// One entry of `git worktree list --porcelain`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorktreeEntry {
+    pub path: String,
+    pub head: Option<String>,
+    pub branch: Option<String>,
+    pub is_bare: bool,
+    // Some("") when the worktree is locked or prunable without a reason
+    pub locked: Option<String>,
+    pub prunable: Option<String>,
+}
+
+// Every worktree of the repository at `repo_path`, including the bare repository itself
+pub fn worktree_entries(repo_path: &Path) -> Result<Vec<WorktreeEntry>, String> {
+    git_command(vec!["worktree", "list", "--porcelain"], repo_path)
+        .map(|result| parse_worktree_entries(&result.stdout_lines()))
+        .map_err(|result| result.error_message())
+}
+
+pub fn parse_worktree_entries(lines: &[String]) -> Vec<WorktreeEntry> {
+    let mut entries: Vec<WorktreeEntry> = vec![];
+
+    for line in lines {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+
+        if key == "worktree" {
+            entries.push(WorktreeEntry {
+                path: value.to_string(),
+                ..WorktreeEntry::default()
+            });
+            continue;
+        }
+
+        let entry = match entries.last_mut() {
+            Some(entry) => entry,
+            None => continue,
+        };
+
+        match key {
+            "HEAD" => entry.head = Some(value.to_string()),
+            "branch" => {
+                entry.branch = Some(
+                    value
+                        .strip_prefix("refs/heads/")
+                        .unwrap_or(value)
+                        .to_string(),
+                )
+            }
+            "bare" => entry.is_bare = true,
+            "locked" => entry.locked = Some(value.to_string()),
+            "prunable" => entry.prunable = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    entries
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Changes {
+    pub staged: usize,
+    pub modified: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+}
+
+impl Changes {
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+// What `git status --porcelain=v2 --branch` says about a worktree
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkingTreeState {
+    pub upstream: Option<String>,
+    // (ahead, behind), None when the upstream is gone
+    pub upstream_ahead_behind: Option<(usize, usize)>,
+    pub changes: Changes,
+}
+
+pub fn parse_status(lines: &[String]) -> WorkingTreeState {
+    let mut state = WorkingTreeState::default();
+
+    for line in lines {
+        if let Some(upstream) = line.strip_prefix("# branch.upstream ") {
+            state.upstream = Some(upstream.to_string());
+        } else if let Some(counts) = line.strip_prefix("# branch.ab ") {
+            state.upstream_ahead_behind = parse_ahead_behind(counts);
+        } else if line.starts_with("1 ") || line.starts_with("2 ") {
+            let mut xy = line[2..].chars();
+
+            if xy.next().is_some_and(|x| x != '.') {
+                state.changes.staged += 1;
+            }
+            if xy.next().is_some_and(|y| y != '.') {
+                state.changes.modified += 1;
+            }
+        } else if line.starts_with("u ") {
+            state.changes.conflicted += 1;
+        } else if line.starts_with("? ") {
+            state.changes.untracked += 1;
+        }
+    }
+
+    state
+}
+
+// Parses `+1 -2`
+fn parse_ahead_behind(counts: &str) -> Option<(usize, usize)> {
+    let (ahead, behind) = counts.split_once(' ')?;
+
+    Some((
+        ahead.strip_prefix('+')?.parse().ok()?,
+        behind.strip_prefix('-')?.parse().ok()?,
+    ))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorktreeStatus {
+    pub entry: WorktreeEntry,
+    pub is_main: bool,
+    pub merged: bool,
+    // (ahead, behind) compared to the main branch
+    pub main_ahead_behind: Option<(usize, usize)>,
+    // None when the worktree's directory is missing
+    pub state: Option<WorkingTreeState>,
+    // Unix timestamp of the commit the worktree is on
+    pub last_commit: Option<i64>,
+}
+
+pub fn worktree_statuses(
+    root: &Path,
+    main_branch_name: &String,
+) -> Result<Vec<WorktreeStatus>, String> {
+    let merged = merged_branches(main_branch_name, root)?;
+    let entries = worktree_entries(root)?
+        .into_iter()
+        .filter(|entry| !entry.is_bare)
+        .collect::<Vec<WorktreeEntry>>();
+
+    // every worktree takes a few git processes, so they're collected concurrently
+    Ok(parallel_map(entries, jobs(), |entry| {
+        debug!("collecting the status of {:?}", entry.path);
+
+        let is_main = entry.branch.as_ref() == Some(main_branch_name);
+        let merged = entry
+            .branch
+            .as_ref()
+            .is_some_and(|branch| merged.contains(branch));
+        let main_ahead_behind = entry
+            .head
+            .as_ref()
+            .and_then(|head| ahead_behind(root, main_branch_name, head));
+        let last_commit = entry
+            .head
+            .as_ref()
+            .and_then(|head| commit_timestamp(root, head));
+        let state = match Path::new(&entry.path).is_dir() {
+            true => git_command(
+                vec!["status", "--porcelain=v2", "--branch"],
+                Path::new(&entry.path),
+            )
+            .ok()
+            .map(|result| parse_status(&result.stdout_lines())),
+            false => None,
+        };
+
+        WorktreeStatus {
+            entry,
+            is_main,
+            merged,
+            main_ahead_behind,
+            state,
+            last_commit,
+        }
+    }))
+}
+
+pub fn ahead_behind(root: &Path, base: &str, head: &str) -> Option<(usize, usize)> {
+    let range = format!("{}...{}", base, head);
+    let counts = git_command(vec!["rev-list", "--left-right", "--count", &range], root)
+        .ok()?
+        .stdout_value()?;
+    let (behind, ahead) = counts.split_once('\t')?;
+
+    Some((ahead.parse().ok()?, behind.parse().ok()?))
+}
+
+fn commit_timestamp(root: &Path, commit: &str) -> Option<i64> {
+    git_command(vec!["show", "-s", "--format=%ct", commit], root)
+        .ok()?
+        .stdout_value()?
+        .parse()
+        .ok()
+}
+
+pub fn format_age(seconds: i64) -> String {
+    let plural = |count: i64, unit: &str| match count {
+        1 => format!("1 {} ago", unit),
+        _ => format!("{} {}s ago", count, unit),
+    };
+
+    match seconds {
+        s if s < 60 => "just now".to_string(),
+        s if s < 60 * 60 => plural(s / 60, "minute"),
+        s if s < 24 * 60 * 60 => plural(s / (60 * 60), "hour"),
+        s if s < 14 * 24 * 60 * 60 => plural(s / (24 * 60 * 60), "day"),
+        s if s < 60 * 24 * 60 * 60 => plural(s / (7 * 24 * 60 * 60), "week"),
+        s if s < 365 * 24 * 60 * 60 => plural(s / (30 * 24 * 60 * 60), "month"),
+        s => plural(s / (365 * 24 * 60 * 60), "year"),
+    }
+}
+
+pub fn format_ahead_behind(counts: Option<(usize, usize)>) -> String {
+    match counts {
+        Some((0, 0)) => "=".to_string(),
+        Some((ahead, behind)) => format!("+{} -{}", ahead, behind),
+        None => "-".to_string(),
+    }
+}
+
+fn format_changes(changes: &Changes) -> String {
+    if changes.is_clean() {
+        return "clean".to_string();
+    }
+
+    [
+        (changes.staged, "staged"),
+        (changes.modified, "modified"),
+        (changes.untracked, "untracked"),
+        (changes.conflicted, "conflicted"),
+    ]
+    .iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, label)| format!("{} {}", count, label))
+    .collect::<Vec<String>>()
+    .join(", ")
+}
+
+impl WorktreeStatus {
+    fn flags(&self) -> Vec<&'static str> {
+        [
+            (self.is_main, "main"),
+            (self.merged, "merged"),
+            (self.entry.branch.is_none(), "detached"),
+            (self.entry.locked.is_some(), "locked"),
+            (self.entry.prunable.is_some(), "prunable"),
+        ]
+        .into_iter()
+        .filter(|(applies, _)| *applies)
+        .map(|(_, flag)| flag)
+        .collect()
+    }
+
+    fn table_row(&self, root: &Path, now: i64) -> Vec<String> {
+        let path = Path::new(&self.entry.path);
+        let upstream = match &self.state {
+            Some(WorkingTreeState {
+                upstream: Some(upstream),
+                upstream_ahead_behind: Some(counts),
+                ..
+            }) => format!("{} {}", upstream, format_ahead_behind(Some(*counts))),
+            Some(WorkingTreeState {
+                upstream: Some(upstream),
+                upstream_ahead_behind: None,
+                ..
+            }) => format!("{} gone", upstream),
+            _ => "-".to_string(),
+        };
+
+        vec![
+            self.entry
+                .branch
+                .clone()
+                .unwrap_or_else(|| "(detached)".to_string()),
+            path.strip_prefix(root)
+                .ok()
+                .filter(|relative| !relative.as_os_str().is_empty())
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string(),
+            upstream,
+            format_ahead_behind(self.main_ahead_behind),
+            self.state.as_ref().map_or("missing".to_string(), |state| {
+                format_changes(&state.changes)
+            }),
+            self.last_commit
+                .map_or("-".to_string(), |time| format_age(now - time)),
+            self.flags().join(","),
+        ]
+    }
+
+    pub fn to_json(&self) -> Json {
+        let counts = |counts: Option<(usize, usize)>| match counts {
+            Some((ahead, behind)) => Json::object(vec![
+                ("ahead", Json::from(ahead)),
+                ("behind", Json::from(behind)),
+            ]),
+            None => Json::Null,
+        };
+        let state = self.state.as_ref();
+
+        Json::object(vec![
+            ("path", Json::from(self.entry.path.as_str())),
+            ("branch", Json::from(self.entry.branch.clone())),
+            ("head", Json::from(self.entry.head.clone())),
+            ("main", Json::from(self.is_main)),
+            ("merged", Json::from(self.merged)),
+            (
+                "upstream",
+                Json::from(state.and_then(|state| state.upstream.clone())),
+            ),
+            (
+                "upstream_ahead_behind",
+                counts(state.and_then(|state| state.upstream_ahead_behind)),
+            ),
+            ("main_ahead_behind", counts(self.main_ahead_behind)),
+            (
+                "changes",
+                match state {
+                    Some(state) => Json::object(vec![
+                        ("staged", Json::from(state.changes.staged)),
+                        ("modified", Json::from(state.changes.modified)),
+                        ("untracked", Json::from(state.changes.untracked)),
+                        ("conflicted", Json::from(state.changes.conflicted)),
+                    ]),
+                    None => Json::Null,
+                },
+            ),
+            ("last_commit", Json::from(self.last_commit)),
+            ("locked", Json::from(self.entry.locked.is_some())),
+            ("locked_reason", Json::from(non_empty(&self.entry.locked))),
+            ("prunable", Json::from(self.entry.prunable.is_some())),
+            (
+                "prunable_reason",
+                Json::from(non_empty(&self.entry.prunable)),
+            ),
+        ])
+    }
+}
+
+fn non_empty(reason: &Option<String>) -> Option<String> {
+    reason.clone().filter(|reason| !reason.is_empty())
+}
+
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+const TABLE_HEADERS: [&str; 7] = [
+    "BRANCH",
+    "PATH",
+    "UPSTREAM",
+    "MAIN",
+    "CHANGES",
+    "LAST COMMIT",
+    "FLAGS",
+];
+
+fn table_rows(statuses: &[WorktreeStatus], root: &Path, now: i64) -> Vec<Vec<String>> {
+    statuses
+        .iter()
+        .map(|status| status.table_row(root, now))
+        .collect()
+}
+
+pub fn render_table(statuses: &[WorktreeStatus], root: &Path, now: i64) -> String {
+    table::render(&TABLE_HEADERS, &table_rows(statuses, root, now))
+}
+
+pub fn render_csv(statuses: &[WorktreeStatus], root: &Path, now: i64) -> String {
+    table::render_csv(&TABLE_HEADERS, &table_rows(statuses, root, now))
+}
+
+pub fn render_json(statuses: &[WorktreeStatus]) -> String {
+    Json::Array(statuses.iter().map(WorktreeStatus::to_json).collect()).to_string()
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+use std::fs::File;
+
+#[cfg(test)]
+use super::{format_age, parse_status, parse_worktree_entries, worktree_statuses, Changes};
+
+#[cfg(test)]
+use crate::{repository::Repository, test_helpers::run_test, test_setup};
+
+#[cfg(test)]
+fn lines(output: &str) -> Vec<String> {
+    output.lines().map(String::from).collect()
+}
+
+#[test]
+fn test_porcelain_worktree_list_is_parsed() {
+    let entries = parse_worktree_entries(&lines(
+        "worktree /repo\nbare\n\n\
+         worktree /repo/main\nHEAD 1111\nbranch refs/heads/main\n\n\
+         worktree /repo/old\nHEAD 2222\ndetached\nlocked on a usb drive\nprunable gitdir file points to non-existent location\n",
+    ));
+
+    assert_eq!(3, entries.len());
+    assert!(entries[0].is_bare);
+    assert_eq!(Some("main".to_string()), entries[1].branch);
+    assert_eq!(Some("1111".to_string()), entries[1].head);
+    assert_eq!(None, entries[2].branch);
+    assert_eq!(Some("on a usb drive".to_string()), entries[2].locked);
+    assert!(entries[2].prunable.is_some());
+}
+
+#[test]
+fn test_porcelain_worktree_list_keeps_paths_and_branch_names_as_they_are() {
+    let entries = parse_worktree_entries(&lines(
+        "worktree /a repo/some work [x]\nHEAD 1111\nbranch refs/heads/feature/[x] y\\z\n\n\
+         worktree /a repo/(bare)\nHEAD 2222\nbranch refs/heads/(detached HEAD)\n",
+    ));
+
+    assert_eq!(2, entries.len());
+    assert_eq!("/a repo/some work [x]", entries[0].path);
+    assert_eq!(Some("feature/[x] y\\z".to_string()), entries[0].branch);
+    assert!(!entries[1].is_bare);
+    assert_eq!(Some("(detached HEAD)".to_string()), entries[1].branch);
+}
+
+#[test]
+fn test_porcelain_status_is_counted() {
+    let state = parse_status(&lines(
+        "# branch.oid 1111\n# branch.head work\n# branch.upstream origin/work\n# branch.ab +2 -3\n\
+         1 M. N... 100644 100644 100644 a a file\n\
+         1 MM N... 100644 100644 100644 a a other\n\
+         2 R. N... 100644 100644 100644 a a R100 new\told\n\
+         1 .D N... 100644 100644 000000 a a gone\n\
+         u UU N... 100644 100644 100644 100644 a a a conflict\n\
+         ? untracked\n? untracked-too\n",
+    ));
+
+    assert_eq!(Some("origin/work".to_string()), state.upstream);
+    assert_eq!(Some((2, 3)), state.upstream_ahead_behind);
+    assert_eq!(
+        Changes {
+            staged: 3,
+            modified: 2,
+            untracked: 2,
+            conflicted: 1,
+        },
+        state.changes
+    );
+}
+
+#[test]
+fn test_ages_use_the_largest_fitting_unit() {
+    assert_eq!("just now", format_age(5));
+    assert_eq!("1 minute ago", format_age(90));
+    assert_eq!("3 hours ago", format_age(3 * 60 * 60 + 5));
+    assert_eq!("2 weeks ago", format_age(15 * 24 * 60 * 60));
+    assert_eq!("2 years ago", format_age(800 * 24 * 60 * 60));
+}
+
+#[test]
+fn test_worktree_statuses_of_a_bare_repository() {
+    run_test(
+        "test_worktree_statuses_of_a_bare_repository",
+        test_setup::BARE_REPO_NAME,
+        |repo| {
+            let bare = match &repo {
+                Repository::Bare(bare) => bare,
+                Repository::Normal(_) => panic!("Expected a bare repository"),
+            };
+            let dirty = bare
+                .all_worktrees()
+                .expect("Couldn't list worktrees")
+                .into_iter()
+                .find(|worktree| worktree.name == "dirty")
+                .expect("No dirty worktree");
+            File::create(std::path::Path::new(&dirty.path).join("another-file"))
+                .expect("Couldn't create file");
+
+            let statuses = worktree_statuses(repo.root(), repo.main_branch_name())
+                .expect("Couldn't collect worktree statuses");
+            let status_of = |branch: &str| {
+                statuses
+                    .iter()
+                    .find(|status| status.entry.branch.as_deref() == Some(branch))
+                    .unwrap_or_else(|| panic!("No status for {}", branch))
+            };
+
+            assert!(statuses.iter().all(|status| !status.entry.is_bare));
+            assert!(status_of(test_setup::DEFAULT_BRANCH_NAME).is_main);
+            assert!(status_of("merged").merged);
+            assert!(!status_of("unmerged").merged);
+            assert_eq!(
+                Some(1),
+                status_of("unmerged")
+                    .main_ahead_behind
+                    .map(|(ahead, _)| ahead)
+            );
+            assert!(status_of("merged")
+                .state
+                .as_ref()
+                .unwrap()
+                .changes
+                .is_clean());
+            assert!(status_of("dirty").state.as_ref().unwrap().changes.untracked > 0);
+            assert!(status_of("merged").last_commit.is_some());
+        },
+    );
+}